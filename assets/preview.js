@@ -0,0 +1,65 @@
+// Long-pressing anything with data-preview on a touch screen sends it a `longpress` event,
+// which htmx previews it on, and sends `previewend` once the finger lifts so the preview
+// can be cleared. The click that would follow a long-press is swallowed so that
+// previewing a section doesn't also pick it.
+(function() {
+    var LONG_PRESS_MS = 400;
+    var timer = null;
+    var pressed = null;
+    var swallowClick = false;
+    var startX = 0;
+    var startY = 0;
+
+    function cancel() {
+        clearTimeout(timer);
+        timer = null;
+    }
+
+    document.addEventListener('pointerdown', function(evt) {
+        if (evt.pointerType !== 'touch') return;
+        var target = evt.target.closest('[data-preview]');
+        if (!target) return;
+        cancel();
+        startX = evt.clientX;
+        startY = evt.clientY;
+        timer = setTimeout(function() {
+            timer = null;
+            pressed = target;
+            swallowClick = true;
+            htmx.trigger(target, 'longpress');
+        }, LONG_PRESS_MS);
+    });
+
+    // scrolling the list isn't a long-press
+    document.addEventListener('pointercancel', cancel);
+    document.addEventListener('pointermove', function(evt) {
+        if (evt.pointerType !== 'touch' || !timer) return;
+        if (Math.abs(evt.clientX - startX) > 10 || Math.abs(evt.clientY - startY) > 10) {
+            cancel();
+        }
+    });
+
+    document.addEventListener('pointerup', function(evt) {
+        if (evt.pointerType !== 'touch') return;
+        cancel();
+        if (pressed) {
+            htmx.trigger(pressed, 'previewend');
+            pressed = null;
+            // not every browser clicks after a long-press, so don't wait forever for it
+            setTimeout(function() { swallowClick = false; }, 500);
+        }
+    });
+
+    document.addEventListener('click', function(evt) {
+        if (swallowClick) {
+            swallowClick = false;
+            evt.preventDefault();
+            evt.stopPropagation();
+        }
+    }, true);
+
+    // the browser's own long-press menu would cover the calendar
+    document.addEventListener('contextmenu', function(evt) {
+        if (evt.target.closest('[data-preview]')) evt.preventDefault();
+    });
+})();
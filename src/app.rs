@@ -1,5 +1,5 @@
 use std::{
-    env::{self, current_dir},
+    env::current_dir,
     sync::Arc,
 };
 
@@ -23,11 +23,19 @@ use tracing::{debug, debug_span, info};
 
 use crate::{
     common::{AppError, Stage},
-    data::{store::DynamoUserStore, DatabaseAppState},
-    routes::{calendar, donate, generate, import, login, preview, root, schedule, search, share},
+    data::{
+        auth::AuthConfig, cookies::CookieKeys, notify::Notifier, store::DynamoUserStore,
+        DatabaseAppState,
+    },
+    middlewares::signed_cookies,
+    routes::{
+        calendar, changes, compare, donate, export, friends, generate, history, import, login,
+        preview, register, root, schedule, search, settings, share, trash,
+    },
 };
 
-pub async fn make_app(stage: Stage, use_local_dynamo: bool) -> Router {
+/// Connects to DynamoDB, creating any missing tables
+pub async fn make_user_store(stage: &Stage, use_local_dynamo: bool) -> DynamoUserStore {
     let region = RegionProviderChain::default_provider().or_else("us-east-1");
     let ddb_config = match use_local_dynamo {
         false => {
@@ -53,6 +61,10 @@ pub async fn make_app(stage: Stage, use_local_dynamo: bool) -> Router {
         Stage::PROD => "sessions".to_string(),
         Stage::LOCAL => "sessions-dev".to_string(),
     };
+    let users_table_name = match stage {
+        Stage::PROD => "users".to_string(),
+        Stage::LOCAL => "users-dev".to_string(),
+    };
 
     let ddb_client = aws_sdk_dynamodb::Client::new(&ddb_config);
 
@@ -77,6 +89,11 @@ pub async fn make_app(stage: Stage, use_local_dynamo: bool) -> Router {
         .await
         .map_err(|_e| panic!());
     }
+    if !table_list.table_names().contains(&users_table_name) {
+        let _ = DatabaseAppState::create_table(&ddb_client, &users_table_name, "userId", "itemId")
+            .await
+            .map_err(|_e| panic!());
+    }
     // will set TTL on the table even if it already existed for backwards-compat reasons
     let _ = ddb_client
         .update_time_to_live()
@@ -90,19 +107,45 @@ pub async fn make_app(stage: Stage, use_local_dynamo: bool) -> Router {
         )
         .send()
         .await;
+    // only trashed schedules have purgeAt, so this just empties the trash
+    let _ = ddb_client
+        .update_time_to_live()
+        .table_name(&schedules_table_name)
+        .time_to_live_specification(
+            TimeToLiveSpecification::builder()
+                .attribute_name("purgeAt")
+                .enabled(true)
+                .build()
+                .unwrap(),
+        )
+        .send()
+        .await;
 
-    let user_store = DynamoUserStore::new(ddb_client, &sessions_table_name, &schedules_table_name);
+    DynamoUserStore::new(
+        ddb_client,
+        &sessions_table_name,
+        &schedules_table_name,
+        &users_table_name,
+    )
+}
+
+pub async fn make_app(stage: Stage, use_local_dynamo: bool) -> Router {
+    let user_store = make_user_store(&stage, use_local_dynamo).await;
 
     type State = Arc<DatabaseAppState>;
 
-    let discord_secret = env::var("DISCORD_SECRET").unwrap_or("".to_string());
+    let auth = AuthConfig::from_env(&stage);
+    let cookie_keys = CookieKeys::from_env(&stage).expect("invalid cookie keys");
+    let notifier = Notifier::from_env().expect("invalid notification settings");
 
     let state: State = Arc::new(
         DatabaseAppState::new(
             current_dir().expect("couldn't access current directory"),
             stage,
             user_store,
-            &discord_secret,
+            auth,
+            cookie_keys,
+            notifier,
         )
         .await
         .expect("failed to initialize database state"),
@@ -117,19 +160,63 @@ pub async fn make_app(stage: Stage, use_local_dynamo: bool) -> Router {
             Router::new()
                 .route("/", get(login::get))
                 .route("/google", post(login::post_google))
-                .route("/discord", get(login::get_discord)),
+                .route("/discord", get(login::get_discord))
+                .route("/oidc/start", get(login::get_oidc_start))
+                .route("/oidc", get(login::get_oidc))
+                .route("/link", get(login::get_link))
+                .route("/link/confirm", post(login::post_link_confirm))
+                .route("/import", get(login::get_import).post(login::post_import)),
         )
+        .route("/logout", post(login::logout))
         .route("/share/:schedule_id", get(share::get))
         .route("/import", get(import::get))
+        .route("/import/preview", get(import::preview))
+        .route("/import/image.png", get(export::shared_png))
         .route("/donate", get(donate::get))
+        .route("/settings", get(settings::get))
+        .route("/settings/notifications", post(settings::post_notifications))
+        .route("/settings/email/confirm", get(settings::confirm_email))
+        .route("/settings/sessions", delete(settings::revoke_all_sessions))
+        .route("/settings/sessions/:session_id", delete(settings::revoke_session))
+        .route("/friends", get(friends::get))
+        .route("/friends/invite", post(friends::new_invite))
+        .route(
+            "/friends/invite/:token",
+            get(friends::invite).post(friends::accept),
+        )
+        .route("/friends/share", post(friends::share))
+        .route("/friends/free/:term", get(friends::free_time))
+        .route("/friends/:friend_id", delete(friends::remove))
+        .route("/trash", get(trash::get))
+        .route("/trash/:schedule_id/restore", post(trash::restore))
+        .route("/settings/export", get(settings::export))
+        .route("/settings/account", delete(settings::delete_account))
+        .route("/compare", get(compare::get))
+        .route("/changes", get(changes::index))
+        .route("/changes/:term", get(changes::get))
         .route("/schedule", post(schedule::post))
         .nest(
             "/schedule/:schedule_id",
             Router::new()
                 .route("/", get(schedule::get))
                 .route("/", delete(schedule::delete))
+                .route("/edit", get(schedule::edit))
+                .route("/rename", post(schedule::rename))
+                .route("/duplicate", post(schedule::duplicate))
+                .route("/copy", post(schedule::copy))
                 .route("/search", post(search::search))
                 .route("/generate", get(generate::get).post(generate::post))
+                .route("/print", get(export::print))
+                .route("/register", get(register::get))
+                .route("/pdf", get(export::pdf))
+                .route("/image.svg", get(export::svg))
+                .route("/image.png", get(export::png))
+                .route("/changes/dismiss", post(schedule::dismiss_changes))
+                .route("/conflicts", post(schedule::block_conflicts))
+                .route("/undo", post(history::undo))
+                .route("/redo", post(history::redo))
+                .route("/history", get(history::get))
+                .route("/history/:index", post(history::restore))
                 .nest(
                     "/calendar",
                     Router::new()
@@ -137,13 +224,15 @@ pub async fn make_app(stage: Stage, use_local_dynamo: bool) -> Router {
                         .route("/", put(calendar::add_to_calendar))
                         .route("/", patch(calendar::update_calendar))
                         .route("/", delete(calendar::rm_from_calendar))
-                        .route("/preview", get(preview::preview)),
+                        .route("/preview", get(preview::preview))
+                        .route("/colour", post(calendar::colour_course)),
                 )
                 .layer(middleware::from_fn_with_state(
                     state.clone(),
                     schedule::not_found,
                 )),
         )
+        .layer(middleware::from_fn_with_state(state.clone(), signed_cookies))
         .with_state(state)
         .layer(middleware::from_fn(unauth_redirect))
         .layer(middleware::from_fn(request_logger))
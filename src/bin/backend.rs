@@ -2,9 +2,14 @@ use std::{env, net::SocketAddr, path::PathBuf, str::FromStr, time::Duration};
 
 use anyhow::Context;
 use axum_server::{tls_rustls::RustlsConfig, Handle};
-use scheduler::{app, common::Stage, scraper::{self, Term}};
+use scheduler::{
+    app,
+    common::Stage,
+    data::notify::{self, Notifier},
+    scraper::{self, ScrapeOptions, Term},
+};
 use tokio::{task, time};
-use tracing::debug;
+use tracing::{debug, error};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -79,7 +84,7 @@ async fn main() -> anyhow::Result<()> {
                         .context("error while serving HTTPS app").unwrap();
                 });
                 interval.tick().await;
-                let _r = scraper::scrape(true, Some(Term::from_str("202409").unwrap())).await;
+                rescrape(&stage, use_local_dynamo).await;
                 app = app::make_app(stage.clone(), use_local_dynamo).await;
                 handle.shutdown();
             }
@@ -100,7 +105,7 @@ async fn main() -> anyhow::Result<()> {
                 });
                 interval.tick().await;
                 debug!("running scraper");
-                let _r = scraper::scrape(true, Some(Term::from_str("202409").unwrap())).await;
+                rescrape(&stage, use_local_dynamo).await;
                 debug!("done scraping");
                 app = app::make_app(stage.clone(), use_local_dynamo).await;
                 handle.shutdown();
@@ -108,3 +113,27 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 }
+
+async fn rescrape(stage: &Stage, use_local_dynamo: bool) {
+    let oldest = Term::from_str("202409").unwrap();
+    let report = match scraper::scrape(true, Some(oldest), &ScrapeOptions::default()).await {
+        Ok(report) => report,
+        Err(e) => {
+            error!("scrape failed before any term was fetched: {e:#}");
+            return;
+        }
+    };
+    report.log();
+
+    let notifier = match Notifier::from_env() {
+        Ok(notifier) => notifier,
+        Err(e) => {
+            error!("could not configure notifications: {e:#}");
+            return;
+        }
+    };
+    let user_store = app::make_user_store(stage, use_local_dynamo).await;
+    if let Err(e) = notify::notify_changes(&user_store, &notifier, &report, stage).await {
+        error!("could not notify users of section changes: {e:#}");
+    }
+}
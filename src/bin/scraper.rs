@@ -1,6 +1,9 @@
+use std::{path::PathBuf, process::ExitCode, time::Duration};
+
 use anyhow::Result;
 use clap::Parser;
-use scheduler::scraper::{scrape, Term};
+use rusqlite::{Connection, OpenFlags};
+use scheduler::scraper::{diff, scrape, ScrapeOptions, Term};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(Parser)]
@@ -22,10 +25,34 @@ struct Args {
     /// positional TERM argument if present
     #[arg(long, short, value_name = "TERM")]
     oldest: Option<Term>,
+
+    /// Maximum number of concurrent requests per term
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+
+    /// Number of times to retry a request that failed with a transient error
+    #[arg(long, default_value_t = 5)]
+    retries: u32,
+
+    /// Minimum number of milliseconds between requests to the same host
+    #[arg(long, value_name = "MS", default_value_t = 250)]
+    interval: u64,
+
+    /// Print every change found while scraping, not just how many there were
+    #[arg(long, default_value_t = false)]
+    diff: bool,
+
+    /// Print the changelog stored in TERM's database instead of scraping
+    #[arg(long, value_name = "TERM", conflicts_with_all = ["force", "oldest"])]
+    changelog: Option<Term>,
+
+    /// Write a JSON report of the run to this file
+    #[arg(long, value_name = "FILE")]
+    report: Option<PathBuf>,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
@@ -35,5 +62,40 @@ async fn main() -> Result<()> {
         .init();
 
     let args = Args::parse();
-    scrape(args.force, args.oldest).await
+
+    if let Some(term) = args.changelog {
+        let conn = Connection::open_with_flags(
+            format!("sections_{}.sqlite3", term),
+            OpenFlags::SQLITE_OPEN_READ_ONLY,
+        )?;
+        for entry in diff::read_changelog(&conn, u32::MAX)? {
+            println!("{}: {}", entry.scraped_at, entry.change);
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let options = ScrapeOptions {
+        concurrency: args.concurrency,
+        max_retries: args.retries,
+        min_request_interval: Duration::from_millis(args.interval),
+        ..Default::default()
+    };
+
+    let report = scrape(args.force, args.oldest, &options).await?;
+    print!("{report}");
+    if args.diff {
+        for (term, changes) in report.changes() {
+            for change in changes {
+                println!("{term}: {change}");
+            }
+        }
+    }
+    if let Some(path) = args.report {
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+    }
+
+    Ok(match report.is_success() {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    })
 }
@@ -0,0 +1,202 @@
+//! Where classes are held, and how long it takes to walk between them
+
+use itertools::Itertools;
+use jiff::civil::Time;
+
+use crate::scraper::{Day, MeetingTime, Section};
+
+pub struct Building {
+    pub code: &'static str,
+    pub name: &'static str,
+    /// Roughly the middle of the building
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+const fn building(
+    code: &'static str,
+    name: &'static str,
+    latitude: f64,
+    longitude: f64,
+) -> Building {
+    Building {
+        code,
+        name,
+        latitude,
+        longitude,
+    }
+}
+
+/// Buildings classes are held in, by the code Banner gives them
+const BUILDINGS: [Building; 15] = [
+    building("BEC", "Bob Wright Centre", 48.4620, -123.3091),
+    building("CARSA", "CARSA", 48.4667, -123.3146),
+    building("CLE", "Clearihue Building", 48.4627, -123.3137),
+    building("COR", "Cornett Building", 48.4640, -123.3140),
+    building("DTB", "David Turpin Building", 48.4623, -123.3123),
+    building(
+        "ECS",
+        "Engineering & Computer Science Building",
+        48.4611,
+        -123.3106,
+    ),
+    building("ELL", "Elliott Building", 48.4625, -123.3103),
+    building("ELW", "Engineering Lab Wing", 48.4614, -123.3100),
+    building("EOW", "Engineering Office Wing", 48.4617, -123.3107),
+    building("FIA", "Fine Arts Building", 48.4625, -123.3163),
+    building("HHB", "Hickman Building", 48.4640, -123.3128),
+    building(
+        "HSD",
+        "Human & Social Development Building",
+        48.4644,
+        -123.3117,
+    ),
+    building("MAC", "MacLaurin Building", 48.4622, -123.3150),
+    building("MCK", "McKinnon Building", 48.4659, -123.3146),
+    building("SUB", "Student Union Building", 48.4650, -123.3084),
+];
+
+/// Where a meeting is held, as Banner writes it, e.g. "ECS 125"
+pub fn location(mt: &MeetingTime) -> Option<String> {
+    match (&mt.building, &mt.room) {
+        (Some(building), Some(room)) => Some(format!("{building} {room}")),
+        (Some(building), None) => Some(building.clone()),
+        (None, _) => None,
+    }
+}
+
+/// The building Banner calls `code`. Without signing in to Banner, buildings come back
+/// anonymized, and those aren't found.
+pub fn find(code: &str) -> Option<&'static Building> {
+    BUILDINGS.iter().find(|b| b.code.eq_ignore_ascii_case(code))
+}
+
+impl Building {
+    pub fn map_link(&self) -> String {
+        format!(
+            "https://www.openstreetmap.org/?mlat={lat}&mlon={lon}#map=18/{lat}/{lon}",
+            lat = self.latitude,
+            lon = self.longitude
+        )
+    }
+
+    /// How far apart two buildings are in a straight line, in metres
+    fn distance(&self, other: &Building) -> f64 {
+        const EARTH_RADIUS: f64 = 6_371_000.0;
+        let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let dlat = lat2 - lat1;
+        let dlon = (other.longitude - self.longitude).to_radians();
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().asin()
+    }
+
+    /// Minutes to walk to `other`, allowing for paths not going straight there and for
+    /// getting in and out of the buildings
+    pub fn walking_minutes(&self, other: &Building) -> i64 {
+        const METRES_PER_MINUTE: f64 = 70.0;
+        const DETOUR: f64 = 1.4;
+        if self.code == other.code {
+            return 0;
+        }
+        (self.distance(other) * DETOUR / METRES_PER_MINUTE).ceil() as i64 + 2
+    }
+}
+
+/// One class right after another, with less time between them than it takes to walk
+pub struct Walk<'a> {
+    pub from: &'a Section,
+    pub to: &'a Section,
+    pub day: Day,
+    pub gap_minutes: i64,
+    pub walking_minutes: i64,
+}
+
+/// Back-to-back classes in `selected` that are too far apart to walk between in time
+pub fn tight_walks(selected: &[Section]) -> Vec<Walk<'_>> {
+    let meetings = selected
+        .iter()
+        .flat_map(|s| s.meeting_times.iter().map(move |mt| (s, mt)))
+        .filter_map(|(s, mt)| Some((s, mt, mt.start_time?, mt.end_time?)))
+        .collect_vec();
+    Day::ALL
+        .iter()
+        .flat_map(|&day| {
+            meetings
+                .iter()
+                .filter(|(_, mt, _, _)| day.is_in_days(mt.days))
+                .sorted_by_key(|(_, _, start, _)| *start)
+                .tuple_windows()
+                .filter_map(move |(&(from, from_mt, _, end), &(to, to_mt, start, _))| {
+                    if !same_weeks(from_mt, to_mt) {
+                        return None;
+                    }
+                    let gap_minutes = minutes_between(end, start)?;
+                    let walking_minutes = find(from_mt.building.as_deref()?)?
+                        .walking_minutes(find(to_mt.building.as_deref()?)?);
+                    (walking_minutes > gap_minutes).then_some(Walk {
+                        from,
+                        to,
+                        day,
+                        gap_minutes,
+                        walking_minutes,
+                    })
+                })
+        })
+        .collect()
+}
+
+fn same_weeks(a: &MeetingTime, b: &MeetingTime) -> bool {
+    a.start_date <= b.end_date && b.start_date <= a.end_date
+}
+
+/// Minutes from `end` to `start`, if `start` isn't before it
+fn minutes_between(end: Time, start: Time) -> Option<i64> {
+    let of_day = |t: Time| t.hour() as i64 * 60 + t.minute() as i64;
+    let minutes = of_day(start) - of_day(end);
+    (minutes >= 0).then_some(minutes)
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::{date, time};
+
+    use super::*;
+
+    fn section(crn: u64, building: &str, start: Time, end: Time) -> Section {
+        Section {
+            crn,
+            subject_code: "CSC".to_string(),
+            course_code: crn.to_string(),
+            sequence_code: "A01".to_string(),
+            enrollment: 0,
+            enrollment_capacity: 0,
+            waitlist: 0,
+            waitlist_capacity: 0,
+            meeting_times: vec![MeetingTime {
+                start_time: Some(start),
+                end_time: Some(end),
+                start_date: date(2026, 9, 8),
+                end_date: date(2026, 12, 4),
+                days: Day::Monday.into(),
+                building: Some(building.to_string()),
+                room: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn only_far_apart_back_to_back_classes_are_tight() {
+        let engineering = section(1, "ECS", time(10, 0, 0, 0), time(11, 20, 0, 0));
+        let next_door = section(2, "ELW", time(11, 30, 0, 0), time(12, 20, 0, 0));
+        let across_campus = section(3, "MCK", time(12, 30, 0, 0), time(13, 20, 0, 0));
+        let anonymized = section(4, "XXX", time(13, 30, 0, 0), time(14, 20, 0, 0));
+        let selected = [engineering, next_door, across_campus, anonymized];
+
+        let walks = tight_walks(&selected);
+        assert_eq!(
+            walks.iter().map(|w| (w.from.crn, w.to.crn)).collect_vec(),
+            [(2, 3)]
+        );
+        assert_eq!(walks[0].gap_minutes, 10);
+    }
+}
@@ -12,8 +12,14 @@ use serde::{Deserialize, Serialize};
 use tracing::{debug, error};
 
 use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+use jiff::{
+    civil::{Date, Time},
+    Timestamp, ToSpan,
+};
 
-use crate::scraper::{Course, Section, Term, ThinCourse, ThinSection};
+use crate::scraper::{
+    diff::ChangelogEntry, Course, Day, MeetingTime, Section, Term, ThinCourse, ThinSection,
+};
 
 pub enum AppError {
     Anyhow(anyhow::Error),
@@ -54,6 +60,16 @@ pub enum Stage {
     PROD,
 }
 
+impl Stage {
+    /// Where users reach this deployment, used for links that leave the browser
+    pub fn public_url(&self) -> &'static str {
+        match self {
+            Stage::LOCAL => "http://localhost:8443",
+            Stage::PROD => "https://scheduler.brennanmcmicking.net",
+        }
+    }
+}
+
 impl From<String> for Stage {
     fn from(value: String) -> Self {
         match value.as_str() {
@@ -84,11 +100,18 @@ impl From<String> for SectionType {
     }
 }
 
+/// Hues courses are coloured with, far enough apart to tell a handful of courses apart
+pub const COURSE_HUES: [u64; 10] = [210, 30, 130, 340, 270, 180, 55, 0, 300, 90];
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Selection {
     pub lecture: ThinSection,
     pub lab: Option<ThinSection>,
     pub tutorial: Option<ThinSection>,
+    /// The course's colour, picked when it was added or by the user. Courses added before
+    /// colours were kept get one when they're drawn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hue: Option<u64>,
 }
 
 impl Selection {
@@ -111,6 +134,7 @@ impl Default for Selection {
             lecture: ThinSection { crn: 0 },
             lab: None,
             tutorial: None,
+            hue: None,
         }
     }
 }
@@ -120,6 +144,12 @@ pub struct Schedule {
     pub name: String,
     pub term: Term,
     pub selected: SelectedCourses,
+    /// When the user last dismissed the section changes banner for this schedule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes_seen_at: Option<Timestamp>,
+    /// Stop sections that clash with the rest of the schedule from being picked
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub block_conflicts: bool,
 }
 
 impl Schedule {
@@ -163,6 +193,36 @@ impl SelectedCourses {
     pub fn crns(&self) -> Vec<u64> {
         self.courses.values().flat_map(|s| s.crns()).collect()
     }
+
+    /// The colour of every course: the one it was given, otherwise the first of
+    /// [`COURSE_HUES`] no other course has
+    pub fn hues(&self) -> BTreeMap<ThinCourse, u64> {
+        let mut taken: Vec<u64> = self.courses.values().filter_map(|s| s.hue).collect();
+        self.courses
+            .iter()
+            .map(|(course, selection)| {
+                let hue = selection.hue.unwrap_or_else(|| {
+                    let hue = Self::free_hue(&taken);
+                    taken.push(hue);
+                    hue
+                });
+                (course.clone(), hue)
+            })
+            .collect()
+    }
+
+    /// The colour a course added now would get
+    pub fn next_hue(&self) -> u64 {
+        Self::free_hue(&self.hues().into_values().collect::<Vec<_>>())
+    }
+
+    fn free_hue(taken: &[u64]) -> u64 {
+        // with more courses than colours, reuse whichever is used least
+        *COURSE_HUES
+            .iter()
+            .min_by_key(|hue| taken.iter().filter(|t| t == hue).count())
+            .expect("there are course hues")
+    }
 }
 
 impl From<Vec<Section>> for SelectedCourses {
@@ -213,6 +273,66 @@ impl ScheduleWithId {
     }
 }
 
+/// A deleted schedule, kept for [`TrashedSchedule::RETENTION_DAYS`] in case it's wanted back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedSchedule {
+    pub id: String,
+    pub schedule: Schedule,
+    pub deleted_at: Timestamp,
+}
+
+impl TrashedSchedule {
+    pub const RETENTION_DAYS: i64 = 30;
+
+    pub fn new(schedule: ScheduleWithId) -> TrashedSchedule {
+        TrashedSchedule {
+            id: schedule.id,
+            schedule: schedule.schedule,
+            deleted_at: Timestamp::now(),
+        }
+    }
+
+    /// When the schedule is gone for good
+    pub fn purge_at(&self) -> Timestamp {
+        self.deleted_at
+            .checked_add((Self::RETENTION_DAYS * 24).hours())
+            .expect("deletion time should be far from the end of time")
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.purge_at() <= Timestamp::now()
+    }
+
+    pub fn cookie_name(schedule_id: &str) -> String {
+        format!("trash_{}", schedule_id)
+    }
+
+    pub fn to_base64(&self) -> String {
+        let trashed_json = serde_json::to_string(&self).expect("failed to serialize to json");
+        STANDARD_NO_PAD.encode(trashed_json)
+    }
+
+    /// Where the trash of a user who isn't logged in is kept. The cookie outlives the
+    /// retention period, so readers skip expired ones instead of relying on the browser.
+    pub fn make_cookie(&self) -> Cookie<'static> {
+        Cookie::build((Self::cookie_name(&self.id), self.to_base64()))
+            .http_only(true)
+            .secure(true)
+            .path("/")
+            .permanent()
+            .build()
+    }
+}
+
+impl TryFrom<&str> for TrashedSchedule {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let trashed_json = STANDARD_NO_PAD.decode(value)?;
+        Ok(serde_json::from_slice(&trashed_json)?)
+    }
+}
+
 #[derive(Debug)]
 pub struct Schedules {
     pub schedules: Vec<ScheduleWithId>,
@@ -226,3 +346,510 @@ pub fn selected_sections(courses: &[Course], selected: &SelectedCourses) -> Vec<
         .filter(|s| crns.contains(&s.crn))
         .collect()
 }
+
+/// How a week of classes adds up, for comparing schedules
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct WeekSummary {
+    /// Time spent in class, not counting overlapping meetings twice
+    pub class_minutes: i64,
+    /// Days with at least one class
+    pub days: Vec<Day>,
+    /// Time between classes on the same day
+    pub gap_minutes: i64,
+}
+
+impl WeekSummary {
+    pub fn of(sections: &[Section]) -> WeekSummary {
+        let minutes = |from: Time, to: Time| to.duration_since(from).as_secs() / 60;
+        let mut summary = WeekSummary::default();
+        for day in Day::ALL {
+            let mut meetings: Vec<(Time, Time)> = sections
+                .iter()
+                .flat_map(|s| &s.meeting_times)
+                .filter(|mt| day.is_in_days(mt.days))
+                .filter_map(|mt| mt.start_time.zip(mt.end_time))
+                .collect();
+            meetings.sort();
+            let Some(&(mut start, mut end)) = meetings.first() else {
+                continue;
+            };
+            summary.days.push(day);
+            for &(next_start, next_end) in &meetings[1..] {
+                if next_start > end {
+                    summary.class_minutes += minutes(start, end);
+                    summary.gap_minutes += minutes(end, next_start);
+                    start = next_start;
+                }
+                end = end.max(next_end);
+            }
+            summary.class_minutes += minutes(start, end);
+        }
+        summary
+    }
+}
+
+/// A stretch of a weekday when nobody in a group of schedules has class
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreeTime {
+    pub day: Day,
+    pub start: Time,
+    pub end: Time,
+}
+
+impl FreeTime {
+    /// Free time is only looked for between these, since nobody wants to meet at midnight
+    pub const DAY_START: Time = jiff::civil::time(8, 30, 0, 0);
+    pub const DAY_END: Time = jiff::civil::time(18, 0, 0, 0);
+    /// Shorter gaps are just the walk between classes
+    pub const MIN_MINUTES: i64 = 30;
+
+    /// When everyone is free in the week starting `monday`, or any week if there is none,
+    /// given each person's sections. Weekend days only count once someone has class then.
+    pub fn common(schedules: &[Vec<Section>], monday: Option<Date>) -> Vec<FreeTime> {
+        let meetings: Vec<&MeetingTime> = schedules
+            .iter()
+            .flatten()
+            .flat_map(|s| &s.meeting_times)
+            .filter(|mt| monday.is_none_or(|monday| mt.is_held_in_week(monday)))
+            .collect();
+        let days = Day::ALL.into_iter().filter(|day| {
+            Day::WEEKDAYS.contains(day) || meetings.iter().any(|mt| day.is_in_days(mt.days))
+        });
+
+        let mut free = Vec::new();
+        for day in days {
+            let mut busy: Vec<(Time, Time)> = meetings
+                .iter()
+                .filter(|mt| day.is_in_days(mt.days))
+                .filter_map(|mt| mt.start_time.zip(mt.end_time))
+                .collect();
+            busy.sort();
+
+            // the end of the day is busy too, so the last gap is closed off
+            busy.push((Self::DAY_END, Self::DAY_END));
+
+            let mut start = Self::DAY_START;
+            for (busy_start, busy_end) in busy {
+                let end = busy_start.min(Self::DAY_END);
+                if end.duration_since(start).as_secs() / 60 >= Self::MIN_MINUTES {
+                    free.push(FreeTime { day, start, end });
+                }
+                start = start.max(busy_end);
+            }
+        }
+        free
+    }
+}
+
+/// Ways a saved schedule has drifted from what Banner currently offers
+#[derive(Debug, Default)]
+pub struct ScheduleChanges {
+    /// Selected CRNs that no longer exist in the term, with the course they were selected for
+    pub missing: Vec<(ThinCourse, u64)>,
+    /// Changes to selected sections since the user last dismissed them
+    pub changes: Vec<ChangelogEntry>,
+}
+
+impl ScheduleChanges {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.changes.is_empty()
+    }
+}
+
+/// One saved state of a schedule's selection
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Revision {
+    pub at: Timestamp,
+    pub description: String,
+    pub selected: SelectedCourses,
+}
+
+/// Every revision of a schedule's selection, oldest first
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    pub revisions: Vec<Revision>,
+    /// Index of the revision the schedule currently matches
+    pub current: usize,
+}
+
+impl History {
+    /// Most revisions kept for schedules belonging to an account
+    pub const MAX_REVISIONS: usize = 100;
+    /// Most revisions kept in a cookie, which is also bounded by [`Self::MAX_COOKIE_BYTES`]
+    pub const MAX_COOKIE_REVISIONS: usize = 10;
+    /// Leaves room for the cookie's name and attributes under browsers' 4KiB limit
+    pub const MAX_COOKIE_BYTES: usize = 3500;
+
+    pub fn cookie_name(schedule_id: &str) -> String {
+        format!("history_{}", schedule_id)
+    }
+
+    /// Records a change from `before` to `after`, discarding anything that could have been redone
+    pub fn record(&mut self, before: &SelectedCourses, after: &SelectedCourses, description: String) {
+        if self.revisions.is_empty() {
+            // schedules made before history existed start with their state at the first change
+            self.revisions.push(Revision {
+                at: Timestamp::now(),
+                description: "started tracking changes".to_string(),
+                selected: before.clone(),
+            });
+        }
+        self.revisions.truncate(self.current + 1);
+        self.revisions.push(Revision {
+            at: Timestamp::now(),
+            description,
+            selected: after.clone(),
+        });
+        self.current = self.revisions.len() - 1;
+        self.trim(Self::MAX_REVISIONS);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.current > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.current + 1 < self.revisions.len()
+    }
+
+    /// Steps back one revision, returning the selection to go back to
+    pub fn undo(&mut self) -> Option<SelectedCourses> {
+        if !self.can_undo() {
+            return None;
+        }
+        self.current -= 1;
+        Some(self.revisions[self.current].selected.clone())
+    }
+
+    /// Steps forward one revision, returning the selection to go forward to
+    pub fn redo(&mut self) -> Option<SelectedCourses> {
+        if !self.can_redo() {
+            return None;
+        }
+        self.current += 1;
+        Some(self.revisions[self.current].selected.clone())
+    }
+
+    /// Brings back an earlier revision as a new revision, so the restore itself can be undone
+    pub fn restore(&mut self, index: usize) -> Option<SelectedCourses> {
+        let revision = self.revisions.get(index)?.clone();
+        let current = self.revisions.get(self.current)?.selected.clone();
+        self.record(
+            &current,
+            &revision.selected,
+            format!("restored \"{}\"", revision.description),
+        );
+        Some(revision.selected)
+    }
+
+    /// Drops the oldest revisions (or, if they are still reachable by redo, the newest) down to `max`
+    fn trim(&mut self, max: usize) {
+        while self.revisions.len() > max {
+            if self.current > 0 {
+                self.revisions.remove(0);
+                self.current -= 1;
+            } else {
+                self.revisions.pop();
+            }
+        }
+    }
+
+    pub fn to_base64(&self) -> String {
+        let history_json = serde_json::to_string(&self).expect("failed to serialize to json");
+        STANDARD_NO_PAD.encode(history_json)
+    }
+
+    pub fn make_cookie(&self, schedule_id: &str) -> Cookie<'static> {
+        let mut history = self.clone();
+        history.trim(Self::MAX_COOKIE_REVISIONS);
+        let mut value = history.to_base64();
+        while value.len() > Self::MAX_COOKIE_BYTES {
+            match history.revisions.len() {
+                // not even one revision fits, so there is nothing to undo to
+                0 | 1 => history = History::default(),
+                n => history.trim(n - 1),
+            }
+            value = history.to_base64();
+        }
+        Cookie::build((Self::cookie_name(schedule_id), value))
+            .http_only(true)
+            .secure(true)
+            .path("/")
+            .permanent()
+            .build()
+    }
+}
+
+impl TryFrom<&str> for History {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let history_json = STANDARD_NO_PAD.decode(value)?;
+        Ok(serde_json::from_slice(&history_json)?)
+    }
+}
+
+const WEEK_MAX_AGE: time::Duration = time::Duration::days(1);
+
+/// How the user last left a schedule's calendar
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CalendarView {
+    /// The week it was moved to, if it was moved off the default one
+    pub week: Option<Date>,
+    /// Whether to always or never show the weekend, if the user chose, rather than only
+    /// when there are classes then. Applies to every schedule.
+    pub full_week: Option<bool>,
+}
+
+impl CalendarView {
+    pub const FULL_WEEK_COOKIE: &'static str = "full_week";
+
+    pub fn week_cookie_name(schedule_id: &str) -> String {
+        format!("week_{}", schedule_id)
+    }
+
+    /// Only lasts a day, since the week worth looking at moves on and each schedule gets its
+    /// own cookie
+    pub fn week_cookie(&self, schedule_id: &str) -> Cookie<'static> {
+        let name = Self::week_cookie_name(schedule_id);
+        match self.week {
+            Some(week) => Cookie::build((name, week.to_string()))
+                .http_only(true)
+                .secure(true)
+                .path("/")
+                .max_age(WEEK_MAX_AGE)
+                .build(),
+            None => Cookie::build((name, "")).path("/").removal().build(),
+        }
+    }
+
+    pub fn full_week_cookie(&self) -> Cookie<'static> {
+        let value = self.full_week.map(|f| f.to_string()).unwrap_or_default();
+        Cookie::build((Self::FULL_WEEK_COOKIE, value))
+            .http_only(true)
+            .secure(true)
+            .path("/")
+            .permanent()
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scraper::{Days, MeetingTime};
+
+    fn selection(crns: &[u64]) -> SelectedCourses {
+        SelectedCourses {
+            courses: crns
+                .iter()
+                .map(|&crn| {
+                    (
+                        ThinCourse {
+                            subject_code: "CSC".to_string(),
+                            course_code: crn.to_string(),
+                        },
+                        Selection {
+                            lecture: ThinSection { crn },
+                            lab: None,
+                            tutorial: None,
+                            hue: None,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn courses_keep_their_colour_and_others_avoid_it() {
+        let mut selected = selection(&[1, 2, 3]);
+        selected.courses.values_mut().nth(1).unwrap().hue = Some(COURSE_HUES[0]);
+        let hues = selected.hues().into_values().collect::<Vec<_>>();
+        assert_eq!(hues, [COURSE_HUES[1], COURSE_HUES[0], COURSE_HUES[2]]);
+        assert_eq!(selected.next_hue(), COURSE_HUES[3]);
+    }
+
+    #[test]
+    fn undo_redo_and_restore() {
+        let mut history = History::default();
+        history.record(&selection(&[]), &selection(&[1]), "added 1".to_string());
+        history.record(&selection(&[1]), &selection(&[1, 2]), "added 2".to_string());
+        assert_eq!(history.revisions.len(), 3);
+
+        assert_eq!(history.undo().unwrap().crns(), vec![1]);
+        assert_eq!(history.undo().unwrap().crns(), Vec::<u64>::new());
+        assert!(history.undo().is_none());
+        assert_eq!(history.redo().unwrap().crns(), vec![1]);
+
+        // a new change after undoing discards what could have been redone
+        history.record(&selection(&[1]), &selection(&[1, 3]), "added 3".to_string());
+        assert!(!history.can_redo());
+        assert_eq!(history.revisions.len(), 3);
+
+        assert_eq!(history.restore(0).unwrap().crns(), Vec::<u64>::new());
+        assert_eq!(history.current, 3);
+        assert_eq!(history.undo().unwrap().crns(), vec![1, 3]);
+    }
+
+    #[test]
+    fn cookie_history_is_bounded() {
+        let mut history = History::default();
+        // a busy term's worth of courses, swapped in and out
+        for i in 0..50u64 {
+            let crns: Vec<u64> = (i..i + 6).collect();
+            history.record(
+                &selection(&crns[1..]),
+                &selection(&crns),
+                format!("added {i}"),
+            );
+        }
+        assert_eq!(history.revisions.len(), 50 + 1);
+
+        let cookie = history.make_cookie("id");
+        assert!(cookie.value().len() <= History::MAX_COOKIE_BYTES);
+        let restored = History::try_from(cookie.value()).unwrap();
+        assert!(restored.revisions.len() <= History::MAX_COOKIE_REVISIONS);
+        assert_eq!(restored.revisions.last().unwrap().description, "added 49");
+        assert_eq!(restored.current, restored.revisions.len() - 1);
+
+        let mut huge = History::default();
+        let crns: Vec<u64> = (0..200).collect();
+        huge.record(&selection(&[]), &selection(&crns), "added 200".to_string());
+        let cookie = huge.make_cookie("id");
+        assert!(cookie.value().len() <= History::MAX_COOKIE_BYTES);
+    }
+
+    fn section(crn: u64, days: Days, start: (i8, i8), end: (i8, i8)) -> Section {
+        Section {
+            crn,
+            subject_code: "CSC".to_string(),
+            course_code: crn.to_string(),
+            sequence_code: "A01".to_string(),
+            enrollment: 0,
+            enrollment_capacity: 0,
+            waitlist: 0,
+            waitlist_capacity: 0,
+            meeting_times: vec![MeetingTime {
+                start_time: Some(jiff::civil::time(start.0, start.1, 0, 0)),
+                end_time: Some(jiff::civil::time(end.0, end.1, 0, 0)),
+                start_date: jiff::civil::date(2026, 9, 8),
+                end_date: jiff::civil::date(2026, 12, 4),
+                days,
+                building: None,
+                room: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn week_summary_merges_overlaps() {
+        let mwr = Days {
+            monday: true,
+            tuesday: false,
+            wednesday: true,
+            thursday: true,
+            friday: false,
+            saturday: false,
+            sunday: false,
+        };
+        let monday = Days {
+            wednesday: false,
+            thursday: false,
+            ..mwr
+        };
+        let summary = WeekSummary::of(&[
+            section(1, mwr, (8, 30), (9, 50)),
+            // overlaps the first on monday, then leaves a gap until the third
+            section(2, monday, (9, 30), (10, 20)),
+            section(3, monday, (13, 0), (14, 20)),
+        ]);
+        assert_eq!(
+            summary.days,
+            vec![Day::Monday, Day::Wednesday, Day::Thursday]
+        );
+        assert_eq!(summary.class_minutes, (110 + 80) + 80 + 80);
+        assert_eq!(summary.gap_minutes, 160);
+    }
+
+    #[test]
+    fn free_time_is_when_nobody_has_class() {
+        let monday = Days {
+            monday: true,
+            tuesday: false,
+            wednesday: false,
+            thursday: false,
+            friday: false,
+            saturday: false,
+            sunday: false,
+        };
+        let free = FreeTime::common(
+            &[
+                vec![section(1, monday, (8, 30), (9, 50))],
+                vec![
+                    section(2, monday, (9, 30), (10, 20)),
+                    // only leaves a 10 minute gap after the first
+                    section(3, monday, (10, 30), (11, 20)),
+                    section(4, monday, (13, 0), (17, 50)),
+                ],
+            ],
+            None,
+        );
+        let mondays: Vec<_> = free
+            .iter()
+            .filter(|f| f.day == Day::Monday)
+            .map(|f| (f.start, f.end))
+            .collect();
+        let lunch = (
+            jiff::civil::time(11, 20, 0, 0),
+            jiff::civil::time(13, 0, 0, 0),
+        );
+        assert_eq!(mondays, vec![lunch]);
+        assert!(free.contains(&FreeTime {
+            day: Day::Tuesday,
+            start: FreeTime::DAY_START,
+            end: FreeTime::DAY_END,
+        }));
+        assert!(!free.iter().any(|f| f.day == Day::Saturday));
+    }
+
+    #[test]
+    fn free_time_only_counts_classes_that_week() {
+        let days = |monday, saturday| Days {
+            monday,
+            tuesday: false,
+            wednesday: false,
+            thursday: false,
+            friday: false,
+            saturday,
+            sunday: false,
+        };
+        let mut first_half = section(1, days(true, false), (8, 30), (18, 0));
+        first_half.meeting_times[0].end_date = jiff::civil::date(2026, 10, 16);
+        let weekend = section(2, days(false, true), (10, 0), (12, 0));
+        let schedules = [vec![first_half], vec![weekend]];
+
+        let all_day = |day| FreeTime {
+            day,
+            start: FreeTime::DAY_START,
+            end: FreeTime::DAY_END,
+        };
+        let early = FreeTime::common(&schedules, Some(jiff::civil::date(2026, 9, 14)));
+        assert!(!early.iter().any(|f| f.day == Day::Monday));
+        let late = FreeTime::common(&schedules, Some(jiff::civil::date(2026, 11, 16)));
+        assert!(late.contains(&all_day(Day::Monday)));
+
+        let saturdays: Vec<_> = late
+            .iter()
+            .filter(|f| f.day == Day::Saturday)
+            .map(|f| (f.start, f.end))
+            .collect();
+        assert_eq!(
+            saturdays,
+            vec![
+                (FreeTime::DAY_START, jiff::civil::time(10, 0, 0, 0)),
+                (jiff::civil::time(12, 0, 0, 0), FreeTime::DAY_END),
+            ]
+        );
+    }
+}
@@ -7,8 +7,13 @@ use crate::{
 
 pub mod button;
 pub mod calendar;
+pub mod changes;
+pub mod compare;
 pub mod container;
 pub mod courses;
+pub mod history;
+pub mod print;
+pub mod register;
 pub mod schedules;
 pub mod search_result;
 
@@ -19,12 +24,20 @@ pub fn base(content: Markup, session: Option<Session>) -> Markup {
                 @match session.authority {
                     Authority::DISCORD => img src="/assets/discord-mark-white.svg" class="rounded p-1 lg:p-2 bg-[#5865F2]" {},
                     Authority::GOOGLE => img src="/assets/google-g-logo.svg" class="rounded-full lg:p-2 bg-white" {},
+                    Authority::OIDC => img src="/assets/key.svg" class="rounded p-1 lg:p-2 bg-slate-600" {},
                 }
-                div class="hidden lg:flex items-center" {
+                a href="/friends" class="hidden lg:flex items-center hover:underline" {
+                    "friends"
+                }
+                a href="/settings" class="hidden lg:flex items-center hover:underline" {
                     (session.username)
                 }
             }
-            (components::button::link("/login", html!("log out")))
+            form action="/logout" method="post" class="flex" {
+                button class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 rounded-lg transition px-1 lg:p-1" {
+                    "log out"
+                }
+            }
         ),
         None => html!(
             (components::button::link("/login", html!("log in")))
@@ -36,6 +49,7 @@ pub fn base(content: Markup, session: Option<Session>) -> Markup {
                 title {"scheduler"}
                 script src="/assets/htmx.min.js" {}
                 script src="/assets/multi-swap.js" {}
+                script src="/assets/preview.js" {}
                 script src="/assets/tailwind.js" {}
                 meta name="viewport" content="width=device-width,initial-scale=1.0" {}
             }
@@ -46,6 +60,7 @@ pub fn base(content: Markup, session: Option<Session>) -> Markup {
                             div class="w-1/3 flex justify-start gap-2 lg:py-2" {
                                 (components::button::link("/", html!("home")))
                                 (components::button::link("/donate", html!("donate")))
+                                (components::button::link("/changes", html!("changes")))
                             }
                             div class="w-1/3 flex justify-center items-center" {
                                 "uvic scheduler"
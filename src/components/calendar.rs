@@ -1,18 +1,67 @@
 use itertools::Itertools;
-use jiff::{civil::Time, ToSpan};
+use jiff::{
+    civil::{Date, Time},
+    ToSpan,
+};
 use maud::{html, Markup};
 
-use crate::scraper::{Day, MeetingTime, Section};
+use crate::{
+    campus,
+    common::{CalendarView, FreeTime, SelectedCourses},
+    conflict,
+    scraper::{Day, MeetingTime, Section},
+};
+
+/// Hues that tell schedules apart when they are drawn on the same calendar
+const SCHEDULE_HUES: [u64; 4] = [210, 30, 130, 300];
+
+pub fn schedule_hue(index: usize) -> u64 {
+    SCHEDULE_HUES[index % SCHEDULE_HUES.len()]
+}
 
 #[derive(Clone)]
 struct RenderableMeetingTime {
     mt: MeetingTime,
-    crn: u64,
     subject_code: String,
     course_code: String,
     sequence_code: String,
     preview: bool,
     full: bool,
+    hue: u64,
+}
+
+/// Colours sections by course, giving a course that isn't selected the colour it would get
+/// if it were added
+fn course_hue(selected: &SelectedCourses) -> impl Fn(&Section) -> u64 {
+    let hues = selected.hues();
+    let next = selected.next_hue();
+    move |s| hues.get(&s.thin_course()).copied().unwrap_or(next)
+}
+
+/// The meetings of `sections`, in the colour `hue` gives each section
+fn renderable(
+    sections: &[Section],
+    preview: bool,
+    hue: impl Fn(&Section) -> u64,
+) -> Vec<RenderableMeetingTime> {
+    sections
+        .iter()
+        .flat_map(|s| {
+            let hue = hue(s);
+            s.meeting_times
+                .clone()
+                .into_iter()
+                .map(move |mt| RenderableMeetingTime {
+                    mt,
+                    subject_code: s.subject_code.clone(),
+                    course_code: s.course_code.clone(),
+                    sequence_code: s.sequence_code.clone(),
+                    full: s.enrollment == s.enrollment_capacity || s.waitlist > 0,
+                    preview,
+                    hue,
+                })
+        })
+        .collect()
 }
 
 fn has_conflict(meeting: &RenderableMeetingTime, other: &RenderableMeetingTime) -> bool {
@@ -28,38 +77,92 @@ fn has_conflict(meeting: &RenderableMeetingTime, other: &RenderableMeetingTime)
     }
 }
 
-fn render_section_cards(
+/// Whether `preview` would be picked instead of `other`, being another section of the same
+/// type in the same course
+fn replaces(preview: &RenderableMeetingTime, other: &RenderableMeetingTime) -> bool {
+    preview.subject_code == other.subject_code
+        && preview.course_code == other.course_code
+        && preview.sequence_code.get(..1) == other.sequence_code.get(..1)
+}
+
+/// Whether a previewed meeting overlaps the schedule's own, ignoring the section it would
+/// replace
+fn clashes(meeting: &RenderableMeetingTime, same_day: &[&RenderableMeetingTime]) -> bool {
+    meeting.preview
+        && same_day.iter().any(|other| {
+            !other.preview
+                && !replaces(meeting, other)
+                && conflict::MeetingTime::new(&meeting.mt) == conflict::MeetingTime::new(&other.mt)
+        })
+}
+
+/// Where a meeting's card goes in its day, as percentages of the day's height and width
+/// to leave above, below, left and right of it. `earliest` and `latest` are the first and
+/// last timeslots, and meetings overlapping it share the width in order.
+fn card_position(
     earliest: &Time,
     latest: &Time,
-    renderable_meeting: &RenderableMeetingTime,
+    meeting: &RenderableMeetingTime,
     conflicts_before: usize,
     conflicts_after: usize,
-) -> Markup {
+) -> Option<(f32, f32, f32, f32)> {
+    let (st, et) = meeting.mt.start_time.zip(meeting.mt.end_time)?;
     let earliest = earliest.hour() as f32 + earliest.minute() as f32 / 60.0;
     let latest = latest.hour() as f32 + latest.minute() as f32 / 60.0 + 0.5;
     let num_overlapping: f32 = conflicts_before as f32 + 1.0 + conflicts_after as f32;
+    let st = st.hour() as f32 + st.minute() as f32 / 60.0;
+    let et = et.hour() as f32 + et.minute() as f32 / 60.0;
+    Some((
+        (st - earliest) / (latest - earliest) * 100.0,
+        (latest - et) / (latest - earliest) * 100.0,
+        (conflicts_before as f32 / num_overlapping) * 100.0,
+        (conflicts_after as f32 / num_overlapping) * 100.0,
+    ))
+}
+
+/// How many of the meetings before and after each of `meetings` it overlaps
+fn overlaps<'a>(
+    meetings: &'a [&'a RenderableMeetingTime],
+) -> impl Iterator<Item = (&'a RenderableMeetingTime, usize, usize)> {
+    meetings.iter().enumerate().map(|(pos, meeting)| {
+        let before = meetings[..pos]
+            .iter()
+            .filter(|other| has_conflict(meeting, other))
+            .count();
+        let after = meetings[pos + 1..]
+            .iter()
+            .filter(|other| has_conflict(meeting, other))
+            .count();
+        (*meeting, before, after)
+    })
+}
+
+fn render_section_cards(
+    earliest: &Time,
+    latest: &Time,
+    renderable_meeting: &RenderableMeetingTime,
+    conflicts_before: usize,
+    conflicts_after: usize,
+    clash: bool,
+) -> Markup {
     html!(
-        @match renderable_meeting.mt.start_time.zip(renderable_meeting.mt.end_time) {
+        @match card_position(earliest, latest, renderable_meeting, conflicts_before, conflicts_after) {
             None => {}
-            Some((st, et)) => {
-                @let st = st.hour() as f32 + st.minute() as f32 / 60.0;
-                @let et = et.hour() as f32 + et.minute() as f32 / 60.0;
-                @let tp = (st - earliest) / (latest - earliest) * 100.0;
-                @let bp = (latest - et) / (latest - earliest) * 100.0;
-                @let lp = (conflicts_before as f32 / num_overlapping) * 100.0;
-                @let rp = (conflicts_after as f32 / num_overlapping) * 100.0;
+            Some((tp, bp, lp, rp)) => {
                 @let border = if renderable_meeting.full {
                     " border-2 border-red-800"
                 } else {
                     ""
                 };
-                @let opacity = if renderable_meeting.preview {
+                @let opacity = if clash {
+                    " opacity-75 z-10 rounded-lg ring-4 ring-red-500"
+                } else if renderable_meeting.preview {
                     " opacity-50"
                 } else {
                     ""
                 };
                 div class={"absolute top-[calc(" (tp) "%)] bottom-[calc(" (bp) "%)] left-[calc(" (lp) "%)] right-[calc(" (rp) "%)] h-auto w-auto" (opacity)} {
-                    div class={"h-full w-full rounded-lg overflow-y-scroll text-xs lg:text-sm color-red bg-[hsl(" ((renderable_meeting.crn * 10) % 360) ",100%,40%)] dark:bg-[hsl(" ((renderable_meeting.crn * 10) % 360) ",100%,25%)] flex flex-col box-sizing" (border)} {
+                    div class={"h-full w-full rounded-lg overflow-y-scroll text-xs lg:text-sm color-red bg-[hsl(" (renderable_meeting.hue) ",100%,40%)] dark:bg-[hsl(" (renderable_meeting.hue) ",100%,25%)] flex flex-col box-sizing" (border)} {
                         div class="flex justify-between flex-wrap bg-slate-200 dark:bg-slate-800 px-1" {
                             span class="text-[calc(1.5vh)] lg:text-xs lg:text-md" {
                                 (renderable_meeting.subject_code) " " (renderable_meeting.course_code)
@@ -68,6 +171,13 @@ fn render_section_cards(
                                 (renderable_meeting.sequence_code)
                             }
                         }
+                        @if let Some(location) = campus::location(&renderable_meeting.mt) {
+                            @match renderable_meeting.mt.building.as_deref().and_then(campus::find) {
+                                Some(building) => a href=(building.map_link()) target="_blank" title=(building.name)
+                                    class="px-1 text-[calc(1.5vh)] lg:text-xs hover:underline" { (location) },
+                                None => span class="px-1 text-[calc(1.5vh)] lg:text-xs" { (location) },
+                            }
+                        }
                     }
                 }
             }
@@ -75,55 +185,14 @@ fn render_section_cards(
     )
 }
 
-fn render_day(
-    day: Day,
-    timeslots: &Vec<Time>,
-    sections: &[Section],
-    preview_sections: &[Section],
-) -> Markup {
-    let earliest = timeslots.first().unwrap();
-    let latest = timeslots.last().unwrap();
+fn render_day(day: Day, timeslots: &Vec<Time>, meetings: &[RenderableMeetingTime]) -> Markup {
+    let earliest = timeslots.first().expect("there is always a timeslot");
+    let latest = timeslots.last().expect("there is always a timeslot");
 
-    let renderable_meetings: Vec<RenderableMeetingTime> = [
-        sections
-            .iter()
-            .flat_map(|s| {
-                s.meeting_times
-                    .clone()
-                    .into_iter()
-                    .map(|mt| RenderableMeetingTime {
-                        mt,
-                        crn: s.crn,
-                        subject_code: s.subject_code.clone(),
-                        course_code: s.course_code.clone(),
-                        sequence_code: s.sequence_code.clone(),
-                        full: s.enrollment == s.enrollment_capacity || s.waitlist > 0,
-                        preview: false,
-                    })
-            })
-            .collect::<Vec<_>>(),
-        preview_sections
-            .iter()
-            .flat_map(|s| {
-                s.meeting_times
-                    .clone()
-                    .into_iter()
-                    .map(|mt| RenderableMeetingTime {
-                        mt,
-                        crn: s.crn,
-                        subject_code: s.subject_code.clone(),
-                        course_code: s.course_code.clone(),
-                        sequence_code: s.sequence_code.clone(),
-                        full: s.enrollment == s.enrollment_capacity || s.waitlist > 0,
-                        preview: true,
-                    })
-            })
-            .collect(),
-    ]
-    .concat()
-    .into_iter()
-    .filter(|rm| day.is_in_days(rm.mt.days))
-    .collect();
+    let renderable_meetings: Vec<&RenderableMeetingTime> = meetings
+        .iter()
+        .filter(|rm| day.is_in_days(rm.mt.days))
+        .collect();
     // debug!(?day, ?sections);
     html!(
         div class="flex-1 flex flex-col" {
@@ -132,56 +201,413 @@ fn render_day(
                 @for _ in timeslots {
                     div class="h-auto grow bg-neutral-100 dark:bg-neutral-600" {  }
                 }
-                @for (pos, meeting) in renderable_meetings.iter().enumerate() {
-                    @let conflicts_before: usize = renderable_meetings[0..pos].iter().filter(|other| has_conflict(meeting, other)).collect_vec().len();
-                    @let conflicts_after: usize = renderable_meetings[pos+1..renderable_meetings.len()].iter().filter(|other| has_conflict(meeting, other)).collect_vec().len();
+                @for (meeting, conflicts_before, conflicts_after) in overlaps(&renderable_meetings) {
+                    @let clash = clashes(meeting, &renderable_meetings);
 
-                    (render_section_cards(earliest, latest, &meeting, conflicts_before, conflicts_after))
+                    (render_section_cards(earliest, latest, meeting, conflicts_before, conflicts_after, clash))
                 }
             }
         }
     )
 }
 
-pub fn view(sections: &[Section], preview_sections: &[Section]) -> Markup {
-    // debug!(?sections);
+/// The Monday starting the week `date` is in
+fn monday_of(date: Date) -> Date {
+    date - (date.weekday().to_monday_zero_offset() as i64).days()
+}
+
+/// The Mondays of the first and last weeks any of `meetings` are held in
+fn term_weeks(meetings: &[RenderableMeetingTime]) -> Option<(Date, Date)> {
+    let held = meetings.iter().filter(|m| m.mt.start_time.is_some());
+    let first = held.clone().map(|m| m.mt.start_date).min()?;
+    let last = held.map(|m| m.mt.end_date).max()?;
+    Some((monday_of(first), monday_of(last)))
+}
+
+/// The week to show: the one picked if classes are held then, otherwise this week while
+/// classes are on, otherwise the first week of classes
+fn shown_week(picked: Option<Date>, first: Date, last: Date) -> Date {
+    let today = monday_of(jiff::Zoned::now().date());
+    match picked.map(monday_of) {
+        Some(picked) if (first..=last).contains(&picked) => picked,
+        _ if (first..=last).contains(&today) => today,
+        _ => first,
+    }
+}
 
-    let meeting_times: Vec<&MeetingTime> = sections
+fn week_label(monday: Date, first: Date) -> Markup {
+    let sunday = monday + 6.days();
+    html! {
+        span {
+            "week " ((monday - first).get_days() / 7 + 1) " · "
+            (monday.strftime("%b %-d")) "–"
+            @if sunday.month() == monday.month() {
+                (sunday.strftime("%-d"))
+            } @else {
+                (sunday.strftime("%b %-d"))
+            }
+        }
+    }
+}
+
+/// Whether any of `meetings` are on a Saturday or Sunday
+fn has_weekend(meetings: &[RenderableMeetingTime]) -> bool {
+    meetings.iter().any(|m| {
+        m.mt.start_time.is_some()
+            && (Day::Saturday.is_in_days(m.mt.days) || Day::Sunday.is_in_days(m.mt.days))
+    })
+}
+
+fn week_controls(
+    schedule_id: &str,
+    monday: Date,
+    first: Date,
+    last: Date,
+    full_week: bool,
+) -> Markup {
+    let url = format!("/schedule/{}/calendar", schedule_id);
+    let button =
+        "px-2 rounded-lg hover:bg-neutral-200 hover:dark:bg-neutral-700 disabled:opacity-25";
+    html! {
+        div class="flex items-center justify-center gap-2 text-[calc(1.5vh)] lg:text-sm" {
+            button class=(button) disabled[monday <= first]
+                hx-get={(url) "?week=" (monday - 7.days())} hx-target="#calendar-view" hx-swap="outerHTML" {
+                "‹"
+            }
+            (week_label(monday, first))
+            input type="date" name="week" value=(monday) min=(first) max=(last + 6.days())
+                class="rounded bg-slate-100 dark:bg-neutral-900"
+                hx-get=(url) hx-trigger="change" hx-target="#calendar-view" hx-swap="outerHTML" {}
+            button class=(button) disabled[monday >= last]
+                hx-get={(url) "?week=" (monday + 7.days())} hx-target="#calendar-view" hx-swap="outerHTML" {
+                "›"
+            }
+            button class=(button) title="show or hide the weekend"
+                hx-get={(url) "?full_week=" (!full_week)} hx-target="#calendar-view" hx-swap="outerHTML" {
+                @if full_week { "5 days" } @else { "7 days" }
+            }
+        }
+    }
+}
+
+/// Which colour is which course
+fn legend(meetings: &[RenderableMeetingTime]) -> Markup {
+    let courses = meetings
         .iter()
-        .flat_map(|s| &s.meeting_times)
-        .chain(preview_sections.iter().flat_map(|s| &s.meeting_times))
-        .collect();
+        .unique_by(|m| (&m.subject_code, &m.course_code))
+        .sorted_by_key(|m| (&m.subject_code, &m.course_code));
+    html! {
+        div class="flex flex-wrap justify-center gap-x-2 text-[calc(1.5vh)] lg:text-sm" {
+            @for m in courses {
+                span class={"flex items-center gap-1" @if m.preview { " opacity-50" }} {
+                    span class={"inline-block w-3 h-3 rounded-full bg-[hsl(" (m.hue) ",100%,40%)] dark:bg-[hsl(" (m.hue) ",100%,25%)]"} {}
+                    (m.subject_code) " " (m.course_code)
+                }
+            }
+        }
+    }
+}
+
+/// The sections' calendar for the default week of their term, coloured like `selected`
+pub fn view(
+    sections: &[Section],
+    preview_sections: &[Section],
+    selected: &SelectedCourses,
+) -> Markup {
+    let hue = course_hue(selected);
+    let meetings = [
+        renderable(sections, false, &hue),
+        renderable(preview_sections, true, &hue),
+    ]
+    .concat();
+    let full_week = has_weekend(&meetings);
+    let header = |label| html! { (label) (legend(&meetings)) };
+    match term_weeks(&meetings) {
+        Some((first, last)) => {
+            let monday = shown_week(None, first, last);
+            let header = header(week_label(monday, first));
+            render(&meetings, Some(monday), full_week, header)
+        }
+        None => render(&meetings, None, full_week, header(html!())),
+    }
+}
+
+/// A schedule's calendar as the user left it, coloured like `selected`, with controls to move between weeks and
+/// show or hide the weekend
+pub fn week_view(
+    schedule_id: &str,
+    sections: &[Section],
+    preview_sections: &[Section],
+    selected: &SelectedCourses,
+    calendar: CalendarView,
+) -> Markup {
+    let hue = course_hue(selected);
+    let meetings = [
+        renderable(sections, false, &hue),
+        renderable(preview_sections, true, &hue),
+    ]
+    .concat();
+    let full_week = calendar.full_week.unwrap_or_else(|| has_weekend(&meetings));
+    let header = |controls| html! { (controls) (legend(&meetings)) };
+    match term_weeks(&meetings) {
+        Some((first, last)) => {
+            let monday = shown_week(calendar.week, first, last);
+            let controls = week_controls(schedule_id, monday, first, last, full_week);
+            render(&meetings, Some(monday), full_week, header(controls))
+        }
+        None => render(&meetings, None, full_week, header(html!())),
+    }
+}
+
+/// Every meeting of the term on one week, for printing
+pub fn whole_term(sections: &[Section], selected: &SelectedCourses) -> Markup {
+    let meetings = renderable(sections, false, course_hue(selected));
+    render(&meetings, None, has_weekend(&meetings), legend(&meetings))
+}
+
+/// The whole term's calendar drawn as an SVG image, for sharing outside the app. It is laid
+/// out like [`render`] lays out the page.
+pub fn image(sections: &[Section], selected: &SelectedCourses) -> Markup {
+    const WIDTH: f32 = 1200.0;
+    const HEIGHT: f32 = 630.0;
+    const TIME_WIDTH: f32 = 70.0;
+    const DAY_HEIGHT: f32 = 30.0;
+    const LEGEND_HEIGHT: f32 = 36.0;
+
+    let meetings = renderable(sections, false, course_hue(selected));
+    let timeslots = timeslots(&meetings);
+    let earliest = timeslots.first().expect("there is always a timeslot");
+    let latest = timeslots.last().expect("there is always a timeslot");
+    let days: &[Day] = if has_weekend(&meetings) {
+        &Day::ALL
+    } else {
+        &Day::WEEKDAYS
+    };
+
+    let grid_height = HEIGHT - DAY_HEIGHT - LEGEND_HEIGHT;
+    let day_width = (WIDTH - TIME_WIDTH) / days.len() as f32;
+    let slot_height = grid_height / timeslots.len() as f32;
+    let courses = meetings
+        .iter()
+        .unique_by(|m| (&m.subject_code, &m.course_code))
+        .sorted_by_key(|m| (&m.subject_code, &m.course_code));
+
+    html! {
+        svg xmlns="http://www.w3.org/2000/svg" width=(WIDTH) height=(HEIGHT) viewBox={"0 0 " (WIDTH) " " (HEIGHT)}
+            font-family="Helvetica, Arial, 'DejaVu Sans', 'Liberation Sans', sans-serif" font-size="14" {
+            rect width=(WIDTH) height=(HEIGHT) fill="white" {}
+            @for (i, slot) in timeslots.iter().enumerate() {
+                text x="4" y=(DAY_HEIGHT + i as f32 * slot_height + 14.0) fill="#404040" font-size="12" {
+                    (slot.strftime("%-I:%M%P"))
+                }
+            }
+            @for (i, day) in days.iter().enumerate() {
+                @let x = TIME_WIDTH + i as f32 * day_width;
+                text x=(x + day_width / 2.0) y="20" text-anchor="middle" fill="black" {
+                    (day.to_string().to_lowercase())
+                }
+                @for j in 0..timeslots.len() {
+                    rect x=(x + 1.0) y=(DAY_HEIGHT + j as f32 * slot_height + 1.0) width=(day_width - 2.0)
+                        height=(slot_height - 2.0) fill="#f5f5f5" {}
+                }
+                @let on_day = meetings.iter().filter(|m| day.is_in_days(m.mt.days)).collect_vec();
+                @for (meeting, before, after) in overlaps(&on_day) {
+                    @if let Some((tp, bp, lp, rp)) = card_position(earliest, latest, meeting, before, after) {
+                        @let left = x + lp / 100.0 * day_width;
+                        @let top = DAY_HEIGHT + tp / 100.0 * grid_height;
+                        @let height = grid_height * (1.0 - (tp + bp) / 100.0);
+                        rect x=(left + 1.0) y=(top) width=(day_width * (1.0 - (lp + rp) / 100.0) - 2.0) height=(height)
+                            rx="6" fill={"hsl(" (meeting.hue) ",100%,40%)"} {}
+                        text x=(left + 6.0) y=(top + 18.0) fill="white" {
+                            (meeting.subject_code) " " (meeting.course_code)
+                        }
+                        @if height > 40.0 {
+                            text x=(left + 6.0) y=(top + 36.0) fill="white" font-size="12" {
+                                (meeting.sequence_code)
+                            }
+                        }
+                    }
+                }
+            }
+            @for (i, m) in courses.enumerate() {
+                @let x = 10.0 + i as f32 * 130.0;
+                circle cx=(x + 6.0) cy=(HEIGHT - LEGEND_HEIGHT / 2.0) r="6" fill={"hsl(" (m.hue) ",100%,40%)"} {}
+                text x=(x + 18.0) y=(HEIGHT - LEGEND_HEIGHT / 2.0 + 5.0) fill="black" {
+                    (m.subject_code) " " (m.course_code)
+                }
+            }
+        }
+    }
+}
+
+/// Several schedules on one calendar, each in the colour of [`schedule_hue`]
+pub fn overlay(schedules: &[Vec<Section>]) -> Markup {
+    let meetings = schedules
+        .iter()
+        .enumerate()
+        .flat_map(|(i, sections)| renderable(sections, false, |_| schedule_hue(i)))
+        .collect::<Vec<_>>();
+    render(&meetings, None, has_weekend(&meetings), html!())
+}
+
+/// The week of the term `sections` are in to show, picked like [`week_view`] picks it
+pub fn week_of(sections: &[Section], picked: Option<Date>) -> Option<Date> {
+    let (first, last) = term_weeks(&renderable(sections, false, |_| 0))?;
+    Some(shown_week(picked, first, last))
+}
+
+/// Free time of a group of friends in the week starting `monday`, drawn like classes so it
+/// lines up with their calendars
+pub fn free_time(free: &[FreeTime], monday: Option<Date>) -> Markup {
+    let full_week = free
+        .iter()
+        .any(|f| matches!(f.day, Day::Saturday | Day::Sunday));
+    let header = html! {
+        @if let Some(monday) = monday {
+            div class="flex justify-center text-[calc(1.5vh)] lg:text-sm" {
+                "week of " (monday.strftime("%b %-d"))
+            }
+        }
+    };
+    render(
+        &free
+            .iter()
+            .map(|f| RenderableMeetingTime {
+                mt: MeetingTime {
+                    start_time: Some(f.start),
+                    end_time: Some(f.end),
+                    start_date: Date::default(),
+                    end_date: Date::default(),
+                    days: f.day.into(),
+                    building: None,
+                    room: None,
+                },
+                subject_code: "free".to_string(),
+                course_code: String::new(),
+                sequence_code: format!(
+                    "{}–{}",
+                    f.start.strftime("%-I:%M%P"),
+                    f.end.strftime("%-I:%M%P")
+                ),
+                preview: false,
+                full: false,
+                hue: 140,
+            })
+            .collect::<Vec<_>>(),
+        None,
+        full_week,
+        header,
+    )
+}
+
+/// A strip under the calendar of meetings that aren't drawn on it, with why
+fn off_grid(id: &str, label: &str, meetings: &[(&RenderableMeetingTime, String)]) -> Markup {
+    html! {
+        @if !meetings.is_empty() {
+            div id=(id) class="flex flex-wrap items-center gap-1 pt-1 text-[calc(1.5vh)] lg:text-sm" {
+                span { (label) }
+                @for (m, detail) in meetings {
+                    span class={"rounded-lg px-1 bg-[hsl(" (m.hue) ",100%,40%)] dark:bg-[hsl(" (m.hue) ",100%,25%)]" @if m.preview { " opacity-50" }} {
+                        (m.subject_code) " " (m.course_code) " " (m.sequence_code) " · " (detail)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The half hours the calendar has rows for, from the start of the earliest meeting to the
+/// end of the latest. There is always at least one, falling back to 8:30 to 11pm when the
+/// meetings are too short to fill a row.
+fn timeslots(meetings: &[RenderableMeetingTime]) -> Vec<Time> {
+    let meeting_times: Vec<&MeetingTime> = meetings.iter().map(|rm| &rm.mt).collect();
     // debug!(?meeting_times);
+    let default_start = jiff::civil::time(8, 30, 0, 0);
+    let default_end = jiff::civil::time(23, 20, 0, 0);
 
     let earliest: Time = meeting_times
         .iter()
         .flat_map(|mt| mt.start_time)
         .min()
-        .unwrap_or(jiff::civil::time(8, 30, 0, 0));
+        .unwrap_or(default_start);
 
     let latest = meeting_times
         .iter()
         .flat_map(|mt| mt.end_time)
         .max()
-        .unwrap_or(jiff::civil::time(23, 20, 0, 0))
-        - 20.minutes();
+        .unwrap_or(default_end);
 
     // debug!(?earliest, ?latest);
 
-    let timeslots = earliest
-        .series(30.minutes())
-        .take_while(|&t| t <= latest)
-        .collect::<Vec<Time>>();
+    let half_hours = |earliest: Time, latest: Time| {
+        earliest
+            .series(30.minutes())
+            .take_while(|&t| t <= latest - 20.minutes())
+            .collect::<Vec<Time>>()
+    };
+    match half_hours(earliest, latest) {
+        slots if slots.is_empty() => half_hours(default_start, default_end),
+        slots => slots,
+    }
+}
 
-    let saturday = meeting_times
+/// Draws `meetings` under `header`. Only those held in the week starting `monday` are drawn
+/// if it is given, but the hours shown always fit every meeting so they don't jump around
+/// between weeks. Weekend meetings are listed under the calendar unless it is `full_week`.
+fn render(
+    meetings: &[RenderableMeetingTime],
+    monday: Option<Date>,
+    full_week: bool,
+    header: Markup,
+) -> Markup {
+    let timeslots = timeslots(meetings);
+
+    let meetings: Vec<RenderableMeetingTime> = match monday {
+        Some(monday) => meetings
+            .iter()
+            .filter(|m| m.mt.is_held_in_week(monday))
+            .cloned()
+            .collect(),
+        None => meetings.to_vec(),
+    };
+    let meetings = meetings.as_slice();
+
+    let days: &[Day] = if full_week { &Day::ALL } else { &Day::WEEKDAYS };
+
+    // meetings that can't be placed on the grid are listed under it
+    let untimed = meetings
         .iter()
-        .filter(|mt| Day::Saturday.is_in_days(mt.days))
-        .count()
-        > 0;
+        .filter(|m| m.mt.start_time.is_none() || m.mt.end_time.is_none())
+        .map(|m| {
+            (
+                m,
+                m.mt.building.as_deref().unwrap_or("async").to_lowercase(),
+            )
+        })
+        .unique_by(|(m, _)| (&m.subject_code, &m.course_code, &m.sequence_code))
+        .collect_vec();
+    let hidden_weekend = meetings
+        .iter()
+        .filter_map(|m| {
+            let (start, end) = m.mt.start_time.zip(m.mt.end_time)?;
+            let hidden = Day::ALL
+                .iter()
+                .filter(|d| d.is_in_days(m.mt.days) && !days.contains(d))
+                .map(|d| d.to_string().to_lowercase()[..3].to_string())
+                .join(", ");
+            let times = format!(
+                "{}–{}",
+                start.strftime("%-I:%M%P"),
+                end.strftime("%-I:%M%P")
+            );
+            (!hidden.is_empty()).then(|| (m, format!("{} {}", hidden, times)))
+        })
+        .collect_vec();
 
     html!(
-        div id="calendar-view" class="w-full h-full" {
-            div id="calendar" class="w-full h-full overflow-y-scroll flex gap-0.5 lg:gap-1" {
+        div id="calendar-view" class="w-full h-full flex flex-col" {
+            (header)
+            div id="calendar" class="w-full grow min-h-0 overflow-y-scroll flex gap-0.5 lg:gap-1" {
                 div class="flex flex-col shrink" {
                     div class="text-[calc(1.5vh)] lg:text-sm shrink flex justify-center items-center" { "time" }
                     div class="relative flex flex-col grow gap-0.5 lg:gap-1" {
@@ -195,13 +621,12 @@ pub fn view(sections: &[Section], preview_sections: &[Section]) -> Markup {
                         }
                     }
                 }
-                @for d in &Day::WEEKDAYS {
-                    (render_day(*d, &timeslots, sections, preview_sections))
-                }
-                @if saturday {
-                    (render_day(Day::Saturday, &timeslots, sections, preview_sections))
+                @for d in days {
+                    (render_day(*d, &timeslots, meetings))
                 }
             }
+            (off_grid("no-fixed-time", "no fixed time:", &untimed))
+            (off_grid("weekend", "on the weekend:", &hidden_weekend))
         }
     )
 }
@@ -0,0 +1,40 @@
+use maud::{html, Markup};
+
+use crate::common::ScheduleChanges;
+
+/// Tells the user what happened to their selected sections since they last looked
+pub fn banner(schedule_id: &str, changes: &ScheduleChanges) -> Markup {
+    if changes.is_empty() {
+        return html!();
+    }
+    html! {
+        div id="changes-banner" class="fixed bottom-2 left-1/2 -translate-x-1/2 z-10 w-11/12 max-w-xl max-h-64 overflow-y-auto rounded-lg p-2 shadow-xl bg-amber-200 dark:bg-amber-800" {
+            div class="flex items-center gap-2" {
+                div class="grow font-bold" {
+                    "Sections in this schedule changed"
+                }
+                // missing sections would still be missing after dismissing
+                @if !changes.changes.is_empty() {
+                    button class="bg-amber-400 dark:bg-amber-600 hover:bg-amber-500 hover:dark:bg-amber-700 transition rounded-lg px-2"
+                        hx-post={"/schedule/" (schedule_id) "/changes/dismiss"}
+                        hx-target="#changes-banner"
+                        hx-swap="outerHTML" {
+                        "dismiss"
+                    }
+                }
+            }
+            ul class="list-disc pl-4" {
+                @for (course, crn) in &changes.missing {
+                    li {
+                        (course.subject_code) " " (course.course_code) " (" (crn) ") is no longer offered"
+                    }
+                }
+                @for entry in &changes.changes {
+                    li {
+                        (entry.change)
+                    }
+                }
+            }
+        }
+    }
+}
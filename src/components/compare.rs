@@ -0,0 +1,148 @@
+use std::collections::BTreeSet;
+
+use itertools::Itertools;
+use maud::{html, Markup};
+
+use crate::{
+    common::{ScheduleWithId, WeekSummary},
+    components::{self, calendar::schedule_hue},
+    scraper::{Section, ThinCourse},
+};
+
+/// Most schedules that can be told apart on one calendar
+pub const MAX_COMPARED: usize = 4;
+
+/// A schedule picked for comparison, with what is needed to draw and summarize it
+pub struct Compared {
+    pub schedule: ScheduleWithId,
+    pub sections: Vec<Section>,
+    pub summary: WeekSummary,
+}
+
+impl Compared {
+    /// The sequence codes of the sections picked for `course`, if it is in the schedule
+    fn picked(&self, course: &ThinCourse) -> Option<String> {
+        self.schedule.schedule.selected.courses.get(course)?;
+        Some(
+            self.sections
+                .iter()
+                .filter(|s| {
+                    s.subject_code == course.subject_code && s.course_code == course.course_code
+                })
+                .map(|s| s.sequence_code.as_str())
+                .join(", "),
+        )
+    }
+}
+
+fn duration(minutes: i64) -> String {
+    format!("{}h{:02}", minutes / 60, minutes % 60)
+}
+
+fn swatch(index: usize) -> Markup {
+    html! {
+        span class={"inline-block w-3 h-3 rounded-full bg-[hsl(" (schedule_hue(index)) ",100%,40%)]"} {}
+    }
+}
+
+fn url(compared: &[Compared], overlay: bool) -> String {
+    let ids = compared
+        .iter()
+        .map(|c| format!("ids={}", c.schedule.id))
+        .join("&");
+    format!("/compare?{}&overlay={}", ids, overlay)
+}
+
+/// Compared schedules side by side or on one calendar, above a table of how they differ
+pub fn page(compared: &[Compared], overlay: bool) -> Markup {
+    if !(2..=MAX_COMPARED).contains(&compared.len()) {
+        return html! {
+            div class="flex flex-col gap-2 h-full items-center justify-center" {
+                p { "Pick between 2 and " (MAX_COMPARED) " of your schedules to compare." }
+                (components::button::link("/", html!("back")))
+            }
+        };
+    }
+
+    let courses: BTreeSet<&ThinCourse> = compared
+        .iter()
+        .flat_map(|c| c.schedule.schedule.selected.courses.keys())
+        .collect();
+
+    html! {
+        div class="flex flex-col gap-2 p-2 h-full overflow-y-auto" {
+            div class="flex items-center gap-2" {
+                h2 class="grow text-xl" { "comparing " (compared.len()) " schedules" }
+                @if overlay {
+                    a href=(url(compared, false)) class="hover:underline" { "side by side" }
+                } @else {
+                    a href=(url(compared, true)) class="hover:underline" { "overlay" }
+                }
+                (components::button::link("/", html!("back")))
+            }
+            @if overlay {
+                div class="flex gap-4 flex-wrap" {
+                    @for (i, c) in compared.iter().enumerate() {
+                        span class="flex items-center gap-1" { (swatch(i)) (c.schedule.schedule.name) }
+                    }
+                }
+                div class="w-full h-[70vh] shrink-0 lg:p-1 bg-white dark:bg-neutral-800 rounded-lg shadow-xl" {
+                    (components::calendar::overlay(&compared.iter().map(|c| c.sections.clone()).collect::<Vec<_>>()))
+                }
+            } @else {
+                div class="grid grid-cols-1 lg:grid-cols-2 gap-2" {
+                    @for (i, c) in compared.iter().enumerate() {
+                        div class="flex flex-col gap-1 h-[50vh] lg:p-1 bg-white dark:bg-neutral-800 rounded-lg shadow-xl" {
+                            span class="flex items-center gap-1 px-1" {
+                                (swatch(i)) (c.schedule.schedule.name) " · " (c.schedule.schedule.term.human_display())
+                            }
+                            div class="grow min-h-0" {
+                                (components::calendar::view(&c.sections, &[], &c.schedule.schedule.selected))
+                            }
+                        }
+                    }
+                }
+            }
+            table class="w-full text-sm rounded-lg bg-white dark:bg-neutral-800 shadow-xl" {
+                thead {
+                    tr class="text-left" {
+                        th class="p-1" {}
+                        @for (i, c) in compared.iter().enumerate() {
+                            th class="p-1" { span class="flex items-center gap-1" { (swatch(i)) (c.schedule.schedule.name) } }
+                        }
+                    }
+                }
+                tbody {
+                    tr class="border-t border-slate-200 dark:border-neutral-700" {
+                        td class="p-1" { "class time per week" }
+                        @for c in compared { td class="p-1" { (duration(c.summary.class_minutes)) } }
+                    }
+                    tr class="border-t border-slate-200 dark:border-neutral-700" {
+                        td class="p-1" { "days on campus" }
+                        @for c in compared {
+                            td class="p-1" {
+                                (c.summary.days.len()) " ("
+                                (c.summary.days.iter().map(|d| d.to_string().to_lowercase()[..3].to_string()).join(", "))
+                                ")"
+                            }
+                        }
+                    }
+                    tr class="border-t border-slate-200 dark:border-neutral-700" {
+                        td class="p-1" { "time between classes" }
+                        @for c in compared { td class="p-1" { (duration(c.summary.gap_minutes)) } }
+                    }
+                    @for course in courses {
+                        @let picked = compared.iter().map(|c| c.picked(course)).collect::<Vec<_>>();
+                        @let differs = !picked.iter().all_equal();
+                        tr class={"border-t border-slate-200 dark:border-neutral-700" @if differs { " bg-amber-100 dark:bg-amber-900" }} {
+                            td class="p-1" { (course.subject_code) " " (course.course_code) }
+                            @for sections in &picked {
+                                td class="p-1" { (sections.as_deref().unwrap_or("—")) }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
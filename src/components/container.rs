@@ -1,6 +1,7 @@
 use maud::{html, Markup};
 
 use crate::{
+    common::{CalendarView, History, Schedule, SelectedCourses},
     components,
     scraper::{Course, Section, ThinCourse},
 };
@@ -10,12 +11,15 @@ pub fn main_container(
     search_courses: &[ThinCourse],
     courses: &[Course],
     selected: &[Section],
+    history: &History,
+    schedule: &Schedule,
+    calendar: CalendarView,
 ) -> Markup {
     html! {
         div id="main-container" class="flex flex-col w-full h-full lg:flex-row lg:p-1 gap-1" {
             div id="calendar-container" class="w-full h-1/2 lg:h-full" {
                 div class="w-full h-full lg:p-1 flex justify-center items-center bg-white dark:bg-neutral-800 lg:rounded-lg shadow-xl" {
-                    (components::calendar::view(selected, &[]))
+                    (components::calendar::week_view(schedule_id, selected, &[], &schedule.selected, calendar))
                 }
             }
             div id="interactive-container" class="w-full h-1/2 flex flex-row px-1 pb-1 gap-1 lg:contents" {
@@ -31,9 +35,10 @@ pub fn main_container(
                         (components::search_result::render(schedule_id, search_courses))
                     }
                 }
-                section class="h-full overflow-y-hidden shrink-0 grow basis-1/2 lg:basis-1/5 bg-white dark:bg-neutral-800 p-2 rounded-lg" {
-                    div id="courses-container" class="h-full overflow-y-scroll" {
-                        (components::courses::view(schedule_id, courses, selected))
+                section class="h-full overflow-y-hidden shrink-0 grow basis-1/2 lg:basis-1/5 bg-white dark:bg-neutral-800 p-2 rounded-lg flex flex-col gap-1" {
+                    (components::history::controls(schedule_id, history))
+                    div id="courses-container" class="grow min-h-0 overflow-y-scroll" {
+                        (components::courses::view(schedule_id, courses, selected, schedule))
                     }
                 }
             }
@@ -44,6 +49,7 @@ pub fn main_container(
 pub fn generator_container(
     schedule_id: &String,
     sections: &Vec<Section>,
+    selected: &SelectedCourses,
     prev_url: &String,
     next_url: &String,
     overwrite_url: &str,
@@ -53,7 +59,7 @@ pub fn generator_container(
         div id="generator-container" class="flex flex-col w-full h-full lg:flex-row lg:p-1 gap-1" {
             dif id="calendar-container" class="w-full h-1/2 lg:h-full" {
                 div class="w-full h-full lg:p-1 flex justify-center items-center bg-white dark:bg-neutral-800 lg:rounded-lg shadow-xl" {
-                    (components::calendar::view(sections, &[]))
+                    (components::calendar::view(sections, &[], selected))
                 }
             }
             div id="interactive-container" class="w-full h-1/2 flex flex-row px-1 pb-1 gap-1 lg:contents" {
@@ -1,11 +1,21 @@
+use itertools::Itertools;
 use maud::{html, Markup};
 use tracing::debug;
 
 use crate::{
-    components,
-    scraper::{Course, MeetingTime, Section},
+    campus,
+    common::{Schedule, COURSE_HUES},
+    components, conflict,
+    scraper::{Course, MeetingTime, Section, ThinCourse},
 };
 
+fn section_name(section: &Section) -> String {
+    format!(
+        "{} {} {}",
+        section.subject_code, section.course_code, section.sequence_code
+    )
+}
+
 fn meeting_time_indicator(mt: &MeetingTime) -> Markup {
     let start_time_str = match mt.start_time {
         Some(t) => t.strftime("%-I:%M%P to").to_string(),
@@ -60,6 +70,17 @@ fn meeting_time_indicator(mt: &MeetingTime) -> Markup {
                 div class={ (friday_bg) " border px-2"} {
                     "F"
                 }
+                // weekends only take up room when there's a class then
+                @if mt.days.saturday {
+                    div class="bg-blue-400 border px-2" {
+                        "S"
+                    }
+                }
+                @if mt.days.sunday {
+                    div class="bg-blue-400 border px-2" {
+                        "U"
+                    }
+                }
             }
             div {
                 p {
@@ -68,6 +89,11 @@ fn meeting_time_indicator(mt: &MeetingTime) -> Markup {
                 p {
                     (end_time_str)
                 }
+                @if let Some(location) = campus::location(mt) {
+                    p title=[mt.building.as_deref().and_then(campus::find).map(|b| b.name)] {
+                        (location)
+                    }
+                }
             }
         }
     )
@@ -78,10 +104,16 @@ fn small_section_card(
     title: &String,
     section: &Section,
     selected: bool,
+    clashes: &[&Section],
+    block_conflicts: bool,
 ) -> Markup {
-    let color = match selected {
-        true => "bg-blue-600 dark:bg-blue-800",
-        false => "bg-green-500 dark:bg-green-800 hover:bg-green-600 hover:dark:bg-green-900",
+    let blocked = !selected && !clashes.is_empty() && block_conflicts;
+    let color = match (selected, clashes.is_empty()) {
+        (true, true) => "bg-blue-600 dark:bg-blue-800",
+        (true, false) => "bg-blue-600 dark:bg-blue-800 ring-4 ring-inset ring-red-500",
+        (false, true) => "bg-green-500 dark:bg-green-800 hover:bg-green-600 hover:dark:bg-green-900",
+        (false, false) if blocked => "bg-red-500 dark:bg-red-800 cursor-not-allowed",
+        (false, false) => "bg-red-500 dark:bg-red-800 hover:bg-red-600 hover:dark:bg-red-900",
     };
 
     let meeting_times = &section.meeting_times;
@@ -89,27 +121,34 @@ fn small_section_card(
     let full = section.enrollment >= section.enrollment_capacity || section.waitlist > 0;
 
     html!(
-        form hx-patch={"/schedule/" (schedule_id) "/calendar" } hx-swap="multi:#calendar-view,#courses-view" class="mb-0" {
-            div
-            // hx-get={"/schedule/" (schedule_id) "/calendar"} hx-target="#calendar-view" hx-trigger="pointerleave delay:100ms"
-            {
-                button
-                // hx-get={"/schedule/" (schedule_id) "/calendar/preview"} hx-target="#calendar-view" hx-trigger="pointerenter delay:100ms"
+        form hx-patch={"/schedule/" (schedule_id) "/calendar" } hx-swap="multi:#calendar-view,#courses-view,#history-controls" class="mb-0" {
+            button
+                hx-get=[(!selected).then(|| format!("/schedule/{schedule_id}/calendar/preview?crn={crn}"))]
+                hx-target=[(!selected).then_some("#calendar-view")]
+                hx-swap=[(!selected).then_some("outerHTML")]
+                hx-sync=[(!selected).then_some("#main-container:replace")]
+                hx-trigger=[(!selected).then_some("pointerenter[pointerType!='touch'] delay:100ms, longpress")]
+                data-preview[!selected]
+                disabled[blocked]
                 class={(color) " transition p-2 rounded-lg w-full flex flex-col"} name="crn" value=(crn) {
-                    div class="font-bold" {
-                        (title)
+                div class="font-bold" {
+                    (title)
+                }
+                div class="text-xs" {
+                    @if full {
+                        "FULL, waitlist: " (section.waitlist) "/" (section.waitlist_capacity)
+                    } @else {
+                        "seats: " (section.enrollment) "/" (section.enrollment_capacity)
                     }
-                    div class="text-xs" {
-                        @if full {
-                            "FULL, waitlist: " (section.waitlist) "/" (section.waitlist_capacity)
-                        } @else {
-                            "seats: " (section.enrollment) "/" (section.enrollment_capacity)
-                        }
+                }
+                @if !clashes.is_empty() {
+                    div class="text-xs text-left" {
+                        "clashes with " (clashes.iter().map(|s| section_name(s)).join(", "))
                     }
-                    div class="flex flex-col" {
-                        @for mt in meeting_times {
-                            (meeting_time_indicator(mt))
-                        }
+                }
+                div class="flex flex-col" {
+                    @for mt in meeting_times {
+                        (meeting_time_indicator(mt))
                     }
                 }
             }
@@ -117,32 +156,116 @@ fn small_section_card(
     )
 }
 
-fn sections(schedule_id: &String, sections: Vec<&Section>, selected: &[u64]) -> Markup {
+fn sections(
+    schedule_id: &String,
+    sections: Vec<&Section>,
+    selected: &[Section],
+    block_conflicts: bool,
+) -> Markup {
     html!(
         div class="flex flex-col gap-2 py-2 border-t" {
             @for section in sections {
-                (small_section_card(schedule_id, &section.sequence_code, &section, selected.contains(&section.crn)))
+                @let is_selected = selected.iter().any(|s| s.crn == section.crn);
+                @let clashes = conflict::clashes_with(section, selected);
+                (small_section_card(schedule_id, &section.sequence_code, section, is_selected, &clashes, block_conflicts))
             }
         }
 
     )
 }
 
-pub fn view(schedule_id: &String, courses: &[Course], selected: &[Section]) -> Markup {
-    let selected: Vec<u64> = selected.iter().map(|s| s.crn).collect();
+/// What the schedule's clashes are, and whether sections that would clash can be picked
+fn conflicts_summary(schedule_id: &String, selected: &[Section], block_conflicts: bool) -> Markup {
+    let conflicts = conflict::conflicts(selected);
+    html! {
+        div class="flex flex-col gap-1" {
+            @if !conflicts.is_empty() {
+                div class="rounded-lg p-1 bg-red-100 dark:bg-red-900" {
+                    p { (conflicts.len()) @if conflicts.len() == 1 { " clash" } @else { " clashes" } " in this schedule:" }
+                    ul class="list-disc list-inside text-sm" {
+                        @for (a, b) in &conflicts {
+                            li { (section_name(a)) " and " (section_name(b)) }
+                        }
+                    }
+                }
+            }
+            form class="mb-0 text-sm" hx-post={"/schedule/" (schedule_id) "/conflicts"} hx-trigger="change"
+                hx-target="#courses-view" hx-swap="outerHTML" {
+                label class="flex gap-2 items-center" {
+                    input type="checkbox" name="block" value="true" checked[block_conflicts] {}
+                    "don't let me pick sections that clash"
+                }
+            }
+        }
+    }
+}
+
+/// The course's colour, and the ones it could be changed to
+fn colour_picker(schedule_id: &String, course: &ThinCourse, hue: u64) -> Markup {
+    html! {
+        form class="mb-0 flex flex-wrap gap-1" hx-post={"/schedule/" (schedule_id) "/calendar/colour"}
+            hx-swap="multi:#calendar-view,#courses-view,#history-controls" {
+            input type="hidden" name="course" value={(course.subject_code) " " (course.course_code)} {}
+            @for h in COURSE_HUES {
+                button name="hue" value=(h) title="colour the course like this"
+                    class={"w-4 h-4 rounded-full bg-[hsl(" (h) ",100%,40%)] dark:bg-[hsl(" (h) ",100%,25%)]"
+                        @if h == hue { " ring-2 ring-black dark:ring-white" }} {}
+            }
+        }
+    }
+}
+
+/// Back-to-back classes too far apart to walk between before the next one starts
+fn walks_summary(selected: &[Section]) -> Markup {
+    let walks = campus::tight_walks(selected);
+    html! {
+        @if !walks.is_empty() {
+            div class="rounded-lg p-1 bg-yellow-100 dark:bg-yellow-900" {
+                p { "not much time to walk between:" }
+                ul class="list-disc list-inside text-sm" {
+                    @for walk in &walks {
+                        li {
+                            (walk.day.to_string().to_lowercase()) ": "
+                            (section_name(walk.from)) " and " (section_name(walk.to)) ", "
+                            (walk.walking_minutes) " min walk in " (walk.gap_minutes) " min"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn view(
+    schedule_id: &String,
+    courses: &[Course],
+    selected: &[Section],
+    schedule: &Schedule,
+) -> Markup {
+    let block_conflicts = schedule.block_conflicts;
+    let hues = schedule.selected.hues();
     let share_link = format!("/share/{schedule_id}");
     let generate_link = format!("/schedule/{schedule_id}/generate");
-    debug!(?selected);
+    let print_link = format!("/schedule/{schedule_id}/print");
+    let register_link = format!("/schedule/{schedule_id}/register");
+    debug!(selected = ?selected.iter().map(|s| s.crn).collect::<Vec<_>>());
 
     html! {
-        div id="courses-view" class="flex flex-col gap-2 " {
+        div id="courses-view" class="flex flex-col gap-2 "
+            hx-get={"/schedule/" (schedule_id) "/calendar"} hx-target="#calendar-view" hx-swap="outerHTML"
+            hx-sync="#main-container:replace" hx-disinherit="*"
+            hx-trigger="pointerleave[pointerType!='touch'] delay:150ms, previewend" {
             @if courses.is_empty() {
                 "use the search bar to add some courses"
             } @else {
                 div class="flex justify-between gap-2" {
                     (components::button::link(share_link.as_str(), html!("share")))
+                    (components::button::link(print_link.as_str(), html!("print")))
+                    (components::button::link(register_link.as_str(), html!("register")))
                     (components::button::link(generate_link.as_str(), html!("generate")))
                 }
+                (conflicts_summary(schedule_id, selected, block_conflicts))
+                (walks_summary(selected))
                 @for course in courses {
                     @let lectures: Vec<&Section> = course.sections.iter().filter(|s| s.sequence_code.starts_with("A")).collect();
                     @let labs : Vec<&Section>= course.sections.iter().filter(|s| s.sequence_code.starts_with("B")).collect();
@@ -153,7 +276,7 @@ pub fn view(schedule_id: &String, courses: &[Course], selected: &[Section]) -> M
                             form class="mb-0" {
                                 button name="course" value={(course.subject_code) " " (course.course_code)}
                                 class="bg-red-500 dark:bg-red-600 hover:bg-red-700 hover:dark:bg-red-800 h-full text-xl p-1 rounded-lg"
-                                hx-delete={"/schedule/" (schedule_id) "/calendar"} hx-swap="multi:#calendar-view,#courses-view" {
+                                hx-delete={"/schedule/" (schedule_id) "/calendar"} hx-swap="multi:#calendar-view,#courses-view,#history-controls" {
                                     "remove"
                                 }
                             }
@@ -161,17 +284,21 @@ pub fn view(schedule_id: &String, courses: &[Course], selected: &[Section]) -> M
                         h3 {
                             (&course.title)
                         }
+                        @let thin_course = ThinCourse { subject_code: course.subject_code.clone(), course_code: course.course_code.clone() };
+                        @if let Some(hue) = hues.get(&thin_course) {
+                            (colour_picker(schedule_id, &thin_course, *hue))
+                        }
 
                         @if !lectures.is_empty() {
-                            (sections(&schedule_id, lectures, &selected))
+                            (sections(schedule_id, lectures, selected, block_conflicts))
                         }
 
                         @if !labs.is_empty() {
-                            (sections(&schedule_id, labs, &selected))
+                            (sections(schedule_id, labs, selected, block_conflicts))
                         }
 
                         @if !tutorials.is_empty() {
-                            (sections(&schedule_id, tutorials, &selected))
+                            (sections(schedule_id, tutorials, selected, block_conflicts))
                         }
                     }
                 }
@@ -211,7 +338,7 @@ fn generator_sections(schedule_id: &String, sections: &Vec<Section>) -> Markup {
         div class="flex flex-col gap-2 py-2 border-t" {
             @for section in sections {
                 @let card_title = format!("{} {} {}", &section.subject_code, &section.course_code, &section.sequence_code);
-                (small_section_card(schedule_id, &card_title, &section, true))
+                (small_section_card(schedule_id, &card_title, section, true, &[], false))
             }
         }
     )
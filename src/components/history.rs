@@ -0,0 +1,68 @@
+use maud::{html, Markup};
+
+use crate::{common::History, components};
+
+const SWAP: &str = "multi:#calendar-view,#courses-view,#history-controls";
+
+fn control(schedule_id: &str, action: &str, enabled: bool) -> Markup {
+    html! {
+        button class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 disabled:bg-slate-400 disabled:dark:bg-neutral-600 transition rounded-lg px-2"
+            hx-post={"/schedule/" (schedule_id) "/" (action)}
+            hx-swap=(SWAP)
+            disabled[!enabled] {
+            (action)
+        }
+    }
+}
+
+/// Undo/redo buttons, swapped alongside the calendar and course list after every change
+pub fn controls(schedule_id: &str, history: &History) -> Markup {
+    html! {
+        div id="history-controls" class="flex gap-1 items-center" {
+            (control(schedule_id, "undo", history.can_undo()))
+            (control(schedule_id, "redo", history.can_redo()))
+            a href={"/schedule/" (schedule_id) "/history"} class="hover:underline px-1" {
+                "history"
+            }
+            a href={"/schedule/" (schedule_id) "/edit"} class="hover:underline px-1" {
+                "edit"
+            }
+        }
+    }
+}
+
+pub fn page(schedule_id: &str, name: &str, history: &History) -> Markup {
+    html! {
+        div class="flex justify-center p-2 h-full" {
+            div class="w-full max-w-xl flex flex-col gap-2 rounded-lg p-2 bg-white dark:bg-neutral-800 shadow-xl overflow-y-auto" {
+                div class="flex items-center gap-2" {
+                    h2 class="grow text-xl" { (name) " history" }
+                    (components::button::link(&format!("/schedule/{}", schedule_id), html!("back")))
+                }
+                @if history.revisions.is_empty() {
+                    p { "No changes yet." }
+                }
+                @for (i, revision) in history.revisions.iter().enumerate().rev() {
+                    div class="flex items-center gap-2 border-b border-slate-200 dark:border-neutral-700 py-1" {
+                        div class="grow flex flex-col" {
+                            span { (revision.description) }
+                            span class="text-xs text-neutral-500" {
+                                (revision.at.strftime("%Y-%m-%d %H:%M UTC"))
+                                " · "
+                                (revision.selected.courses.len()) " courses"
+                            }
+                        }
+                        @if i == history.current {
+                            span class="px-2" { "current" }
+                        } @else {
+                            button class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 transition rounded-lg px-2"
+                                hx-post={"/schedule/" (schedule_id) "/history/" (i)} {
+                                "restore"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
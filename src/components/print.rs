@@ -0,0 +1,68 @@
+use maud::{html, Markup, DOCTYPE};
+
+use crate::{campus, common::Schedule, export, scraper::Section};
+
+/// The schedule on its own page, laid out to be printed
+pub fn page(schedule_id: &str, schedule: &Schedule, sections: &[Section]) -> Markup {
+    let crns = sections
+        .iter()
+        .map(|s| s.crn.to_string())
+        .collect::<Vec<_>>();
+    html! {
+        (DOCTYPE)
+        html {
+            head {
+                title { (schedule.name) " · scheduler" }
+                script src="/assets/tailwind.js" {}
+                meta name="viewport" content="width=device-width,initial-scale=1.0" {}
+            }
+            body class="bg-white text-black p-4 flex flex-col gap-4 [print-color-adjust:exact]" {
+                div class="flex gap-2 print:hidden" {
+                    a href={"/schedule/" (schedule_id)} class="bg-green-500 hover:bg-green-700 rounded-lg transition p-1" { "back" }
+                    button onclick="window.print()" class="bg-green-500 hover:bg-green-700 rounded-lg transition p-1" { "print" }
+                    a href={"/schedule/" (schedule_id) "/pdf"} class="bg-green-500 hover:bg-green-700 rounded-lg transition p-1" { "download pdf" }
+                }
+                div {
+                    h1 class="text-2xl" { (schedule.name) }
+                    p {
+                        (schedule.term.human_display())
+                        @if let Some((first, last)) = export::term_dates(sections) {
+                            " · classes " (export::dates(first, last))
+                        }
+                    }
+                }
+                div class="h-[60vh] print:h-[120mm] break-inside-avoid" {
+                    (super::calendar::whole_term(sections, &schedule.selected))
+                }
+                p { "CRNs: " span class="font-mono" { (crns.join(" ")) } }
+                table class="w-full text-sm text-left" {
+                    thead {
+                        tr class="border-b border-black" {
+                            th { "course" } th { "section" } th { "CRN" } th { "days" } th { "time" }
+                            th { "dates" } th { "building" } th { "room" }
+                        }
+                    }
+                    tbody {
+                        @for (section, mt) in export::rows(sections) {
+                            tr class="border-b border-neutral-300 break-inside-avoid" {
+                                td { (section.subject_code) " " (section.course_code) }
+                                td { (section.sequence_code) }
+                                td class="font-mono" { (section.crn) }
+                                td { (mt.days) }
+                                td { (export::times(mt)) }
+                                td { (export::dates(mt.start_date, mt.end_date)) }
+                                td {
+                                    @match mt.building.as_deref().and_then(campus::find) {
+                                        Some(building) => a href=(building.map_link()) class="underline" { (building.name) },
+                                        None => (mt.building.as_deref().unwrap_or("")),
+                                    }
+                                }
+                                td { (mt.room.as_deref().unwrap_or("")) }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
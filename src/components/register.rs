@@ -0,0 +1,95 @@
+use itertools::Itertools;
+use maud::{html, Markup};
+
+use crate::{
+    common::Schedule,
+    components, conflict,
+    scraper::{Course, Section},
+};
+
+/// How many backups are listed for each section
+const BACKUPS: usize = 3;
+
+fn copy_button(text: &str, label: &str) -> Markup {
+    html! {
+        button class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 rounded-lg transition px-1 text-sm"
+            data-copy=(text) onclick="navigator.clipboard.writeText(this.dataset.copy).then(() => this.textContent = 'copied')" {
+            (label)
+        }
+    }
+}
+
+fn seats(section: &Section) -> Markup {
+    html! {
+        @if section.waitlist > 0 {
+            span class="text-red-600 dark:text-red-400" { (section.waitlist) " waitlisted" }
+        } @else if conflict::full(section) {
+            span class="text-red-600 dark:text-red-400" { "full" }
+        } @else {
+            span { (section.enrollment_capacity - section.enrollment) " seats left" }
+        }
+    }
+}
+
+fn section_row(section: &Section) -> Markup {
+    html! {
+        div class="flex items-center gap-2" {
+            span class="w-10" { (section.sequence_code) }
+            span class="font-mono w-14" { (section.crn) }
+            span class="grow text-sm" { (seats(section)) }
+            (copy_button(&section.crn.to_string(), "copy"))
+        }
+    }
+}
+
+/// The schedule's CRNs by course, ready to paste into Banner, with sections to fall back on
+/// if one is full
+pub fn page(
+    schedule_id: &str,
+    schedule: &Schedule,
+    courses: &[Course],
+    sections: &[Section],
+) -> Markup {
+    let groups = conflict::partition(courses);
+    // one CRN per line, which pastes into Banner's boxes one at a time
+    let all_crns = schedule.selected.crns().iter().join("\n");
+    html! {
+        div class="flex h-full w-full justify-center overflow-y-auto" {
+            div class="w-full max-w-screen-md flex flex-col gap-2 p-2" {
+                div class="flex items-center gap-2" {
+                    (components::button::link(&format!("/schedule/{schedule_id}"), html!("back")))
+                    h1 class="grow text-xl" { "registering for " (schedule.name) }
+                    (copy_button(&all_crns, "copy all CRNs"))
+                }
+                p class="text-sm" {
+                    "enter these CRNs in banner's \"enter CRNs\" form. if a section is full, try its backups in order: they don't clash with the rest of your schedule."
+                }
+                @for (course, selection) in &schedule.selected.courses {
+                    @let title = courses.iter().find(|c| c.subject_code == course.subject_code && c.course_code == course.course_code).map(|c| c.title.as_str());
+                    div class="bg-white dark:bg-neutral-800 rounded-lg shadow-lg p-2 flex flex-col gap-1" {
+                        div class="flex items-center gap-2" {
+                            h2 class="grow text-lg" {
+                                (course.subject_code) " " (course.course_code)
+                                @if let Some(title) = title { span class="text-sm" { " · " (title) } }
+                            }
+                            (copy_button(&selection.crns().iter().join("\n"), "copy course"))
+                        }
+                        @for section in selection.crns().iter().filter_map(|crn| sections.iter().find(|s| s.crn == *crn)) {
+                            (section_row(section))
+                            @let group = groups.iter().find(|g| g.iter().any(|s| s.crn == section.crn));
+                            @let backups = group.map(|g| conflict::backups(section, g, sections)).unwrap_or_default();
+                            @if !backups.is_empty() {
+                                div class="pl-4 flex flex-col gap-1 border-l-2 border-neutral-300 dark:border-neutral-600" {
+                                    span class="text-sm" { "backups for " (section.sequence_code) ":" }
+                                    @for backup in backups.into_iter().take(BACKUPS) {
+                                        (section_row(backup))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -4,24 +4,101 @@ use maud::{html, Markup};
 
 use common::ScheduleWithId;
 
-use crate::common;
+use crate::{
+    common::{self, Schedule},
+    components,
+    scraper::{Term, ThinCourse},
+};
 
 pub fn view(mut schedules: Vec<ScheduleWithId>) -> Markup {
     schedules.sort_by_key(|s| Reverse(format!("{}{}", s.schedule.term, s.schedule.name)));
     html!(
         div id="schedules-view" class="flex flex-col gap-2" {
             @for schedule in schedules {
-                div class="flex gap-2" {
+                div class="flex gap-2 items-center" {
+                    input type="checkbox" name="ids" value=(schedule.id) form="compare-form" class="w-5 h-5" {}
                     a href={"/schedule/" (schedule.id)} class="grow flex gap-2 justify-between bg-blue-500 dark:bg-blue-600 hover:bg-blue-700 hover:dark:bg-blue-800 rounded-lg transition p-2" {
                         p {(schedule.schedule.name)}
                         p {(schedule.schedule.term.human_display())}
                     }
+                    a href={"/schedule/" (schedule.id) "/edit"}
+                    class="h-10 flex justify-center items-center bg-slate-300 dark:bg-neutral-700 hover:bg-slate-400 hover:dark:bg-neutral-600 rounded-lg transition p-2" {
+                        ("edit")
+                    }
                     button hx-delete={"/schedule/" (schedule.id)} hx-target="#schedules-view" hx-swap="outerHTML"
                     class="w-10 h-10 flex justify-center items-center bg-red-500 dark:bg-red-600 hover:bg-red-700 hover:dark:bg-red-800 rounded-lg transition p-2" {
                         ("x")
                     }
                 }
             }
+            form id="compare-form" action="/compare" method="get" class="flex gap-2 items-center justify-end text-sm" {
+                label class="flex gap-1 items-center" {
+                    input type="checkbox" name="overlay" value="true" {}
+                    "overlay"
+                }
+                button class="bg-slate-300 dark:bg-neutral-700 hover:bg-slate-400 hover:dark:bg-neutral-600 rounded-lg transition px-2 py-1" {
+                    "compare selected"
+                }
+            }
+            a href="/trash" class="self-end text-sm hover:underline" { "recently deleted" }
         }
     )
 }
+
+/// Renaming, duplicating and copying a schedule to another term
+pub fn edit_page(schedule_id: &str, schedule: &Schedule, terms: &[Term]) -> Markup {
+    html! {
+        div class="flex justify-center p-2 h-full" {
+            div class="w-full max-w-xl flex flex-col gap-4 rounded-lg p-2 bg-white dark:bg-neutral-800 shadow-xl overflow-y-auto" {
+                div class="flex items-center gap-2" {
+                    h2 class="grow text-xl" { (schedule.name) " · " (schedule.term.human_display()) }
+                    (components::button::link(&format!("/schedule/{}", schedule_id), html!("back")))
+                }
+                form method="post" action={"/schedule/" (schedule_id) "/rename"} class="flex gap-2" {
+                    input type="text" name="name" value=(schedule.name) maxlength="100" required
+                        class="p-2 rounded-lg text-black grow border-neutral-400 border-2 dark:border-0" {}
+                    button class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 rounded-lg transition px-2" {
+                        "rename"
+                    }
+                }
+                form method="post" action={"/schedule/" (schedule_id) "/duplicate"} class="flex gap-2 items-center" {
+                    p class="grow" { "Make a copy to try out a variant of this schedule." }
+                    button class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 rounded-lg transition px-2" {
+                        "duplicate"
+                    }
+                }
+                form method="post" action={"/schedule/" (schedule_id) "/copy"} class="flex gap-2 items-center" {
+                    p class="grow" { "Start a schedule with the same courses in" }
+                    select name="term" class="text-black rounded-lg p-2 border-2 border-neutral-400 dark:border-0" {
+                        @for term in terms.iter().filter(|t| **t != schedule.term) {
+                            option value=(term) { (term.human_display()) }
+                        }
+                    }
+                    button class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 rounded-lg transition px-2" {
+                        "copy"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Shown after copying a schedule to another term that doesn't offer all of its courses
+pub fn copied_page(schedule_id: &str, schedule: &Schedule, missing: &[ThinCourse]) -> Markup {
+    html! {
+        div class="flex justify-center p-2 h-full" {
+            div class="w-full max-w-xl flex flex-col gap-2 rounded-lg p-2 bg-white dark:bg-neutral-800 shadow-xl overflow-y-auto" {
+                h2 class="text-xl" { "Copied to " (schedule.term.human_display()) }
+                p { "These courses aren't offered in " (schedule.term.human_display()) ", so they were left out:" }
+                ul class="list-disc pl-6" {
+                    @for course in missing {
+                        li { (course.subject_code) " " (course.course_code) }
+                    }
+                }
+                div class="flex" {
+                    (components::button::link(&format!("/schedule/{}", schedule_id), html!("open the copy")))
+                }
+            }
+        }
+    }
+}
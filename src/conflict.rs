@@ -0,0 +1,248 @@
+//! Whether sections can be taken together, shared by the generator and the manual builder
+
+use itertools::Itertools;
+use jiff::civil::{Date, Time};
+
+use crate::scraper;
+
+/// A meeting time reduced to what decides whether it clashes with another. Two are equal
+/// when they clash.
+pub struct MeetingTime {
+    start_time: Option<Time>,
+    end_time: Option<Time>,
+    start_date: Date,
+    end_date: Date,
+
+    days: u8,
+}
+
+impl MeetingTime {
+    pub fn new(t: &scraper::MeetingTime) -> Self {
+        let mut day_mask = 0u8;
+        if t.days.monday {
+            day_mask |= 1 << 0;
+        }
+        if t.days.tuesday {
+            day_mask |= 1 << 1;
+        }
+        if t.days.wednesday {
+            day_mask |= 1 << 2;
+        }
+        if t.days.thursday {
+            day_mask |= 1 << 3;
+        }
+        if t.days.friday {
+            day_mask |= 1 << 4;
+        }
+        if t.days.saturday {
+            day_mask |= 1 << 5;
+        }
+        if t.days.sunday {
+            day_mask |= 1 << 6;
+        }
+        MeetingTime {
+            start_time: t.start_time,
+            end_time: t.end_time,
+            start_date: t.start_date,
+            end_date: t.end_date,
+            days: day_mask,
+        }
+    }
+}
+
+impl PartialEq for MeetingTime {
+    fn eq(&self, other: &Self) -> bool {
+        // async and online meetings can be fit around anything
+        if [self.start_time, self.end_time, other.start_time, other.end_time].contains(&None) {
+            return false;
+        }
+        // no conflict in term
+        if self.end_date < other.start_date || other.end_date < self.start_date {
+            return false;
+        }
+        // no conflict in week
+        if (self.days & other.days) == 0 {
+            return false;
+        }
+        // no conflict in day
+        if self.end_time < other.start_time || other.end_time < self.start_time {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Whether any meeting of `a` clashes with any meeting of `b`
+pub fn sections_conflict(a: &scraper::Section, b: &scraper::Section) -> bool {
+    let b_times = b.meeting_times.iter().map(MeetingTime::new).collect_vec();
+    a.meeting_times
+        .iter()
+        .map(MeetingTime::new)
+        .any(|a_time| b_times.contains(&a_time))
+}
+
+/// Whether `a` and `b` are the same type of section in the same course, so that picking
+/// one replaces the other instead of clashing with it
+pub fn same_slot(a: &scraper::Section, b: &scraper::Section) -> bool {
+    a.subject_code == b.subject_code
+        && a.course_code == b.course_code
+        && a.sequence_code.get(..1) == b.sequence_code.get(..1)
+}
+
+/// The pairs of `selected` sections that clash with each other
+pub fn conflicts(selected: &[scraper::Section]) -> Vec<(&scraper::Section, &scraper::Section)> {
+    selected
+        .iter()
+        .tuple_combinations()
+        .filter(|(a, b)| !same_slot(a, b) && sections_conflict(a, b))
+        .collect()
+}
+
+/// The `selected` sections that `section` would clash with if it were picked
+pub fn clashes_with<'a>(
+    section: &scraper::Section,
+    selected: &'a [scraper::Section],
+) -> Vec<&'a scraper::Section> {
+    selected
+        .iter()
+        .filter(|s| !same_slot(section, s) && sections_conflict(section, s))
+        .collect()
+}
+
+/// Groups the sections of `courses` by course and section type, so that one is picked from
+/// each group
+pub fn partition(courses: &[scraper::Course]) -> Vec<Vec<scraper::Section>> {
+    courses
+        .iter()
+        .flat_map(|c| {
+            c.sections
+                .iter()
+                .chunk_by(|&s| {
+                    (
+                        s.subject_code.clone(),
+                        s.course_code.clone(),
+                        s.sequence_code.chars().next(),
+                    )
+                })
+                .into_iter()
+                .map(|(_, chunk)| chunk.cloned().collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
+}
+
+/// Whether a section has no seats left to register in
+pub fn full(section: &scraper::Section) -> bool {
+    section.enrollment >= section.enrollment_capacity || section.waitlist > 0
+}
+
+/// The other sections of `section`'s group that could be picked instead of it without
+/// clashing with the rest of `selected`, best first: ones with open seats, then the
+/// shortest waitlists
+pub fn backups<'a>(
+    section: &scraper::Section,
+    group: &'a [scraper::Section],
+    selected: &[scraper::Section],
+) -> Vec<&'a scraper::Section> {
+    group
+        .iter()
+        .filter(|s| s.crn != section.crn && clashes_with(s, selected).is_empty())
+        .sorted_by_key(|s| {
+            (
+                full(s),
+                s.waitlist,
+                std::cmp::Reverse(s.enrollment_capacity.saturating_sub(s.enrollment)),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::time;
+
+    use super::*;
+    use crate::scraper::{Day, Days, Section};
+
+    fn section(crn: u64, course_code: &str, sequence_code: &str, days: Days) -> Section {
+        Section {
+            crn,
+            subject_code: "CSC".to_string(),
+            course_code: course_code.to_string(),
+            sequence_code: sequence_code.to_string(),
+            enrollment: 0,
+            enrollment_capacity: 0,
+            waitlist: 0,
+            waitlist_capacity: 0,
+            meeting_times: vec![scraper::MeetingTime {
+                start_time: Some(time(10, 0, 0, 0)),
+                end_time: Some(time(11, 20, 0, 0)),
+                start_date: jiff::civil::date(2026, 9, 8),
+                end_date: jiff::civil::date(2026, 12, 4),
+                days,
+                building: None,
+                room: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn clashes_need_the_same_day_time_and_dates() {
+        let monday = section(1, "111", "A01", Day::Monday.into());
+        let tuesday = section(2, "115", "A01", Day::Tuesday.into());
+        let mut later = section(3, "116", "A01", Day::Monday.into());
+        later.meeting_times[0].start_time = Some(time(11, 30, 0, 0));
+        later.meeting_times[0].end_time = Some(time(12, 20, 0, 0));
+        let mut next_term = section(4, "130", "A01", Day::Monday.into());
+        next_term.meeting_times[0].start_date = jiff::civil::date(2027, 1, 5);
+        next_term.meeting_times[0].end_date = jiff::civil::date(2027, 4, 5);
+        let selected = [monday, tuesday, later, next_term];
+        assert!(conflicts(&selected).is_empty());
+
+        let candidate = section(5, "225", "A01", Day::Monday.into());
+        let clashing = clashes_with(&candidate, &selected);
+        assert_eq!(clashing.iter().map(|s| s.crn).collect_vec(), [1]);
+    }
+
+    #[test]
+    fn meetings_without_a_time_never_clash() {
+        let lecture = section(1, "111", "A01", Day::Monday.into());
+        let mut online = section(2, "115", "A01", Day::Monday.into());
+        online.meeting_times[0].start_time = None;
+        online.meeting_times[0].end_time = None;
+        assert!(clashes_with(&online, &[lecture]).is_empty());
+        assert!(!sections_conflict(&online, &online));
+    }
+
+    #[test]
+    fn backups_avoid_clashes_and_prefer_open_seats() {
+        let lecture = section(1, "111", "A01", Day::Monday.into());
+        let full = Section {
+            enrollment: 10,
+            enrollment_capacity: 10,
+            ..section(2, "111", "A02", Day::Tuesday.into())
+        };
+        let open = Section {
+            enrollment_capacity: 10,
+            ..section(3, "111", "A03", Day::Wednesday.into())
+        };
+        let clashing = section(4, "111", "A04", Day::Thursday.into());
+        let other = section(5, "115", "A01", Day::Thursday.into());
+        let group = [lecture.clone(), full, open, clashing];
+        let selected = [lecture.clone(), other];
+
+        let backups = backups(&lecture, &group, &selected);
+        assert_eq!(backups.iter().map(|s| s.crn).collect_vec(), [3, 2]);
+    }
+
+    #[test]
+    fn picking_another_section_of_the_same_type_does_not_clash() {
+        let lab = section(1, "111", "B01", Day::Monday.into());
+        let other_lab = section(2, "111", "B02", Day::Monday.into());
+        let lecture = section(3, "111", "A01", Day::Monday.into());
+        let selected = [lab, lecture];
+        assert!(clashes_with(&other_lab, &selected[..1]).is_empty());
+        assert_eq!(conflicts(&selected).len(), 1);
+    }
+}
@@ -1,13 +1,19 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use auth::DiscordClient;
+use cookies::CookieKeys;
 use google_oauth::AsyncClient;
+use notify::Notifier;
+use oidc::OidcClient;
 use r2d2_sqlite::SqliteConnectionManager;
 use store::DynamoUserStore;
 
-use crate::{common::Stage, scraper::Term};
+use crate::{common::Stage, export::SharedImages, scraper::Term};
 
 pub mod auth;
+pub mod cookies;
+pub mod notify;
+pub mod oidc;
 pub mod store;
 
 #[derive(Clone)]
@@ -16,6 +22,13 @@ pub struct DatabaseAppState
     pub terms: HashMap<Term, r2d2::Pool<SqliteConnectionManager>>,
     pub user_store: DynamoUserStore,
     pub google_client: AsyncClient,
+    /// Not a secret, the login page hands it to Google's sign-in button
+    pub google_client_id: String,
     pub discord_client: DiscordClient,
+    pub oidc_client: Option<Arc<OidcClient>>,
+    pub cookie_keys: CookieKeys,
+    /// Sends the emails that confirm a notification address
+    pub notifier: Arc<Notifier>,
+    pub shared_images: Arc<SharedImages>,
     pub stage: Stage,
 }
\ No newline at end of file
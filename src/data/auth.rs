@@ -1,18 +1,135 @@
+use std::env;
+
+use axum::http::StatusCode;
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use reqwest::Url;
 use serde::{Deserialize, Serialize};
 
 use anyhow::{anyhow, Result};
 use tracing::debug;
+use uuid::Uuid;
+
+use crate::common::Stage;
+
+use super::oidc::OidcConfig;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Authority {
     DISCORD,
     GOOGLE,
+    OIDC,
 }
 
 pub struct GoogleCsrfCookie {
     pub value: String,
 }
 
+/// Holds the `state` we sent Discord so the callback can be tied back to this browser
+pub const OAUTH_STATE_COOKIE: &str = "oauth_state";
+
+/// Set while a logged in user is linking another login to their account, holding their
+/// session id and the OAuth state of the page they started from
+pub const LINK_ACCOUNT_COOKIE: &str = "link_account";
+
+/// Holds a login that already has an account of its own until the user agrees to merge it
+pub const LINK_CONFIRM_COOKIE: &str = "link_confirm";
+
+/// Holds the state, nonce and PKCE verifier of a sign in with the OIDC provider
+pub const OIDC_FLOW_COOKIE: &str = "oidc_flow";
+
+/// Which apps we sign in through, overridable so other deployments can register their own
+#[derive(Clone)]
+pub struct AuthConfig {
+    pub google_client_id: String,
+    pub discord_client_id: String,
+    pub discord_client_secret: String,
+    pub discord_redirect_uri: String,
+    pub oidc: Option<OidcConfig>,
+}
+
+impl AuthConfig {
+    /// Reads `GOOGLE_CLIENT_ID`, `DISCORD_CLIENT_ID`, `DISCORD_SECRET` and
+    /// `DISCORD_REDIRECT_URI`, plus the OIDC provider if there is one, defaulting to our own apps (which only have a secret in prod)
+    pub fn from_env(stage: &Stage) -> AuthConfig {
+        AuthConfig {
+            google_client_id: env::var("GOOGLE_CLIENT_ID").unwrap_or(
+                "839626045148-u695skik1hvq9o41dactp72usr0i9bsh.apps.googleusercontent.com"
+                    .to_string(),
+            ),
+            discord_client_id: env::var("DISCORD_CLIENT_ID")
+                .unwrap_or("1324110828810797108".to_string()),
+            discord_client_secret: env::var("DISCORD_SECRET").unwrap_or("".to_string()),
+            discord_redirect_uri: env::var("DISCORD_REDIRECT_URI")
+                .unwrap_or(format!("{}/login/discord", stage.public_url())),
+            oidc: OidcConfig::from_env(stage),
+        }
+    }
+}
+
+/// Why signing in failed, worded so it can be shown back on the login page
+#[derive(Debug)]
+pub enum AuthError {
+    /// The CSRF token or OAuth state doesn't match the cookie we set, or it's missing
+    StateMismatch,
+    /// The user backed out at the provider, or it refused to sign them in
+    Denied(String),
+    /// The provider's token couldn't be verified or exchanged
+    Provider(anyhow::Error),
+    /// Google didn't tell us an email to show as the username
+    MissingEmail,
+}
+
+impl AuthError {
+    /// Never UNAUTHORIZED, which would bounce to a login page without the message
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AuthError::StateMismatch | AuthError::Provider(_) => StatusCode::BAD_REQUEST,
+            AuthError::Denied(_) | AuthError::MissingEmail => StatusCode::FORBIDDEN,
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            AuthError::StateMismatch => {
+                "Your sign-in expired or was started in another tab. Please try again."
+            }
+            AuthError::Denied(_) => "Sign-in was cancelled.",
+            AuthError::Provider(_) => "We couldn't confirm who you are. Please try again.",
+            AuthError::MissingEmail => {
+                "Your Google account didn't share an email address, which we need to sign you in."
+            }
+        }
+    }
+}
+
+/// A fresh value for the OAuth `state` parameter and the cookie that remembers it
+pub fn new_oauth_state() -> (String, Cookie<'static>) {
+    let state = Uuid::new_v4().simple().to_string();
+    let cookie = Cookie::build((OAUTH_STATE_COOKIE, state.clone()))
+        .http_only(true)
+        .secure(true)
+        // sent along with the top-level redirect back from the provider
+        .same_site(SameSite::Lax)
+        .path("/login")
+        .build();
+    (state, cookie)
+}
+
+pub fn removed_oauth_state_cookie() -> Cookie<'static> {
+    Cookie::build((OAUTH_STATE_COOKIE, ""))
+        .path("/login")
+        .removal()
+        .build()
+}
+
+/// Checks a CSRF token or OAuth state the provider handed back against the cookie
+pub fn check_state(expected: Option<&str>, given: Option<&str>) -> Result<(), AuthError> {
+    match (expected, given) {
+        (Some(expected), Some(given)) if !expected.is_empty() && expected == given => Ok(()),
+        _ => Err(AuthError::StateMismatch),
+    }
+}
+
 #[derive(Clone, Serialize)]
 struct DiscordTokenRequestBody {
     grant_type: String,
@@ -40,6 +157,31 @@ pub struct DiscordClient {
 }
 
 impl DiscordClient {
+    pub fn new(config: &AuthConfig) -> DiscordClient {
+        DiscordClient {
+            http_client: reqwest::Client::new(),
+            redirect_uri: config.discord_redirect_uri.clone(),
+            client_id: config.discord_client_id.clone(),
+            client_secret: config.discord_client_secret.clone(),
+        }
+    }
+
+    /// Where to send the browser to start signing in, carrying `state` back to us
+    pub fn authorize_url(&self, state: &str) -> String {
+        Url::parse_with_params(
+            "https://discord.com/oauth2/authorize",
+            &[
+                ("client_id", self.client_id.as_str()),
+                ("response_type", "code"),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("scope", "identify"),
+                ("state", state),
+            ],
+        )
+        .expect("discord's authorize url is valid")
+        .to_string()
+    }
+
     pub async fn get_user(&self, code: &str) -> Result<DiscordUser> {
         let body = DiscordTokenRequestBody {
             grant_type: "authorization_code".to_owned(),
@@ -76,3 +218,28 @@ impl DiscordClient {
         Ok(user)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_must_match_cookie() {
+        assert!(check_state(Some("abc"), Some("abc")).is_ok());
+        assert!(check_state(Some("abc"), Some("abd")).is_err());
+        assert!(check_state(None, Some("abc")).is_err());
+        assert!(check_state(Some("abc"), None).is_err());
+        assert!(check_state(Some(""), Some("")).is_err());
+
+        let client = DiscordClient::new(&AuthConfig {
+            google_client_id: "google".to_string(),
+            discord_client_id: "discord".to_string(),
+            discord_client_secret: "".to_string(),
+            discord_redirect_uri: "http://localhost:8443/login/discord".to_string(),
+            oidc: None,
+        });
+        let url = client.authorize_url("abc");
+        assert!(url.contains("state=abc"));
+        assert!(url.contains("redirect_uri=http%3A%2F%2Flocalhost%3A8443%2Flogin%2Fdiscord"));
+    }
+}
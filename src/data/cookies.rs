@@ -0,0 +1,139 @@
+use std::{env, sync::OnceLock};
+
+use anyhow::{anyhow, Context, Result};
+use axum::{http::header::SET_COOKIE, response::IntoResponse};
+use axum_extra::extract::{
+    cookie::{Cookie, Key},
+    SignedCookieJar,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use jiff::civil::Date;
+use tracing::warn;
+
+use crate::common::Stage;
+
+/// Keys that cookies we set are signed with. The first signs new cookies; the rest
+/// are still accepted so that rotating keys doesn't log everyone out.
+#[derive(Clone)]
+pub struct CookieKeys {
+    keys: Vec<Key>,
+    /// The last day unsigned schedule cookies from before cookies were signed are accepted,
+    /// if there is one
+    unsigned_schedules_until: Option<Date>,
+}
+
+impl CookieKeys {
+    pub fn new(keys: Vec<Key>) -> Result<CookieKeys> {
+        if keys.is_empty() {
+            return Err(anyhow!("at least one cookie key is needed"));
+        }
+        Ok(CookieKeys {
+            keys,
+            unsigned_schedules_until: None,
+        })
+    }
+
+    /// Reads `COOKIE_KEYS`, a comma separated list of base64 encoded keys of at least
+    /// 64 bytes, newest first. Locally a key is made up if none are given, which
+    /// lasts until the process exits.
+    ///
+    /// `UNSIGNED_SCHEDULES_UNTIL`, a date like `2027-05-01`, stops accepting unsigned
+    /// schedule cookies after that day.
+    pub fn from_env(stage: &Stage) -> Result<CookieKeys> {
+        let mut keys = Self::keys_from_env(stage)?;
+        keys.unsigned_schedules_until = env::var("UNSIGNED_SCHEDULES_UNTIL")
+            .ok()
+            .map(|until| until.parse())
+            .transpose()
+            .context("UNSIGNED_SCHEDULES_UNTIL must be a date like 2027-05-01")?;
+        Ok(keys)
+    }
+
+    fn keys_from_env(stage: &Stage) -> Result<CookieKeys> {
+        match (env::var("COOKIE_KEYS"), stage) {
+            (Ok(keys), _) => CookieKeys::new(
+                keys.split(',')
+                    .map(|k| {
+                        let bytes = STANDARD
+                            .decode(k.trim())
+                            .context("cookie keys must be base64")?;
+                        Key::try_from(bytes.as_slice())
+                            .map_err(|_e| anyhow!("cookie keys must be at least 64 bytes"))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            (Err(_), Stage::LOCAL) => {
+                static DEV_KEY: OnceLock<Key> = OnceLock::new();
+                warn!("COOKIE_KEYS is not set, cookies will not survive a restart");
+                CookieKeys::new(vec![DEV_KEY.get_or_init(Key::generate).clone()])
+            }
+            (Err(_), Stage::PROD) => Err(anyhow!("COOKIE_KEYS must be set in prod")),
+        }
+    }
+
+    pub fn sign(&self, cookie: Cookie<'static>) -> Cookie<'static> {
+        let name = cookie.name().to_string();
+        let response = SignedCookieJar::new(self.keys[0].clone())
+            .add(cookie)
+            .into_response();
+        response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .filter_map(|v| Cookie::parse_encoded(v.to_string()).ok())
+            .find(|c| c.name() == name)
+            .expect("signed jar should set the cookie it was given")
+    }
+
+    /// Whether schedule cookies set before cookies were signed are still signed and kept
+    pub fn accepts_unsigned_schedules(&self, today: Date) -> bool {
+        self.unsigned_schedules_until
+            .is_none_or(|until| today <= until)
+    }
+
+    /// The cookie's original value if any of our keys signed it, and whether it should be
+    /// signed again because that key is no longer the newest
+    pub fn verify(&self, cookie: &Cookie<'static>) -> Option<(Cookie<'static>, bool)> {
+        self.keys.iter().enumerate().find_map(|(i, key)| {
+            SignedCookieJar::new(key.clone())
+                .verify(cookie.clone())
+                .map(|c| (c, i > 0))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotated_keys_still_verify() {
+        let old = CookieKeys::new(vec![Key::generate()]).unwrap();
+        let new = CookieKeys::new(vec![Key::generate(), old.keys[0].clone()]).unwrap();
+
+        let signed = old.sign(Cookie::new("session", "value"));
+        assert_ne!(signed.value(), "value");
+
+        let (verified, stale) = new.verify(&signed).unwrap();
+        assert_eq!(verified.value(), "value");
+        assert!(stale);
+
+        let (_, stale) = new.verify(&new.sign(verified)).unwrap();
+        assert!(!stale);
+
+        let mut tampered = signed.clone();
+        tampered.set_value(format!("{}x", signed.value()));
+        assert!(new.verify(&tampered).is_none());
+        assert!(new.verify(&Cookie::new("session", "value")).is_none());
+    }
+
+    #[test]
+    fn encoded_values_survive_the_round_trip() {
+        let keys = CookieKeys::new(vec![Key::generate()]).unwrap();
+        let signed = keys.sign(Cookie::new("link_account", "session:state"));
+        let sent = Cookie::parse_encoded(signed.encoded().to_string()).unwrap();
+        let (verified, _) = keys.verify(&sent.into_owned()).unwrap();
+        assert_eq!(verified.value(), "session:state");
+    }
+}
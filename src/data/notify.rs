@@ -0,0 +1,260 @@
+use std::{collections::HashMap, env};
+
+use anyhow::{anyhow, Context, Result};
+use itertools::Itertools;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{CreateMessage, UserId},
+    http::Http,
+};
+use tracing::{debug, error, info};
+
+use crate::{
+    common::{ScheduleWithId, Stage},
+    scraper::{diff::Change, report::ScrapeReport, Term},
+};
+
+use super::store::UserStore;
+
+/// Somewhere a notification can be delivered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Contact {
+    Discord { id: u64 },
+    Email { address: String },
+}
+
+impl Contact {
+    /// The contact implied by the logins linked to the user's account, if there is one
+    pub fn from_identities(identities: &[String]) -> Option<Contact> {
+        identities
+            .iter()
+            .filter_map(|identity| identity.strip_prefix("discord_"))
+            .find_map(|id| id.parse().ok())
+            .map(|id| Contact::Discord { id })
+    }
+}
+
+/// Delivers messages over whichever channels are configured in the environment
+pub struct Notifier {
+    discord: Option<Http>,
+    email: Option<(AsyncSmtpTransport<Tokio1Executor>, Mailbox)>,
+}
+
+impl Notifier {
+    /// Discord needs `DISCORD_BOT_TOKEN`; email needs `SMTP_HOST` and `SMTP_FROM`, and
+    /// optionally `SMTP_USERNAME`/`SMTP_PASSWORD`
+    pub fn from_env() -> Result<Notifier> {
+        let discord = env::var("DISCORD_BOT_TOKEN").ok().map(|t| Http::new(&t));
+
+        let email = match (env::var("SMTP_HOST"), env::var("SMTP_FROM")) {
+            (Ok(host), Ok(from)) => {
+                let mut transport = AsyncSmtpTransport::<Tokio1Executor>::relay(&host)
+                    .context("invalid SMTP_HOST")?;
+                if let (Ok(username), Ok(password)) =
+                    (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD"))
+                {
+                    transport = transport.credentials(Credentials::new(username, password));
+                }
+                let from = from.parse().context("invalid SMTP_FROM")?;
+                Some((transport.build(), from))
+            }
+            _ => None,
+        };
+
+        Ok(Notifier { discord, email })
+    }
+
+    pub async fn send(&self, contact: &Contact, subject: &str, body: &str) -> Result<()> {
+        match contact {
+            Contact::Discord { id } => {
+                let http = self
+                    .discord
+                    .as_ref()
+                    .ok_or(anyhow!("discord notifications are not configured"))?;
+                // discord messages are capped at 2000 characters
+                let content: String = format!("**{subject}**\n{body}").chars().take(2000).collect();
+                UserId::new(*id)
+                    .dm(http, CreateMessage::new().content(content))
+                    .await?;
+            }
+            Contact::Email { address } => {
+                let (transport, from) = self
+                    .email
+                    .as_ref()
+                    .ok_or(anyhow!("email notifications are not configured"))?;
+                let message = Message::builder()
+                    .from(from.clone())
+                    .to(address.parse()?)
+                    .subject(subject)
+                    .body(body.to_string())?;
+                transport.send(message).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The notification body for one user, or None if none of their schedules were affected
+fn message_for(
+    schedules: &[ScheduleWithId],
+    changes: &HashMap<Term, Vec<&Change>>,
+    stage: &Stage,
+) -> Option<String> {
+    let sections = schedules
+        .iter()
+        .filter_map(|s| {
+            let crns = s.schedule.selected.crns();
+            let relevant = changes
+                .get(&s.schedule.term)?
+                .iter()
+                .filter(|c| c.crn().is_some_and(|crn| crns.contains(&crn)))
+                .collect::<Vec<_>>();
+            if relevant.is_empty() {
+                return None;
+            }
+            Some(format!(
+                "{} ({}): {}/schedule/{}\n{}",
+                s.schedule.name,
+                s.schedule.term,
+                stage.public_url(),
+                s.id,
+                relevant.iter().map(|c| format!("- {c}")).join("\n")
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    match sections.is_empty() {
+        true => None,
+        false => Some(sections.join("\n\n")),
+    }
+}
+
+/// Tells every user who opted in about changes in `report` to sections they have saved
+pub async fn notify_changes(
+    user_store: &impl UserStore,
+    notifier: &Notifier,
+    report: &ScrapeReport,
+    stage: &Stage,
+) -> Result<()> {
+    let changes: HashMap<Term, Vec<&Change>> = report
+        .changes()
+        .map(|(term, changes)| {
+            (
+                term,
+                changes.iter().filter(|c| c.affects_schedule()).collect(),
+            )
+        })
+        .filter(|(_, changes): &(Term, Vec<&Change>)| !changes.is_empty())
+        .collect();
+    if changes.is_empty() {
+        debug!("no schedule-affecting changes, not notifying anyone");
+        return Ok(());
+    }
+
+    let by_user = user_store
+        .scan_schedules()
+        .await?
+        .into_iter()
+        .filter(|(_, s)| changes.contains_key(&s.schedule.term))
+        .into_group_map();
+
+    for (user_id, schedules) in by_user {
+        let Some(body) = message_for(&schedules, &changes, stage) else {
+            continue;
+        };
+        // one user's lookup failing shouldn't keep everyone else from hearing about it
+        let preferences = match user_store.get_preferences(&user_id).await {
+            Ok(preferences) => preferences,
+            Err(e) => {
+                error!(user_id, "could not read notification preferences: {e:#}");
+                continue;
+            }
+        };
+        let Some(contact) = preferences.contact.filter(|_| preferences.notify_changes) else {
+            continue;
+        };
+        match notifier
+            .send(&contact, "Sections in your schedule changed", &body)
+            .await
+        {
+            Ok(()) => info!(user_id, "sent section change notification"),
+            Err(e) => error!(user_id, "could not send section change notification: {e:#}"),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{Schedule, Selection},
+        scraper::{ThinCourse, ThinSection},
+    };
+
+    fn csc_111() -> ThinCourse {
+        ThinCourse {
+            subject_code: "CSC".to_string(),
+            course_code: "111".to_string(),
+        }
+    }
+
+    fn schedule(id: &str, term: Term, lecture: u64, lab: Option<u64>) -> ScheduleWithId {
+        let mut schedule = Schedule {
+            name: id.to_string(),
+            term,
+            selected: Default::default(),
+            changes_seen_at: None,
+            block_conflicts: false,
+        };
+        schedule.selected.courses.insert(
+            csc_111(),
+            Selection {
+                lecture: ThinSection { crn: lecture },
+                lab: lab.map(|crn| ThinSection { crn }),
+                tutorial: None,
+                hue: None,
+            },
+        );
+        ScheduleWithId {
+            id: id.to_string(),
+            schedule,
+        }
+    }
+
+    #[test]
+    fn only_mentions_affected_schedules() {
+        let term: Term = "202609".parse().unwrap();
+        let cancelled = Change::SectionCancelled {
+            course: csc_111(),
+            crn: 20001,
+            sequence_code: "A01".to_string(),
+        };
+        let changes = HashMap::from([(term, vec![&cancelled])]);
+
+        let affected = schedule("affected", term, 20001, Some(20002));
+        let unaffected = schedule("unaffected", term, 20003, None);
+
+        let body = message_for(&[affected, unaffected.clone()], &changes, &Stage::LOCAL).unwrap();
+        assert!(body.contains("/schedule/affected"));
+        assert!(!body.contains("/schedule/unaffected"));
+
+        assert!(message_for(&[unaffected], &changes, &Stage::LOCAL).is_none());
+    }
+
+    #[test]
+    fn linked_discord_login_is_a_contact() {
+        let identities = ["google_1".to_string(), "discord_42".to_string()];
+        assert_eq!(
+            Contact::from_identities(&identities),
+            Some(Contact::Discord { id: 42 })
+        );
+        assert_eq!(Contact::from_identities(&identities[..1]), None);
+    }
+}
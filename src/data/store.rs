@@ -1,4 +1,4 @@
-use std::{collections::HashMap, ops::DerefMut, path::PathBuf};
+use std::{collections::HashMap, ops::DerefMut, path::PathBuf, sync::Arc};
 
 use aws_sdk_dynamodb::{
     operation::create_table::CreateTableOutput,
@@ -10,6 +10,7 @@ use aws_sdk_dynamodb::{
 };
 
 use anyhow::{anyhow, Context, Result};
+use axum_extra::extract::CookieJar;
 use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
 use google_oauth::AsyncClient;
 use jiff::{Timestamp, ToSpan};
@@ -18,16 +19,25 @@ use regex::bytes::Regex;
 use rusqlite::{params, Connection, OpenFlags};
 use serde::{Deserialize, Serialize};
 use tokio::fs;
-use tracing::{debug, error};
+use tracing::{debug, error, info};
 use uuid::Uuid;
 
 use crate::{
-    common::{Schedule, ScheduleWithId, Selection, Stage},
-    scraper::{Course, Days, MeetingTime, Section, Term, ThinCourse, ThinSection},
+    common::{
+        History, Schedule, ScheduleChanges, ScheduleWithId, SelectedCourses, Selection, Stage,
+        TrashedSchedule,
+    },
+    scraper::{
+        diff::{self, ChangelogEntry},
+        meeting_time_from_row, Course, Section, Term, ThinCourse, ThinSection,
+    },
 };
 
 use super::{
-    auth::{Authority, DiscordClient},
+    auth::{AuthConfig, Authority, DiscordClient},
+    cookies::CookieKeys,
+    notify::{Contact, Notifier},
+    oidc::OidcClient,
     DatabaseAppState,
 };
 
@@ -50,6 +60,132 @@ pub struct User {
     pub schedules: Vec<ScheduleWithId>,
 }
 
+/// What we know about one of a user's logged in devices
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub session_id: String,
+    /// Missing for sessions made before this was recorded
+    pub created_at: Option<Timestamp>,
+    pub last_used: Option<Timestamp>,
+    pub user_agent: Option<String>,
+    pub expires_at: Timestamp,
+}
+
+impl TryFrom<&HashMap<String, AttributeValue>> for SessionInfo {
+    type Error = anyhow::Error;
+
+    fn try_from(item: &HashMap<String, AttributeValue>) -> Result<Self, Self::Error> {
+        let timestamp = |name: &str| -> Option<Timestamp> {
+            let seconds = item.get(name)?.as_n().ok()?.parse().ok()?;
+            Timestamp::from_second(seconds).ok()
+        };
+        Ok(SessionInfo {
+            session_id: item
+                .get("sessionId")
+                .and_then(|v| v.as_s().ok())
+                .ok_or(anyhow!("session has no sessionId"))?
+                .to_string(),
+            created_at: timestamp("createdAt"),
+            last_used: timestamp("lastUsed"),
+            user_agent: item
+                .get("userAgent")
+                .and_then(|v| v.as_s().ok())
+                .cloned(),
+            expires_at: timestamp("expiresAt").ok_or(anyhow!("session has no expiresAt"))?,
+        })
+    }
+}
+
+/// Everything we keep about an account, as handed to the user when they ask for it
+#[derive(Debug, Serialize)]
+pub struct AccountExport {
+    pub exported_at: Timestamp,
+    pub account_id: String,
+    pub identities: Vec<String>,
+    pub preferences: Preferences,
+    pub schedules: Vec<ExportedSchedule>,
+    pub trash: Vec<TrashedSchedule>,
+    pub sessions: Vec<SessionInfo>,
+    pub friends: Vec<Friend>,
+    pub shared_schedules: Vec<SharedSchedule>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportedSchedule {
+    pub id: String,
+    pub schedule: Schedule,
+    pub history: History,
+}
+
+/// Someone the user became friends with through an invite link
+#[derive(Debug, Clone, Serialize)]
+pub struct Friend {
+    pub user_id: String,
+    /// Their name when the friendship was made
+    pub name: String,
+    pub since: Option<Timestamp>,
+}
+
+/// The schedule a user shows their friends for a term
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedSchedule {
+    pub term: Term,
+    pub schedule_id: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Preferences {
+    /// Send a notification when a section in one of the user's schedules changes
+    #[serde(default)]
+    pub notify_changes: bool,
+    /// Where to send notifications
+    #[serde(default)]
+    pub contact: Option<Contact>,
+    /// An email address that becomes the contact once its owner follows the link sent to it
+    #[serde(default)]
+    pub pending_email: Option<PendingEmail>,
+}
+
+/// An email address waiting for its owner to confirm it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingEmail {
+    pub address: String,
+    pub token: String,
+    pub sent_at: Timestamp,
+}
+
+impl PendingEmail {
+    /// How long the link in a confirmation email works for
+    const EXPIRES_AFTER_HOURS: i64 = 24;
+    /// How long to wait before sending another confirmation email, so the form can't be used
+    /// to flood someone's inbox
+    const RESEND_AFTER_MINUTES: i64 = 5;
+
+    pub fn new(address: String) -> PendingEmail {
+        PendingEmail {
+            address,
+            token: Uuid::new_v4().simple().to_string(),
+            sent_at: Timestamp::now(),
+        }
+    }
+
+    /// Whether following a link with `token` at `now` confirms the address
+    pub fn confirms(&self, token: &str, now: Timestamp) -> bool {
+        self.token == token
+            && self
+                .sent_at
+                .checked_add(Self::EXPIRES_AFTER_HOURS.hours())
+                .is_ok_and(|expiry| now < expiry)
+    }
+
+    /// Whether enough time has passed since this was sent to send another
+    pub fn may_resend(&self, now: Timestamp) -> bool {
+        self.sent_at
+            .checked_add(Self::RESEND_AFTER_MINUTES.minutes())
+            .is_ok_and(|next| next <= now)
+    }
+}
+
 pub trait UserStore: Clone {
     fn get_user(&self, user_id: &str) -> impl std::future::Future<Output = Result<User>> + Send;
     fn get_user_schedule(
@@ -63,22 +199,161 @@ pub trait UserStore: Clone {
         schedule_id: &str,
         schedule: &Schedule,
     ) -> impl std::future::Future<Output = Result<Schedule>> + Send;
+    /// Deletes a schedule for good, failing if the user has no such schedule
     fn delete_user_schedule(
         &self,
         user_id: &str,
         schedule_id: &str,
-    ) -> impl std::future::Future<Output = ()> + Send;
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    /// Moves a schedule, with its history, to the user's trash
+    fn trash_user_schedule(
+        &self,
+        user_id: &str,
+        schedule_id: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    /// Moves a schedule back out of the user's trash
+    fn restore_user_schedule(
+        &self,
+        user_id: &str,
+        schedule_id: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    /// Schedules in the user's trash that haven't expired yet
+    fn list_trash(
+        &self,
+        user_id: &str,
+    ) -> impl std::future::Future<Output = Result<Vec<TrashedSchedule>>> + Send;
     fn make_session(
         &self,
         user_id: &str,
         session_id: &str,
         ttl: i64,
+        user_agent: Option<&str>,
     ) -> impl std::future::Future<Output = Result<()>> + Send;
     fn has_session(
         &self,
         user_id: &str,
         session_id: &str,
     ) -> impl std::future::Future<Output = Result<bool>> + Send;
+    fn list_sessions(
+        &self,
+        user_id: &str,
+    ) -> impl std::future::Future<Output = Result<Vec<SessionInfo>>> + Send;
+    fn delete_session(
+        &self,
+        user_id: &str,
+        session_id: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    fn get_schedule_history(
+        &self,
+        user_id: &str,
+        schedule_id: &str,
+    ) -> impl std::future::Future<Output = Result<History>> + Send;
+    fn set_schedule_history(
+        &self,
+        user_id: &str,
+        schedule_id: &str,
+        history: &History,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    fn get_preferences(
+        &self,
+        user_id: &str,
+    ) -> impl std::future::Future<Output = Result<Preferences>> + Send;
+    fn set_preferences(
+        &self,
+        user_id: &str,
+        preferences: &Preferences,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    fn delete_preferences(
+        &self,
+        user_id: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    /// Every schedule of every user, as (user id, schedule) pairs
+    fn scan_schedules(
+        &self,
+    ) -> impl std::future::Future<Output = Result<Vec<(String, ScheduleWithId)>>> + Send;
+    /// Moves every schedule, with its history, from one user to another
+    fn move_schedules(
+        &self,
+        from_user_id: &str,
+        to_user_id: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    /// The account a login like `google_<sub>` belongs to
+    fn get_account(
+        &self,
+        identity: &str,
+    ) -> impl std::future::Future<Output = Result<Option<String>>> + Send;
+    fn link_identity(
+        &self,
+        account_id: &str,
+        identity: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    /// Only forgets that the account lists `identity`, linking it elsewhere repoints it
+    fn unlink_identity(
+        &self,
+        account_id: &str,
+        identity: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    fn list_identities(
+        &self,
+        account_id: &str,
+    ) -> impl std::future::Future<Output = Result<Vec<String>>> + Send;
+    /// Deletes every schedule saved under `user_id`
+    fn delete_user_schedules(
+        &self,
+        user_id: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    /// Deletes every session of `user_id`, including expired ones still lingering
+    fn delete_sessions(&self, user_id: &str) -> impl std::future::Future<Output = Result<()>> + Send;
+    /// Deletes everything in the users table under `user_id`: preferences, linked logins and
+    /// the pointer from a login to its account
+    fn delete_user_items(
+        &self,
+        user_id: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    /// The token of the user's invite link, if they have made one
+    fn get_invite(
+        &self,
+        user_id: &str,
+    ) -> impl std::future::Future<Output = Result<Option<String>>> + Send;
+    /// Replaces the user's invite link, so the old one stops working
+    fn set_invite(
+        &self,
+        user_id: &str,
+        name: &str,
+        token: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    fn delete_invite(&self, user_id: &str) -> impl std::future::Future<Output = Result<()>> + Send;
+    /// Who made the invite link with `token`, as a friend of whoever accepts it
+    fn find_invite(
+        &self,
+        token: &str,
+    ) -> impl std::future::Future<Output = Result<Option<Friend>>> + Send;
+    /// Only adds `friend` to the user's friends, not the user to theirs
+    fn add_friend(
+        &self,
+        user_id: &str,
+        friend: &Friend,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    fn remove_friend(
+        &self,
+        user_id: &str,
+        friend_id: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+    fn list_friends(
+        &self,
+        user_id: &str,
+    ) -> impl std::future::Future<Output = Result<Vec<Friend>>> + Send;
+    fn list_shared(
+        &self,
+        user_id: &str,
+    ) -> impl std::future::Future<Output = Result<Vec<SharedSchedule>>> + Send;
+    /// Shares `schedule_id` with the user's friends for `term`, or stops sharing if None
+    fn set_shared(
+        &self,
+        user_id: &str,
+        term: &Term,
+        schedule_id: Option<&str>,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
 }
 
 #[derive(Clone)]
@@ -86,6 +361,7 @@ pub struct DynamoUserStore {
     ddb_client: aws_sdk_dynamodb::Client,
     sessions_table_name: String,
     schedules_table_name: String,
+    users_table_name: String,
 }
 
 impl DynamoUserStore {
@@ -93,13 +369,199 @@ impl DynamoUserStore {
         ddb_client: Client,
         sessions_table_name: &str,
         schedules_table_name: &str,
+        users_table_name: &str,
     ) -> DynamoUserStore {
         Self {
             ddb_client,
             sessions_table_name: sessions_table_name.to_string(),
             schedules_table_name: schedules_table_name.to_string(),
+            users_table_name: users_table_name.to_string(),
         }
     }
+
+    async fn get_schedule_item(
+        &self,
+        user_id: &str,
+        schedule_id: &str,
+    ) -> Result<Option<HashMap<String, AttributeValue>>> {
+        let result = self
+            .ddb_client
+            .get_item()
+            .table_name(&self.schedules_table_name)
+            .key("userId", AttributeValue::S(user_id.to_string()))
+            .key("scheduleId", AttributeValue::S(schedule_id.to_string()))
+            .consistent_read(true)
+            .send()
+            .await?;
+        Ok(result.item)
+    }
+
+    /// Moves every item in the schedules table from one partition to another
+    async fn move_partition(&self, from_user_id: &str, to_user_id: &str) -> Result<()> {
+        let items = self
+            .ddb_client
+            .query()
+            .table_name(&self.schedules_table_name)
+            .key_condition_expression("#uid = :user_id")
+            .expression_attribute_names("#uid", "userId")
+            .expression_attribute_values(":user_id", AttributeValue::S(from_user_id.to_string()))
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?;
+
+        for mut item in items {
+            let schedule_id = item
+                .get("scheduleId")
+                .cloned()
+                .ok_or(anyhow!("schedule has no scheduleId"))?;
+            item.insert(
+                "userId".to_string(),
+                AttributeValue::S(to_user_id.to_string()),
+            );
+            self.ddb_client
+                .put_item()
+                .table_name(&self.schedules_table_name)
+                .set_item(Some(item))
+                .send()
+                .await?;
+            self.ddb_client
+                .delete_item()
+                .table_name(&self.schedules_table_name)
+                .key("userId", AttributeValue::S(from_user_id.to_string()))
+                .key("scheduleId", schedule_id)
+                .send()
+                .await?;
+        }
+        debug!("moved schedule items from {} to {}", from_user_id, to_user_id);
+        Ok(())
+    }
+
+    /// Deletes every item in `table` whose partition key is `user_id`
+    async fn delete_partition(&self, table: &str, sort_key: &str, user_id: &str) -> Result<()> {
+        let items = self
+            .ddb_client
+            .query()
+            .table_name(table)
+            .key_condition_expression("#uid = :user_id")
+            .expression_attribute_names("#uid", "userId")
+            .expression_attribute_names("#sk", sort_key)
+            .expression_attribute_values(":user_id", AttributeValue::S(user_id.to_string()))
+            .projection_expression("#uid, #sk")
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?;
+
+        for item in &items {
+            let sort_value = item
+                .get(sort_key)
+                .cloned()
+                .ok_or(anyhow!("item in {} has no {}", table, sort_key))?;
+            self.ddb_client
+                .delete_item()
+                .table_name(table)
+                .key("userId", AttributeValue::S(user_id.to_string()))
+                .key(sort_key, sort_value)
+                .send()
+                .await?;
+        }
+        debug!("deleted {} items of {} from {}", items.len(), user_id, table);
+        Ok(())
+    }
+
+    /// The user's items in the users table whose sort key starts with `prefix`
+    async fn user_items_with_prefix(
+        &self,
+        user_id: &str,
+        prefix: &str,
+    ) -> Result<Vec<HashMap<String, AttributeValue>>> {
+        Ok(self
+            .ddb_client
+            .query()
+            .table_name(&self.users_table_name)
+            .key_condition_expression("#uid = :user_id AND begins_with(itemId, :prefix)")
+            .expression_attribute_names("#uid", "userId")
+            .expression_attribute_values(":user_id", AttributeValue::S(user_id.to_string()))
+            .expression_attribute_values(":prefix", AttributeValue::S(prefix.to_string()))
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?)
+    }
+}
+
+/// The partition of the schedules table holding a user's trash, which keeps trashed
+/// schedules out of everything that lists a user's schedules
+fn trash_of(user_id: &str) -> String {
+    format!("{}{}", TRASH_PARTITION_PREFIX, user_id)
+}
+
+const TRASH_PARTITION_PREFIX: &str = "trash#";
+
+/// A schedule's item moved into the user's trash as `trashed`
+fn trash_item(
+    mut item: HashMap<String, AttributeValue>,
+    user_id: &str,
+    trashed: &TrashedSchedule,
+) -> HashMap<String, AttributeValue> {
+    item.insert("userId".to_string(), AttributeValue::S(trash_of(user_id)));
+    item.insert(
+        "deletedAt".to_string(),
+        AttributeValue::N(format!("{}", trashed.deleted_at.as_second())),
+    );
+    // DynamoDB's TTL on the schedules table empties the trash
+    item.insert(
+        "purgeAt".to_string(),
+        AttributeValue::N(format!("{}", trashed.purge_at().as_second())),
+    );
+    item
+}
+
+/// A trashed schedule's item moved back to the user's schedules
+fn restored_item(
+    mut item: HashMap<String, AttributeValue>,
+    user_id: &str,
+) -> HashMap<String, AttributeValue> {
+    item.insert("userId".to_string(), AttributeValue::S(user_id.to_string()));
+    item.remove("deletedAt");
+    item.remove("purgeAt");
+    item
+}
+
+fn trashed_from_item(item: &HashMap<String, AttributeValue>) -> Option<TrashedSchedule> {
+    let seconds = item.get("deletedAt")?.as_n().ok()?.parse().ok()?;
+    Some(TrashedSchedule {
+        id: item.get("scheduleId")?.as_s().ok()?.to_string(),
+        schedule: item.try_into().ok()?,
+        deleted_at: Timestamp::from_second(seconds).ok()?,
+    })
+}
+
+/// Sort key of the item in the users table holding a user's [`Preferences`]
+const PREFERENCES_ITEM: &str = "preferences";
+/// Starts every account id, which never clashes with a login's id since those start with
+/// the provider
+const ACCOUNT_ID_PREFIX: &str = "user_";
+/// Sort key of the item under a login's id pointing at the account it belongs to
+const ACCOUNT_ITEM: &str = "account";
+/// Prefix of the sort keys of the items listing an account's logins
+const IDENTITY_ITEM_PREFIX: &str = "identity#";
+/// Prefix of the sort keys of the items listing a user's friends, followed by their id
+const FRIEND_ITEM_PREFIX: &str = "friend#";
+/// Prefix of the sort keys of the items holding the schedule a user shares for a term
+const SHARE_ITEM_PREFIX: &str = "share#";
+/// Sort key of the item holding the token of a user's invite link, and of the item under
+/// the token pointing back at the user
+const INVITE_ITEM: &str = "invite";
+/// Starts the id an invite link's token is kept under
+const INVITE_PARTITION_PREFIX: &str = "invite_";
+
+fn invite_partition(token: &str) -> String {
+    format!("{}{}", INVITE_PARTITION_PREFIX, token)
 }
 
 impl UserStore for DynamoUserStore {
@@ -188,72 +650,572 @@ impl UserStore for DynamoUserStore {
         serde_json::from_str(s).map_err(|e| anyhow!("failed to deserialize schedule, {e}"))
     }
 
-    async fn delete_user_schedule(&self, user_id: &str, schedule_id: &str) {
-        match self
-            .ddb_client
+    async fn delete_user_schedule(&self, user_id: &str, schedule_id: &str) -> Result<()> {
+        self.ddb_client
+            .delete_item()
+            .table_name(&self.schedules_table_name)
+            .key("userId", AttributeValue::S(user_id.to_string()))
+            .key("scheduleId", AttributeValue::S(schedule_id.to_string()))
+            .condition_expression("attribute_exists(scheduleId)")
+            .send()
+            .await
+            .with_context(|| format!("failed to delete schedule {}:{}", user_id, schedule_id))?;
+        debug!("deleted schedule {}:{}", user_id, schedule_id);
+        Ok(())
+    }
+
+    async fn trash_user_schedule(&self, user_id: &str, schedule_id: &str) -> Result<()> {
+        let item = self
+            .get_schedule_item(user_id, schedule_id)
+            .await?
+            .ok_or(anyhow!("no schedule {}:{} to trash", user_id, schedule_id))?;
+        let trashed = TrashedSchedule {
+            id: schedule_id.to_string(),
+            schedule: (&item).try_into()?,
+            deleted_at: Timestamp::now(),
+        };
+        self.ddb_client
+            .put_item()
+            .table_name(&self.schedules_table_name)
+            .set_item(Some(trash_item(item, user_id, &trashed)))
+            .send()
+            .await?;
+        self.delete_user_schedule(user_id, schedule_id).await
+    }
+
+    async fn restore_user_schedule(&self, user_id: &str, schedule_id: &str) -> Result<()> {
+        let item = self
+            .get_schedule_item(&trash_of(user_id), schedule_id)
+            .await?
+            .ok_or(anyhow!("no schedule {}:{} in the trash", user_id, schedule_id))?;
+        self.ddb_client
+            .put_item()
+            .table_name(&self.schedules_table_name)
+            .set_item(Some(restored_item(item, user_id)))
+            .send()
+            .await?;
+        self.delete_user_schedule(&trash_of(user_id), schedule_id)
+            .await
+    }
+
+    async fn list_trash(&self, user_id: &str) -> Result<Vec<TrashedSchedule>> {
+        let items = self
+            .ddb_client
+            .query()
+            .table_name(&self.schedules_table_name)
+            .key_condition_expression("#uid = :user_id")
+            .expression_attribute_names("#uid", "userId")
+            .expression_attribute_values(":user_id", AttributeValue::S(trash_of(user_id)))
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?;
+
+        Ok(items
+            .iter()
+            .filter_map(trashed_from_item)
+            // expired items linger until DynamoDB gets around to deleting them
+            .filter(|t| !t.is_expired())
+            .collect())
+    }
+
+    async fn make_session(
+        &self,
+        user_id: &str,
+        session_id: &str,
+        ttl: i64,
+        user_agent: Option<&str>,
+    ) -> Result<()> {
+        let now = AttributeValue::N(format!("{}", Timestamp::now().as_second()));
+        let mut request = self
+            .ddb_client
+            .put_item()
+            .table_name(&self.sessions_table_name)
+            .item("userId", AttributeValue::S(user_id.to_string()))
+            .item("sessionId", AttributeValue::S(session_id.to_string()))
+            .item("expiresAt", AttributeValue::N(format!("{}", ttl)))
+            .item("createdAt", now.clone())
+            .item("lastUsed", now);
+        if let Some(user_agent) = user_agent {
+            request = request.item("userAgent", AttributeValue::S(user_agent.to_string()));
+        }
+        match request.send().await {
+            Ok(_r) => {
+                debug!("created session {}:{}", user_id, session_id);
+                Ok(())
+            }
+            Err(err) => {
+                error!(
+                    "failed to make session {}:{}, {}",
+                    user_id,
+                    session_id,
+                    err.to_string()
+                );
+                Err(anyhow!("{}", err))
+            }
+        }
+    }
+
+    async fn has_session(&self, user_id: &str, session_id: &str) -> Result<bool> {
+        let now = Timestamp::now();
+        let ttl = now.checked_add(168.hours())?.as_second();
+        debug!("getting session {}:{}, ttl={}", user_id, session_id, ttl);
+        match self
+            .ddb_client
+            .update_item()
+            .table_name(&self.sessions_table_name)
+            .key("userId", AttributeValue::S(user_id.to_string()))
+            .key("sessionId", AttributeValue::S(session_id.to_string()))
+            .update_expression("SET expiresAt = :ttl, lastUsed = :now")
+            .expression_attribute_values(":ttl", AttributeValue::N(format!("{}", ttl)))
+            .expression_attribute_values(":now", AttributeValue::N(format!("{}", now.as_second())))
+            .expression_attribute_values(":uid", AttributeValue::S(user_id.to_string()))
+            .expression_attribute_values(":sid", AttributeValue::S(session_id.to_string()))
+            // expired rows linger until DynamoDB gets around to deleting them
+            .condition_expression("userId = :uid AND sessionId = :sid AND expiresAt > :now")
+            .return_values(ReturnValue::UpdatedNew)
+            .send()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(_) => Ok(false),
+        }
+    }
+
+    async fn list_sessions(&self, user_id: &str) -> Result<Vec<SessionInfo>> {
+        let items = self
+            .ddb_client
+            .query()
+            .table_name(&self.sessions_table_name)
+            .key_condition_expression("#uid = :user_id")
+            .expression_attribute_names("#uid", "userId")
+            .expression_attribute_values(":user_id", AttributeValue::S(user_id.to_string()))
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?;
+
+        let now = Timestamp::now();
+        Ok(items
+            .iter()
+            .filter_map(|item| SessionInfo::try_from(item).ok())
+            .filter(|s| s.expires_at > now)
+            .collect())
+    }
+
+    async fn delete_session(&self, user_id: &str, session_id: &str) -> Result<()> {
+        self.ddb_client
+            .delete_item()
+            .table_name(&self.sessions_table_name)
+            .key("userId", AttributeValue::S(user_id.to_string()))
+            .key("sessionId", AttributeValue::S(session_id.to_string()))
+            .send()
+            .await?;
+        debug!("deleted session {}:{}", user_id, session_id);
+        Ok(())
+    }
+
+    async fn get_schedule_history(&self, user_id: &str, schedule_id: &str) -> Result<History> {
+        let result = self
+            .ddb_client
+            .get_item()
+            .table_name(&self.schedules_table_name)
+            .key("userId", AttributeValue::S(user_id.to_string()))
+            .key("scheduleId", AttributeValue::S(schedule_id.to_string()))
+            .projection_expression("history")
+            .send()
+            .await?;
+        let Some(raw) = result.item().and_then(|i| i.get("history")) else {
+            return Ok(History::default());
+        };
+        let raw = raw
+            .as_s()
+            .map_err(|_e| anyhow!("could not parse history attribute value to string"))?;
+        Ok(serde_json::from_str(raw)?)
+    }
+
+    async fn set_schedule_history(
+        &self,
+        user_id: &str,
+        schedule_id: &str,
+        history: &History,
+    ) -> Result<()> {
+        // stored beside the schedule so that deleting the schedule deletes its history
+        self.ddb_client
+            .update_item()
+            .table_name(&self.schedules_table_name)
+            .key("userId", AttributeValue::S(user_id.to_string()))
+            .key("scheduleId", AttributeValue::S(schedule_id.to_string()))
+            .update_expression("SET history = :history")
+            .expression_attribute_values(
+                ":history",
+                AttributeValue::S(serde_json::to_string(history)?),
+            )
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    async fn get_preferences(&self, user_id: &str) -> Result<Preferences> {
+        let result = self
+            .ddb_client
+            .get_item()
+            .table_name(&self.users_table_name)
+            .key("userId", AttributeValue::S(user_id.to_string()))
+            .key("itemId", AttributeValue::S(PREFERENCES_ITEM.to_string()))
+            .send()
+            .await?;
+        let Some(raw) = result.item().and_then(|i| i.get("preferences")) else {
+            return Ok(Preferences::default());
+        };
+        let raw = raw
+            .as_s()
+            .map_err(|_e| anyhow!("could not parse preferences attribute value to string"))?;
+        Ok(serde_json::from_str(raw)?)
+    }
+
+    async fn set_preferences(&self, user_id: &str, preferences: &Preferences) -> Result<()> {
+        self.ddb_client
+            .put_item()
+            .table_name(&self.users_table_name)
+            .item("userId", AttributeValue::S(user_id.to_string()))
+            .item("itemId", AttributeValue::S(PREFERENCES_ITEM.to_string()))
+            .item(
+                "preferences",
+                AttributeValue::S(serde_json::to_string(preferences)?),
+            )
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_preferences(&self, user_id: &str) -> Result<()> {
+        self.ddb_client
+            .delete_item()
+            .table_name(&self.users_table_name)
+            .key("userId", AttributeValue::S(user_id.to_string()))
+            .key("itemId", AttributeValue::S(PREFERENCES_ITEM.to_string()))
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    async fn scan_schedules(&self) -> Result<Vec<(String, ScheduleWithId)>> {
+        let items = self
+            .ddb_client
+            .scan()
+            .table_name(&self.schedules_table_name)
+            .into_paginator()
+            .items()
+            .send()
+            .collect::<Result<Vec<_>, _>>()
+            .await?;
+
+        Ok(items
+            .iter()
+            .filter_map(|v| {
+                let schedule: Schedule = v.try_into().ok()?;
+                let user_id = v.get("userId")?.as_s().ok()?;
+                if user_id.starts_with(TRASH_PARTITION_PREFIX) {
+                    return None;
+                }
+                let schedule_id = v.get("scheduleId")?.as_s().ok()?;
+                Some((
+                    user_id.to_string(),
+                    ScheduleWithId {
+                        id: schedule_id.into(),
+                        schedule,
+                    },
+                ))
+            })
+            .collect())
+    }
+
+    async fn move_schedules(&self, from_user_id: &str, to_user_id: &str) -> Result<()> {
+        self.move_partition(from_user_id, to_user_id).await?;
+        self.move_partition(&trash_of(from_user_id), &trash_of(to_user_id))
+            .await
+    }
+
+    async fn get_account(&self, identity: &str) -> Result<Option<String>> {
+        let result = self
+            .ddb_client
+            .get_item()
+            .table_name(&self.users_table_name)
+            .key("userId", AttributeValue::S(identity.to_string()))
+            .key("itemId", AttributeValue::S(ACCOUNT_ITEM.to_string()))
+            .send()
+            .await?;
+        Ok(result
+            .item()
+            .and_then(|i| i.get("accountId"))
+            .and_then(|v| v.as_s().ok())
+            .cloned())
+    }
+
+    async fn link_identity(&self, account_id: &str, identity: &str) -> Result<()> {
+        self.ddb_client
+            .put_item()
+            .table_name(&self.users_table_name)
+            .item("userId", AttributeValue::S(identity.to_string()))
+            .item("itemId", AttributeValue::S(ACCOUNT_ITEM.to_string()))
+            .item("accountId", AttributeValue::S(account_id.to_string()))
+            .send()
+            .await?;
+        self.ddb_client
+            .put_item()
+            .table_name(&self.users_table_name)
+            .item("userId", AttributeValue::S(account_id.to_string()))
+            .item(
+                "itemId",
+                AttributeValue::S(format!("{}{}", IDENTITY_ITEM_PREFIX, identity)),
+            )
+            .item(
+                "linkedAt",
+                AttributeValue::N(format!("{}", Timestamp::now().as_second())),
+            )
+            .send()
+            .await?;
+        debug!("linked {} to {}", identity, account_id);
+        Ok(())
+    }
+
+    async fn unlink_identity(&self, account_id: &str, identity: &str) -> Result<()> {
+        self.ddb_client
+            .delete_item()
+            .table_name(&self.users_table_name)
+            .key("userId", AttributeValue::S(account_id.to_string()))
+            .key(
+                "itemId",
+                AttributeValue::S(format!("{}{}", IDENTITY_ITEM_PREFIX, identity)),
+            )
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    async fn list_identities(&self, account_id: &str) -> Result<Vec<String>> {
+        let items = self
+            .user_items_with_prefix(account_id, IDENTITY_ITEM_PREFIX)
+            .await?;
+        Ok(items
+            .iter()
+            .filter_map(|i| i.get("itemId")?.as_s().ok())
+            .filter_map(|id| id.strip_prefix(IDENTITY_ITEM_PREFIX))
+            .map(|id| id.to_string())
+            .collect())
+    }
+
+    async fn delete_user_schedules(&self, user_id: &str) -> Result<()> {
+        self.delete_partition(&self.schedules_table_name, "scheduleId", user_id)
+            .await?;
+        self.delete_partition(&self.schedules_table_name, "scheduleId", &trash_of(user_id))
+            .await
+    }
+
+    async fn delete_sessions(&self, user_id: &str) -> Result<()> {
+        self.delete_partition(&self.sessions_table_name, "sessionId", user_id)
+            .await
+    }
+
+    async fn delete_user_items(&self, user_id: &str) -> Result<()> {
+        self.delete_partition(&self.users_table_name, "itemId", user_id)
+            .await
+    }
+
+    async fn get_invite(&self, user_id: &str) -> Result<Option<String>> {
+        let result = self
+            .ddb_client
+            .get_item()
+            .table_name(&self.users_table_name)
+            .key("userId", AttributeValue::S(user_id.to_string()))
+            .key("itemId", AttributeValue::S(INVITE_ITEM.to_string()))
+            .send()
+            .await?;
+        Ok(result
+            .item()
+            .and_then(|i| i.get("token"))
+            .and_then(|v| v.as_s().ok())
+            .cloned())
+    }
+
+    async fn set_invite(&self, user_id: &str, name: &str, token: &str) -> Result<()> {
+        self.delete_invite(user_id).await?;
+        self.ddb_client
+            .put_item()
+            .table_name(&self.users_table_name)
+            .item("userId", AttributeValue::S(invite_partition(token)))
+            .item("itemId", AttributeValue::S(INVITE_ITEM.to_string()))
+            .item("inviterId", AttributeValue::S(user_id.to_string()))
+            .item("name", AttributeValue::S(name.to_string()))
+            .send()
+            .await?;
+        self.ddb_client
+            .put_item()
+            .table_name(&self.users_table_name)
+            .item("userId", AttributeValue::S(user_id.to_string()))
+            .item("itemId", AttributeValue::S(INVITE_ITEM.to_string()))
+            .item("token", AttributeValue::S(token.to_string()))
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_invite(&self, user_id: &str) -> Result<()> {
+        let Some(token) = self.get_invite(user_id).await? else {
+            return Ok(());
+        };
+        self.ddb_client
             .delete_item()
-            .table_name(&self.schedules_table_name)
-            .key("user_id", AttributeValue::S(user_id.to_string()))
-            .key("scheduleId", AttributeValue::S(schedule_id.to_string()))
+            .table_name(&self.users_table_name)
+            .key("userId", AttributeValue::S(invite_partition(&token)))
+            .key("itemId", AttributeValue::S(INVITE_ITEM.to_string()))
             .send()
-            .await
-        {
-            Ok(_out) => debug!("deleted schedule {}:{}", user_id, schedule_id),
-            Err(err) => error!(
-                "failed to delete schedule {}:{}, {}",
-                user_id, schedule_id, err
-            ),
-        }
+            .await?;
+        self.ddb_client
+            .delete_item()
+            .table_name(&self.users_table_name)
+            .key("userId", AttributeValue::S(user_id.to_string()))
+            .key("itemId", AttributeValue::S(INVITE_ITEM.to_string()))
+            .send()
+            .await?;
+        Ok(())
     }
 
-    async fn make_session(&self, user_id: &str, session_id: &str, ttl: i64) -> Result<()> {
-        match self
+    async fn find_invite(&self, token: &str) -> Result<Option<Friend>> {
+        let result = self
             .ddb_client
+            .get_item()
+            .table_name(&self.users_table_name)
+            .key("userId", AttributeValue::S(invite_partition(token)))
+            .key("itemId", AttributeValue::S(INVITE_ITEM.to_string()))
+            .send()
+            .await?;
+        let Some(item) = result.item() else {
+            return Ok(None);
+        };
+        let get = |name: &str| -> Result<String> {
+            Ok(item
+                .get(name)
+                .and_then(|v| v.as_s().ok())
+                .ok_or(anyhow!("invite has no {}", name))?
+                .to_string())
+        };
+        Ok(Some(Friend {
+            user_id: get("inviterId")?,
+            name: get("name")?,
+            since: None,
+        }))
+    }
+
+    async fn add_friend(&self, user_id: &str, friend: &Friend) -> Result<()> {
+        let since = friend.since.unwrap_or_else(Timestamp::now);
+        self.ddb_client
             .put_item()
-            .table_name(&self.sessions_table_name)
+            .table_name(&self.users_table_name)
             .item("userId", AttributeValue::S(user_id.to_string()))
-            .item("sessionId", AttributeValue::S(session_id.to_string()))
-            .item("expiresAt", AttributeValue::N(format!("{}", ttl)))
+            .item(
+                "itemId",
+                AttributeValue::S(format!("{}{}", FRIEND_ITEM_PREFIX, friend.user_id)),
+            )
+            .item("name", AttributeValue::S(friend.name.clone()))
+            .item("since", AttributeValue::N(format!("{}", since.as_second())))
             .send()
-            .await
-        {
-            Ok(_r) => {
-                debug!("created session {}:{}", user_id, session_id);
-                Ok(())
-            }
-            Err(err) => {
-                error!(
-                    "failed to make session {}:{}, {}",
-                    user_id,
-                    session_id,
-                    err.to_string()
-                );
-                Err(anyhow!("{}", err))
-            }
-        }
+            .await?;
+        Ok(())
     }
 
-    async fn has_session(&self, user_id: &str, session_id: &str) -> Result<bool> {
-        let ttl = Timestamp::now().checked_add(168.hours())?.as_second();
-        debug!("getting session {}:{}, ttl={}", user_id, session_id, ttl);
-        match self
-            .ddb_client
-            .update_item()
-            .table_name(&self.sessions_table_name)
+    async fn remove_friend(&self, user_id: &str, friend_id: &str) -> Result<()> {
+        self.ddb_client
+            .delete_item()
+            .table_name(&self.users_table_name)
             .key("userId", AttributeValue::S(user_id.to_string()))
-            .key("sessionId", AttributeValue::S(session_id.to_string()))
-            .update_expression("SET expiresAt = :ttl")
-            .expression_attribute_values(":ttl", AttributeValue::N(format!("{}", ttl)))
-            .expression_attribute_values(":uid", AttributeValue::S(user_id.to_string()))
-            .expression_attribute_values(":sid", AttributeValue::S(session_id.to_string()))
-            .condition_expression("userId = :uid AND sessionId = :sid")
-            .return_values(ReturnValue::UpdatedNew)
+            .key(
+                "itemId",
+                AttributeValue::S(format!("{}{}", FRIEND_ITEM_PREFIX, friend_id)),
+            )
             .send()
-            .await
-        {
-            Ok(_) => Ok(true),
-            Err(_) => Ok(false),
+            .await?;
+        Ok(())
+    }
+
+    async fn list_friends(&self, user_id: &str) -> Result<Vec<Friend>> {
+        let items = self
+            .user_items_with_prefix(user_id, FRIEND_ITEM_PREFIX)
+            .await?;
+        Ok(items
+            .iter()
+            .filter_map(|i| {
+                Some(Friend {
+                    user_id: i
+                        .get("itemId")?
+                        .as_s()
+                        .ok()?
+                        .strip_prefix(FRIEND_ITEM_PREFIX)?
+                        .to_string(),
+                    name: i.get("name")?.as_s().ok()?.to_string(),
+                    since: i
+                        .get("since")
+                        .and_then(|v| v.as_n().ok())
+                        .and_then(|n| n.parse().ok())
+                        .and_then(|n| Timestamp::from_second(n).ok()),
+                })
+            })
+            .collect())
+    }
+
+    async fn list_shared(&self, user_id: &str) -> Result<Vec<SharedSchedule>> {
+        let items = self
+            .user_items_with_prefix(user_id, SHARE_ITEM_PREFIX)
+            .await?;
+        Ok(items
+            .iter()
+            .filter_map(|i| {
+                Some(SharedSchedule {
+                    term: i
+                        .get("itemId")?
+                        .as_s()
+                        .ok()?
+                        .strip_prefix(SHARE_ITEM_PREFIX)?
+                        .parse()
+                        .ok()?,
+                    schedule_id: i.get("scheduleId")?.as_s().ok()?.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    async fn set_shared(
+        &self,
+        user_id: &str,
+        term: &Term,
+        schedule_id: Option<&str>,
+    ) -> Result<()> {
+        let item_id = AttributeValue::S(format!("{}{}", SHARE_ITEM_PREFIX, term));
+        match schedule_id {
+            Some(schedule_id) => {
+                self.ddb_client
+                    .put_item()
+                    .table_name(&self.users_table_name)
+                    .item("userId", AttributeValue::S(user_id.to_string()))
+                    .item("itemId", item_id)
+                    .item("scheduleId", AttributeValue::S(schedule_id.to_string()))
+                    .send()
+                    .await?;
+            }
+            None => {
+                self.ddb_client
+                    .delete_item()
+                    .table_name(&self.users_table_name)
+                    .key("userId", AttributeValue::S(user_id.to_string()))
+                    .key("itemId", item_id)
+                    .send()
+                    .await?;
+            }
         }
+        Ok(())
     }
 }
 
@@ -262,7 +1224,9 @@ impl DatabaseAppState {
         dir: PathBuf,
         stage: Stage,
         user_store: DynamoUserStore,
-        discord_secret: &str,
+        auth: AuthConfig,
+        cookie_keys: CookieKeys,
+        notifier: Notifier,
     ) -> Result<Self> {
         let mut terms = HashMap::new();
 
@@ -290,31 +1254,30 @@ impl DatabaseAppState {
             terms.insert(term, pool);
         }
 
-        // not a secret
-        let google_client = AsyncClient::new(
-            "839626045148-u695skik1hvq9o41dactp72usr0i9bsh.apps.googleusercontent.com",
-        );
-
-        let discord_redirect_uri = match stage {
-            Stage::LOCAL => "http://localhost:8443/login/discord".to_string(),
-            Stage::PROD => "https://scheduler.brennanmcmicking.net/login/discord".to_string(),
-        };
-        let discord_client = DiscordClient {
-            http_client: reqwest::Client::new(),
-            redirect_uri: discord_redirect_uri,
-            client_id: "1324110828810797108".to_string(),
-            client_secret: discord_secret.to_string(),
-        };
+        let google_client = AsyncClient::new(&auth.google_client_id);
+        let discord_client = DiscordClient::new(&auth);
+        let oidc_client = auth.oidc.map(|config| Arc::new(OidcClient::new(config)));
 
         Ok(Self {
             terms,
             user_store,
             google_client,
+            google_client_id: auth.google_client_id,
             discord_client,
+            oidc_client,
+            cookie_keys,
+            notifier: Arc::new(notifier),
+            shared_images: Arc::default(),
             stage,
         })
     }
 
+    /// Whether a schedule from an unsigned cookie is something we could have made
+    pub fn is_plausible_schedule(&self, schedule: &Schedule) -> bool {
+        // sections may have been dropped since they were selected, so CRNs aren't checked
+        self.terms.contains_key(&schedule.term) && schedule.name.len() <= 100
+    }
+
     pub fn get_terms(&self) -> Vec<Term> {
         let mut terms: Vec<_> = self.terms.keys().cloned().collect();
         terms.sort();
@@ -328,13 +1291,14 @@ impl DatabaseAppState {
             return Ok(Vec::new());
         };
 
+        // courses that were dropped from Banner since being selected are skipped
         let courses = keys.iter().map(|&ThinCourse {subject_code, course_code}| {
-            conn.query_row_and_then("
+            conn.prepare("
                     SELECT title, campus
                     FROM course
                     WHERE subject_code = ?1 AND course_code = ?2
-                ",
-                (subject_code, course_code), |row| {
+                ")?
+                .query_and_then((subject_code, course_code), |row| {
                     // no N+1 problem when it's in memory
                     let sections = conn.prepare("
                             SELECT crn, sequence_code, enrollment, enrollment_capacity, waitlist, waitlist_capacity
@@ -352,33 +1316,7 @@ impl DatabaseAppState {
                                 FROM meeting_time
                                 WHERE crn = ?1
                                 ")?
-                                .query_and_then((crn,), |row| {
-                                    let start_time: Option<String> = row.get("start_time")?;
-                                    let end_time: Option<String> = row.get("end_time")?;
-
-                                    let start_date: String = row.get("start_date")?;
-                                    let end_date: String = row.get("end_date")?;
-
-                                    Ok(MeetingTime {
-                                        start_time: start_time.map(|s| s.parse()).transpose()?,
-                                        end_time: end_time.map(|s| s.parse()).transpose()?,
-                                        start_date: start_date.parse()?,
-                                        end_date: end_date.parse()?,
-
-                                        days: Days {
-                                            monday: row.get("monday")?,
-                                            tuesday: row.get("tuesday")?,
-                                            wednesday: row.get("wednesday")?,
-                                            thursday: row.get("thursday")?,
-                                            friday: row.get("friday")?,
-                                            saturday: row.get("saturday")?,
-                                            sunday: row.get("sunday")?,
-                                        },
-
-                                        building: row.get("building")?,
-                                        room: row.get("room")?,
-                                    })
-                                })?.collect::<Result<Vec<_>>>()?;
+                                .query_and_then((crn,), meeting_time_from_row)?.collect::<Result<Vec<_>>>()?;
 
                             Ok(Section {
                                 crn,
@@ -400,10 +1338,12 @@ impl DatabaseAppState {
                        campus: row.get("campus")?,
                        sections,
                     })
-                })
+                })?
+                .next()
+                .transpose()
             }).collect::<Result<Vec<_>>>()?;
 
-        Ok(courses)
+        Ok(courses.into_iter().flatten().collect())
     }
 
     pub fn thin_courses(&self, term: Term) -> Result<Vec<ThinCourse>> {
@@ -453,7 +1393,12 @@ impl DatabaseAppState {
         Ok(courses)
     }
 
-    pub fn default_thin_sections(&self, term: &Term, course: ThinCourse) -> Result<Selection> {
+    /// The first sections of each type of `course`, or None if it has no lectures in `term`
+    pub fn default_thin_sections(
+        &self,
+        term: &Term,
+        course: ThinCourse,
+    ) -> Result<Option<Selection>> {
         let conn = self
             .get_conn(term)
             .context("failed to get conn from pool")?;
@@ -470,19 +1415,16 @@ impl DatabaseAppState {
         .context("query failed")?
         .collect::<anyhow::Result<Vec<_>>>()?;
 
-        let lecture = sections
+        let Some(lecture) = sections
             .iter()
             .filter(|s| s.0.starts_with("A"))
             .map(|(_, ts)| ts.clone())
             .collect::<Vec<_>>()
             .first()
             .cloned()
-            .unwrap_or_else(|| {
-                panic!(
-                    "Expected to find lecture section for course {} {}",
-                    &course.subject_code, &course.course_code
-                )
-            });
+        else {
+            return Ok(None);
+        };
 
         let lab = sections
             .iter()
@@ -500,11 +1442,36 @@ impl DatabaseAppState {
             .first()
             .cloned();
 
-        Ok(Selection {
+        Ok(Some(Selection {
             lecture,
             lab,
             tutorial,
-        })
+            hue: None,
+        }))
+    }
+
+    /// `selected` as it would be in `term`, starting from the default sections of each
+    /// course, along with the courses that aren't offered there
+    pub fn carry_over(
+        &self,
+        selected: &SelectedCourses,
+        term: &Term,
+    ) -> Result<(SelectedCourses, Vec<ThinCourse>)> {
+        let mut carried = SelectedCourses::default();
+        let mut missing = Vec::new();
+        let hues = selected.hues();
+        for course in selected.courses.keys() {
+            match self.default_thin_sections(term, course.clone())? {
+                Some(selection) => {
+                    let hue = hues.get(course).copied();
+                    carried
+                        .courses
+                        .insert(course.clone(), Selection { hue, ..selection });
+                }
+                None => missing.push(course.clone()),
+            }
+        }
+        Ok((carried, missing))
     }
 
     pub fn get_section(&self, term: &Term, section: &ThinSection) -> Result<Section> {
@@ -523,33 +1490,7 @@ impl DatabaseAppState {
             WHERE crn = ?1
             ",
             )?
-            .query_and_then((section.crn,), |row| {
-                let start_time: Option<String> = row.get("start_time")?;
-                let end_time: Option<String> = row.get("end_time")?;
-
-                let start_date: String = row.get("start_date")?;
-                let end_date: String = row.get("end_date")?;
-
-                Ok(MeetingTime {
-                    start_time: start_time.map(|s| s.parse()).transpose()?,
-                    end_time: end_time.map(|s| s.parse()).transpose()?,
-                    start_date: start_date.parse()?,
-                    end_date: end_date.parse()?,
-
-                    days: Days {
-                        monday: row.get("monday")?,
-                        tuesday: row.get("tuesday")?,
-                        wednesday: row.get("wednesday")?,
-                        thursday: row.get("thursday")?,
-                        friday: row.get("friday")?,
-                        saturday: row.get("saturday")?,
-                        sunday: row.get("sunday")?,
-                    },
-
-                    building: row.get("building")?,
-                    room: row.get("room")?,
-                })
-            })?
+            .query_and_then((section.crn,), meeting_time_from_row)?
             .collect::<Result<Vec<_>>>()?;
 
         let result = db
@@ -583,6 +1524,50 @@ impl DatabaseAppState {
         Ok(result)
     }
 
+    /// Newest changes Banner made to `term`, as recorded by the scraper
+    pub fn changelog(&self, term: &Term, limit: u32) -> Result<Vec<ChangelogEntry>> {
+        let Some(conn) = self.get_conn(term) else {
+            return Ok(Vec::new());
+        };
+        diff::read_changelog(&conn, limit)
+    }
+
+    /// Compares `schedule` against `courses`, the currently scraped courses it selects from
+    pub fn schedule_changes(
+        &self,
+        schedule: &Schedule,
+        courses: &[Course],
+    ) -> Result<ScheduleChanges> {
+        let crns = schedule.selected.crns();
+        let missing = schedule
+            .selected
+            .courses
+            .iter()
+            .flat_map(|(course, selection)| {
+                selection
+                    .crns()
+                    .into_iter()
+                    .map(move |crn| (course.clone(), crn))
+            })
+            .filter(|(_, crn)| {
+                !courses
+                    .iter()
+                    .flat_map(|c| &c.sections)
+                    .any(|s| s.crn == *crn)
+            })
+            .collect();
+
+        let changes = match self.get_conn(&schedule.term) {
+            Some(conn) => diff::read_changes_for(&conn, &crns, schedule.changes_seen_at)?
+                .into_iter()
+                .filter(|entry| entry.change.affects_schedule())
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Ok(ScheduleChanges { missing, changes })
+    }
+
     pub fn get_conn(&self, term: &Term) -> Option<impl DerefMut<Target = Connection>> {
         self.terms.get(term).and_then(|p| p.get().ok())
     }
@@ -608,21 +1593,353 @@ impl DatabaseAppState {
             .await
     }
 
-    pub async fn delete_user_schedule(&self, user_id: &str, schedule_id: &str) {
+    pub async fn get_schedule_history(&self, user_id: &str, schedule_id: &str) -> Result<History> {
+        self.user_store
+            .get_schedule_history(user_id, schedule_id)
+            .await
+    }
+
+    /// Saves `schedule` and its `history` to the user's account, or into cookies if
+    /// they aren't logged in. The returned jar has any cookies that need to be set.
+    pub async fn save_schedule(
+        &self,
+        session: Option<&Session>,
+        schedule_id: &str,
+        schedule: &Schedule,
+        history: &History,
+    ) -> Result<CookieJar> {
+        match session {
+            Some(sess) => {
+                self.user_store
+                    .set_user_schedule(&sess.user_id, schedule_id, schedule)
+                    .await?;
+                self.user_store
+                    .set_schedule_history(&sess.user_id, schedule_id, history)
+                    .await?;
+                Ok(CookieJar::new())
+            }
+            None => Ok(CookieJar::new()
+                .add(schedule.make_cookie(schedule_id.to_string()))
+                .add(history.make_cookie(schedule_id))),
+        }
+    }
+
+    pub async fn trash_user_schedule(&self, user_id: &str, schedule_id: &str) -> Result<()> {
+        self.user_store
+            .trash_user_schedule(user_id, schedule_id)
+            .await
+    }
+
+    pub async fn restore_user_schedule(&self, user_id: &str, schedule_id: &str) -> Result<()> {
         self.user_store
-            .delete_user_schedule(user_id, schedule_id)
-            .await;
+            .restore_user_schedule(user_id, schedule_id)
+            .await
+    }
+
+    /// The user's trash, most recently deleted first
+    pub async fn list_trash(&self, user_id: &str) -> Result<Vec<TrashedSchedule>> {
+        let mut trash = self.user_store.list_trash(user_id).await?;
+        trash.sort_by_key(|t| std::cmp::Reverse(t.deleted_at));
+        Ok(trash)
+    }
+
+    pub async fn get_preferences(&self, user_id: &str) -> Result<Preferences> {
+        self.user_store.get_preferences(user_id).await
+    }
+
+    pub async fn set_preferences(&self, user_id: &str, preferences: &Preferences) -> Result<()> {
+        self.user_store.set_preferences(user_id, preferences).await
     }
 
-    pub async fn make_session(&self, user_id: &str) -> Result<String> {
+    pub async fn make_session(&self, user_id: &str, user_agent: Option<&str>) -> Result<String> {
         let session_id = Uuid::new_v4().to_string();
         let ttl = Timestamp::now().checked_add(168.hours())?;
         self.user_store
-            .make_session(user_id, &session_id, ttl.as_second())
+            .make_session(user_id, &session_id, ttl.as_second(), user_agent)
             .await
             .map(|_r| session_id)
     }
 
+    /// The user's unexpired sessions, most recently used first
+    pub async fn list_sessions(&self, user_id: &str) -> Result<Vec<SessionInfo>> {
+        let mut sessions = self.user_store.list_sessions(user_id).await?;
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.last_used));
+        Ok(sessions)
+    }
+
+    pub async fn revoke_session(&self, user_id: &str, session_id: &str) -> Result<()> {
+        self.user_store.delete_session(user_id, session_id).await
+    }
+
+    pub async fn revoke_all_sessions(&self, user_id: &str) -> Result<()> {
+        for session in self.user_store.list_sessions(user_id).await? {
+            self.user_store
+                .delete_session(user_id, &session.session_id)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// The account a login like `google_<sub>` belongs to, making one the first time the
+    /// login is used. Account ids are their own account.
+    pub async fn account_for(&self, identity: &str) -> Result<String> {
+        if identity.starts_with(ACCOUNT_ID_PREFIX) {
+            return Ok(identity.to_string());
+        }
+        if let Some(account_id) = self.user_store.get_account(identity).await? {
+            return Ok(account_id);
+        }
+        let account_id = format!("{}{}", ACCOUNT_ID_PREFIX, Uuid::new_v4());
+        self.link_identity(&account_id, identity).await?;
+        Ok(account_id)
+    }
+
+    /// Whether `identity` already has an account other than `account_id`, which linking it
+    /// would merge away
+    pub async fn belongs_to_other_account(&self, account_id: &str, identity: &str) -> Result<bool> {
+        Ok(self
+            .user_store
+            .get_account(identity)
+            .await?
+            .is_some_and(|existing| existing != account_id))
+    }
+
+    /// Links a login to an account, bringing along whatever was saved under it before:
+    /// either the login's own schedules from before there were accounts, or a whole
+    /// other account, which is merged into this one.
+    ///
+    /// A merge is many writes, any of which can fail. Everything is moved before `identity`
+    /// is repointed, and each step picks up where an earlier attempt stopped, so linking
+    /// again finishes the merge.
+    pub async fn link_identity(&self, account_id: &str, identity: &str) -> Result<()> {
+        let (from, identities) = match self.user_store.get_account(identity).await? {
+            Some(existing) if existing == account_id => return Ok(()),
+            Some(existing) => {
+                let identities = self.user_store.list_identities(&existing).await?;
+                (existing, identities)
+            }
+            None => (identity.to_string(), vec![identity.to_string()]),
+        };
+
+        self.user_store.move_schedules(&from, account_id).await?;
+        let preferences = self.get_preferences(account_id).await?;
+        if !preferences.notify_changes && preferences.contact.is_none() {
+            let old = self.get_preferences(&from).await?;
+            self.set_preferences(account_id, &old).await?;
+        }
+        self.user_store.delete_preferences(&from).await?;
+        self.move_friends(&from, account_id).await?;
+        // anything still logged in as the old id would find nothing there
+        self.revoke_all_sessions(&from).await?;
+
+        // until `identity` itself points here, linking it again redoes the merge
+        let (others, this): (Vec<_>, Vec<_>) =
+            identities.into_iter().partition(|id| id != identity);
+        for identity in others.into_iter().chain(this) {
+            self.user_store.link_identity(account_id, &identity).await?;
+            self.user_store.unlink_identity(&from, &identity).await?;
+        }
+        info!("merged {} into account {}", from, account_id);
+        Ok(())
+    }
+
+    /// Hands `from`'s friends, and the schedules it shares with them, over to `to`
+    async fn move_friends(&self, from: &str, to: &str) -> Result<()> {
+        for friend in self.user_store.list_friends(from).await? {
+            // what the friend knows `from` as
+            let them_to_us = self
+                .user_store
+                .list_friends(&friend.user_id)
+                .await?
+                .into_iter()
+                .find(|f| f.user_id == from);
+            self.user_store.remove_friend(from, &friend.user_id).await?;
+            self.user_store.remove_friend(&friend.user_id, from).await?;
+            // merging two accounts that were friends leaves nobody to be friends with
+            if friend.user_id == to {
+                continue;
+            }
+            self.user_store.add_friend(to, &friend).await?;
+            if let Some(them_to_us) = them_to_us {
+                let us = Friend {
+                    user_id: to.to_string(),
+                    ..them_to_us
+                };
+                self.user_store.add_friend(&friend.user_id, &us).await?;
+            }
+        }
+
+        let already_shared = self.user_store.list_shared(to).await?;
+        for shared in self.user_store.list_shared(from).await? {
+            if !already_shared.iter().any(|s| s.term == shared.term) {
+                self.user_store
+                    .set_shared(to, &shared.term, Some(&shared.schedule_id))
+                    .await?;
+            }
+            self.user_store.set_shared(from, &shared.term, None).await?;
+        }
+        self.user_store.delete_invite(from).await
+    }
+
+    pub async fn export_account(&self, account_id: &str) -> Result<AccountExport> {
+        let mut schedules = Vec::new();
+        for s in self.user_store.get_user(account_id).await?.schedules {
+            schedules.push(ExportedSchedule {
+                history: self.get_schedule_history(account_id, &s.id).await?,
+                id: s.id,
+                schedule: s.schedule,
+            });
+        }
+        Ok(AccountExport {
+            exported_at: Timestamp::now(),
+            account_id: account_id.to_string(),
+            identities: self.list_identities(account_id).await?,
+            preferences: self.get_preferences(account_id).await?,
+            schedules,
+            trash: self.list_trash(account_id).await?,
+            sessions: self.list_sessions(account_id).await?,
+            friends: self.list_friends(account_id).await?,
+            shared_schedules: self.user_store.list_shared(account_id).await?,
+        })
+    }
+
+    /// Deletes the account and everything under it, and under each login linked to it
+    pub async fn delete_account(&self, account_id: &str) -> Result<()> {
+        // a login's own id holds its pointer to the account, plus anything from before
+        // there were accounts that wasn't moved over
+        let mut user_ids = self.list_identities(account_id).await?;
+        if !user_ids.iter().any(|id| id == account_id) {
+            user_ids.push(account_id.to_string());
+        }
+        for user_id in &user_ids {
+            for friend in self.user_store.list_friends(user_id).await? {
+                self.user_store
+                    .remove_friend(&friend.user_id, user_id)
+                    .await?;
+            }
+            self.user_store.delete_invite(user_id).await?;
+            self.user_store.delete_user_schedules(user_id).await?;
+            self.user_store.delete_user_items(user_id).await?;
+            self.user_store.delete_sessions(user_id).await?;
+        }
+        info!("deleted account {}", account_id);
+        Ok(())
+    }
+
+    /// The logins that can be used to get into an account. Sessions from before there
+    /// were accounts are logged in with the login's own id, so that is its only one.
+    pub async fn list_identities(&self, account_id: &str) -> Result<Vec<String>> {
+        let mut identities = self.user_store.list_identities(account_id).await?;
+        if identities.is_empty() {
+            identities.push(account_id.to_string());
+        }
+        identities.sort();
+        Ok(identities)
+    }
+
+    /// The token of the user's invite link, making one the first time it's asked for
+    pub async fn invite_token(&self, user_id: &str, name: &str) -> Result<String> {
+        match self.user_store.get_invite(user_id).await? {
+            Some(token) => Ok(token),
+            None => self.new_invite_token(user_id, name).await,
+        }
+    }
+
+    /// Replaces the user's invite link, for when the old one got around too far
+    pub async fn new_invite_token(&self, user_id: &str, name: &str) -> Result<String> {
+        let token = Uuid::new_v4().simple().to_string();
+        self.user_store.set_invite(user_id, name, &token).await?;
+        Ok(token)
+    }
+
+    /// Who made the invite link with `token`, if it still works
+    pub async fn find_invite(&self, token: &str) -> Result<Option<Friend>> {
+        self.user_store.find_invite(token).await
+    }
+
+    /// Makes the user friends with whoever made the invite link, returning them, or None
+    /// if the link no longer works or is the user's own
+    pub async fn accept_invite(
+        &self,
+        user_id: &str,
+        name: &str,
+        token: &str,
+    ) -> Result<Option<Friend>> {
+        let Some(inviter) = self.find_invite(token).await? else {
+            return Ok(None);
+        };
+        if inviter.user_id == user_id {
+            return Ok(None);
+        }
+        let since = Some(Timestamp::now());
+        let inviter = Friend { since, ..inviter };
+        self.user_store.add_friend(user_id, &inviter).await?;
+        self.user_store
+            .add_friend(
+                &inviter.user_id,
+                &Friend {
+                    user_id: user_id.to_string(),
+                    name: name.to_string(),
+                    since,
+                },
+            )
+            .await?;
+        info!("{} and {} are now friends", user_id, inviter.user_id);
+        Ok(Some(inviter))
+    }
+
+    pub async fn list_friends(&self, user_id: &str) -> Result<Vec<Friend>> {
+        let mut friends = self.user_store.list_friends(user_id).await?;
+        friends.sort_by_key(|f| f.name.to_lowercase());
+        Ok(friends)
+    }
+
+    /// Unfriends both ways, so neither sees the other's schedules anymore
+    pub async fn remove_friend(&self, user_id: &str, friend_id: &str) -> Result<()> {
+        self.user_store.remove_friend(user_id, friend_id).await?;
+        self.user_store.remove_friend(friend_id, user_id).await
+    }
+
+    pub async fn list_shared(&self, user_id: &str) -> Result<Vec<SharedSchedule>> {
+        self.user_store.list_shared(user_id).await
+    }
+
+    /// Shares one of the user's schedules with their friends for `term`, or stops sharing
+    /// anything for it if `schedule_id` is None
+    pub async fn set_shared(
+        &self,
+        user_id: &str,
+        term: &Term,
+        schedule_id: Option<&str>,
+    ) -> Result<()> {
+        self.user_store.set_shared(user_id, term, schedule_id).await
+    }
+
+    /// What each of the user's friends shares for `term`, skipping friends who share
+    /// nothing or whose shared schedule has since been deleted
+    pub async fn friends_schedules(
+        &self,
+        user_id: &str,
+        term: &Term,
+    ) -> Result<Vec<(Friend, Schedule)>> {
+        let mut schedules = Vec::new();
+        for friend in self.list_friends(user_id).await? {
+            let shared = self.user_store.list_shared(&friend.user_id).await?;
+            let Some(shared) = shared.into_iter().find(|s| s.term == *term) else {
+                continue;
+            };
+            match self
+                .user_store
+                .get_user_schedule(&friend.user_id, &shared.schedule_id)
+                .await
+            {
+                Ok(schedule) => schedules.push((friend, schedule)),
+                Err(e) => debug!("{} shares a schedule that is gone: {:?}", friend.user_id, e),
+            }
+        }
+        Ok(schedules)
+    }
+
     pub async fn is_valid_session(&self, user_id: &str, session_id: &str) -> bool {
         self.user_store
             .has_session(user_id, session_id)
@@ -709,3 +2026,217 @@ impl TryFrom<&HashMap<String, AttributeValue>> for Schedule {
         Ok(serde_json::from_str(raw)?)
     }
 }
+
+/// The ignored ones run against a local DynamoDB (see the README) to exercise the real key
+/// schema: `cargo test -- --ignored`
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::make_user_store;
+
+    async fn store() -> DynamoUserStore {
+        make_user_store(&Stage::LOCAL, true).await
+    }
+
+    fn user() -> String {
+        format!("test_{}", Uuid::new_v4())
+    }
+
+    fn schedule(name: &str) -> Schedule {
+        Schedule {
+            name: name.to_string(),
+            term: "202609".parse().unwrap(),
+            selected: Default::default(),
+            changes_seen_at: None,
+            block_conflicts: false,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DynamoDB on localhost:8000"]
+    async fn delete_removes_the_schedule() {
+        let store = store().await;
+        let user = user();
+        store
+            .set_user_schedule(&user, "a", &schedule("a"))
+            .await
+            .unwrap();
+
+        store.delete_user_schedule(&user, "a").await.unwrap();
+        assert!(store.get_user(&user).await.unwrap().schedules.is_empty());
+        // nothing left to delete
+        assert!(store.delete_user_schedule(&user, "a").await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DynamoDB on localhost:8000"]
+    async fn merged_away_preferences_are_deleted() {
+        let store = store().await;
+        let user = user();
+        let preferences = Preferences {
+            notify_changes: true,
+            ..Default::default()
+        };
+        store.set_preferences(&user, &preferences).await.unwrap();
+
+        store.delete_preferences(&user).await.unwrap();
+        assert!(!store.get_preferences(&user).await.unwrap().notify_changes);
+        // deleting again, as a retried merge would, is fine
+        store.delete_preferences(&user).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DynamoDB on localhost:8000"]
+    async fn trash_and_restore() {
+        let store = store().await;
+        let user = user();
+        store
+            .set_user_schedule(&user, "a", &schedule("a"))
+            .await
+            .unwrap();
+        let mut history = History::default();
+        history.record(
+            &SelectedCourses::default(),
+            &SelectedCourses::default(),
+            "nothing".to_string(),
+        );
+        store.set_schedule_history(&user, "a", &history).await.unwrap();
+
+        store.trash_user_schedule(&user, "a").await.unwrap();
+        assert!(store.get_user(&user).await.unwrap().schedules.is_empty());
+        let trash = store.list_trash(&user).await.unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].schedule.name, "a");
+        assert!(store
+            .scan_schedules()
+            .await
+            .unwrap()
+            .iter()
+            .all(|(id, _)| !id.contains(&user)));
+
+        store.restore_user_schedule(&user, "a").await.unwrap();
+        assert!(store.list_trash(&user).await.unwrap().is_empty());
+        assert_eq!(store.get_user(&user).await.unwrap().schedules.len(), 1);
+        let restored = store.get_schedule_history(&user, "a").await.unwrap();
+        assert_eq!(restored.revisions.len(), history.revisions.len());
+
+        store.delete_user_schedules(&user).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DynamoDB on localhost:8000"]
+    async fn cannot_trash_someone_elses_schedule() {
+        let store = store().await;
+        let (owner, other) = (user(), user());
+        store
+            .set_user_schedule(&owner, "a", &schedule("a"))
+            .await
+            .unwrap();
+
+        assert!(store.trash_user_schedule(&other, "a").await.is_err());
+        assert!(store.restore_user_schedule(&other, "a").await.is_err());
+        assert_eq!(store.get_user(&owner).await.unwrap().schedules.len(), 1);
+        assert!(store.list_trash(&other).await.unwrap().is_empty());
+
+        store.delete_user_schedules(&owner).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DynamoDB on localhost:8000"]
+    async fn invites_only_work_until_replaced() {
+        let store = store().await;
+        let user = user();
+        store.set_invite(&user, "alice", "first").await.unwrap();
+        let inviter = store.find_invite("first").await.unwrap().unwrap();
+        assert_eq!(inviter.user_id, user);
+        assert_eq!(inviter.name, "alice");
+
+        store.set_invite(&user, "alice", "second").await.unwrap();
+        assert!(store.find_invite("first").await.unwrap().is_none());
+        assert_eq!(store.get_invite(&user).await.unwrap().unwrap(), "second");
+
+        store.delete_invite(&user).await.unwrap();
+        assert!(store.find_invite("second").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DynamoDB on localhost:8000"]
+    async fn friends_and_shared_schedules() {
+        let store = store().await;
+        let (alice, bob) = (user(), user());
+        let friend = Friend {
+            user_id: bob.clone(),
+            name: "bob".to_string(),
+            since: None,
+        };
+        store.add_friend(&alice, &friend).await.unwrap();
+        let friends = store.list_friends(&alice).await.unwrap();
+        assert_eq!(friends.len(), 1);
+        assert_eq!(friends[0].user_id, bob);
+        assert!(friends[0].since.is_some());
+        // friendships are one way in the store
+        assert!(store.list_friends(&bob).await.unwrap().is_empty());
+
+        store.remove_friend(&alice, &bob).await.unwrap();
+        assert!(store.list_friends(&alice).await.unwrap().is_empty());
+
+        let term: Term = "202609".parse().unwrap();
+        store.set_shared(&alice, &term, Some("a")).await.unwrap();
+        let shared = store.list_shared(&alice).await.unwrap();
+        assert_eq!(shared.len(), 1);
+        assert_eq!((shared[0].term, shared[0].schedule_id.as_str()), (term, "a"));
+
+        store.set_shared(&alice, &term, None).await.unwrap();
+        assert!(store.list_shared(&alice).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn trashing_moves_items_to_an_expiring_partition() {
+        let item = HashMap::from([
+            (
+                "userId".to_string(),
+                AttributeValue::S("user_a".to_string()),
+            ),
+            ("scheduleId".to_string(), AttributeValue::S("s".to_string())),
+            (
+                "schedule".to_string(),
+                AttributeValue::S(serde_json::to_string(&schedule("a")).unwrap()),
+            ),
+        ]);
+        // DynamoDB keeps whole seconds
+        let deleted_at =
+            Timestamp::from_second(Timestamp::now().as_second()).unwrap() - 31.hours() * 24;
+        let trashed = TrashedSchedule {
+            id: "s".to_string(),
+            schedule: schedule("a"),
+            deleted_at,
+        };
+
+        let trash = trash_item(item.clone(), "user_a", &trashed);
+        assert_eq!(trash["userId"].as_s().unwrap(), "trash#user_a");
+        let purge_at: i64 = trash["purgeAt"].as_n().unwrap().parse().unwrap();
+        assert_eq!(purge_at - deleted_at.as_second(), 30 * 24 * 60 * 60);
+
+        let listed = trashed_from_item(&trash).unwrap();
+        assert_eq!((listed.id.as_str(), listed.deleted_at), ("s", deleted_at));
+        assert_eq!(listed.schedule.name, "a");
+        // long since purged, even if DynamoDB hasn't deleted it yet
+        assert!(listed.is_expired());
+        // the user's own schedules never look like they are in the trash
+        assert!(trashed_from_item(&item).is_none());
+
+        assert_eq!(restored_item(trash, "user_a"), item);
+    }
+
+    #[test]
+    fn pending_emails_expire_and_limit_resends() {
+        let pending = PendingEmail::new("someone@example.com".to_string());
+        let sent = pending.sent_at;
+        assert!(pending.confirms(&pending.token, sent.checked_add(1.hours()).unwrap()));
+        assert!(!pending.confirms("not the token", sent));
+        assert!(!pending.confirms(&pending.token, sent.checked_add(25.hours()).unwrap()));
+
+        assert!(!pending.may_resend(sent.checked_add(1.minutes()).unwrap()));
+        assert!(pending.may_resend(sent.checked_add(5.minutes()).unwrap()));
+    }
+}
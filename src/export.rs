@@ -0,0 +1,426 @@
+//! Timetables in a form that leaves the app, for registering in Banner or putting on a wall
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use anyhow::anyhow;
+use anyhow::Result;
+use itertools::Itertools;
+use jiff::civil::{Date, Time};
+use printpdf::{
+    BuiltinFont, Color, IndirectFontRef, Line, Mm, PdfDocument, PdfLayerReference, Point, Rect, Rgb,
+};
+use resvg::{tiny_skia, usvg};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::scraper::{Day, MeetingTime, Section, Term, ThinCourse};
+
+/// Every meeting of `sections`, one per line of a timetable
+pub fn rows(sections: &[Section]) -> Vec<(&Section, &MeetingTime)> {
+    sections
+        .iter()
+        .sorted_by_key(|s| (&s.subject_code, &s.course_code, &s.sequence_code))
+        .flat_map(|s| s.meeting_times.iter().map(move |mt| (s, mt)))
+        .collect()
+}
+
+/// The first and last days of class
+pub fn term_dates(sections: &[Section]) -> Option<(Date, Date)> {
+    let meetings = sections.iter().flat_map(|s| &s.meeting_times);
+    let first = meetings.clone().map(|mt| mt.start_date).min()?;
+    let last = meetings.map(|mt| mt.end_date).max()?;
+    Some((first, last))
+}
+
+pub fn dates(start: Date, end: Date) -> String {
+    format!(
+        "{}–{}",
+        start.strftime("%b %-d"),
+        end.strftime("%b %-d, %Y")
+    )
+}
+
+pub fn times(mt: &MeetingTime) -> String {
+    match mt.start_time.zip(mt.end_time) {
+        Some((start, end)) => format!(
+            "{}–{}",
+            start.strftime("%-I:%M%P"),
+            end.strftime("%-I:%M%P")
+        ),
+        None => "async".to_string(),
+    }
+}
+
+/// A colour of the calendar, light enough to print black text on
+fn print_colour(hue: u64) -> Color {
+    let (s, l) = (0.7, 0.8);
+    let h = hue as f32 / 60.0;
+    let c = (1.0 - (2.0 * l - 1.0_f32).abs()) * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = l - c / 2.0;
+    Color::Rgb(Rgb::new(r + m, g + m, b + m, None))
+}
+
+fn grey(level: f32) -> Color {
+    Color::Rgb(Rgb::new(level, level, level, None))
+}
+
+fn hours(t: Time) -> f32 {
+    t.hour() as f32 + t.minute() as f32 / 60.0
+}
+
+fn line(layer: &PdfLayerReference, from: (f32, f32), to: (f32, f32)) {
+    layer.add_line(Line {
+        points: vec![
+            (Point::new(Mm(from.0), Mm(from.1)), false),
+            (Point::new(Mm(to.0), Mm(to.1)), false),
+        ],
+        is_closed: false,
+    });
+}
+
+const PAGE_WIDTH: f32 = 297.0;
+const PAGE_HEIGHT: f32 = 210.0;
+const MARGIN: f32 = 10.0;
+const ROW_HEIGHT: f32 = 5.0;
+
+/// A landscape A4 timetable: the weekly grid with a legend, then every meeting with its CRN
+pub fn pdf(
+    name: &str,
+    term: Term,
+    sections: &[Section],
+    hues: &BTreeMap<ThinCourse, u64>,
+) -> Result<Vec<u8>> {
+    let (doc, page, layer) = PdfDocument::new(name, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "timetable");
+    let font = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+    let layer = doc.get_page(page).get_layer(layer);
+    let hue = |s: &Section| hues.get(&s.thin_course()).copied().unwrap_or(0);
+
+    let mut y = PAGE_HEIGHT - MARGIN - 5.0;
+    layer.use_text(name, 16.0, Mm(MARGIN), Mm(y), &bold);
+    y -= 6.0;
+    let mut subtitle = term.human_display();
+    if let Some((first, last)) = term_dates(sections) {
+        subtitle += &format!(" · classes {}", dates(first, last));
+    }
+    layer.use_text(subtitle, 10.0, Mm(MARGIN), Mm(y), &font);
+
+    // legend
+    y -= 6.0;
+    let mut x = MARGIN;
+    for s in sections
+        .iter()
+        .unique_by(|s| (&s.subject_code, &s.course_code))
+    {
+        layer.set_fill_color(print_colour(hue(s)));
+        layer.add_rect(Rect::new(Mm(x), Mm(y), Mm(x + 3.0), Mm(y + 3.0)));
+        layer.set_fill_color(grey(0.0));
+        let label = format!("{} {}", s.subject_code, s.course_code);
+        layer.use_text(&label, 9.0, Mm(x + 4.0), Mm(y + 0.5), &font);
+        x += 6.0 + label.len() as f32 * 2.0;
+    }
+
+    y -= 3.0;
+    let grid_bottom = y - 95.0;
+    draw_grid(&layer, &font, sections, &hue, (y, grid_bottom));
+
+    // every meeting, as it would be entered in Banner
+    y = grid_bottom - 8.0;
+    let mut layer = layer;
+    let crns = sections.iter().map(|s| s.crn).sorted().dedup().join(" ");
+    layer.use_text(format!("CRNs: {crns}"), 10.0, Mm(MARGIN), Mm(y), &bold);
+    y -= ROW_HEIGHT + 1.0;
+
+    let columns = [
+        ("course", 25.0),
+        ("section", 17.0),
+        ("CRN", 17.0),
+        ("days", 17.0),
+        ("time", 38.0),
+        ("dates", 42.0),
+        ("building", 95.0),
+        ("room", 20.0),
+    ];
+    let header = |layer: &PdfLayerReference, y: f32| {
+        let mut x = MARGIN;
+        for (title, width) in columns {
+            layer.use_text(title, 9.0, Mm(x), Mm(y), &bold);
+            x += width;
+        }
+        line(layer, (MARGIN, y - 1.5), (PAGE_WIDTH - MARGIN, y - 1.5));
+    };
+    header(&layer, y);
+    for (s, mt) in rows(sections) {
+        y -= ROW_HEIGHT;
+        if y < MARGIN {
+            let (page, new_layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "timetable");
+            layer = doc.get_page(page).get_layer(new_layer);
+            y = PAGE_HEIGHT - MARGIN - 5.0;
+            header(&layer, y);
+            y -= ROW_HEIGHT;
+        }
+        let cells = [
+            format!("{} {}", s.subject_code, s.course_code),
+            s.sequence_code.clone(),
+            s.crn.to_string(),
+            mt.days.to_string(),
+            times(mt),
+            dates(mt.start_date, mt.end_date),
+            mt.building.clone().unwrap_or_default(),
+            mt.room.clone().unwrap_or_default(),
+        ];
+        let mut x = MARGIN;
+        for (cell, (_, width)) in cells.iter().zip(columns) {
+            layer.use_text(cell, 9.0, Mm(x), Mm(y), &font);
+            x += width;
+        }
+    }
+
+    Ok(doc.save_to_bytes()?)
+}
+
+/// An SVG drawn as a PNG, for sites that don't show SVGs
+pub fn png(svg: &str) -> Result<Vec<u8>> {
+    // finding the system's fonts is slow, so it's only done once
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    let fontdb = FONTS.get_or_init(|| {
+        let mut fontdb = usvg::fontdb::Database::new();
+        fontdb.load_system_fonts();
+        Arc::new(fontdb)
+    });
+    let options = usvg::Options {
+        fontdb: fontdb.clone(),
+        ..Default::default()
+    };
+
+    let tree = usvg::Tree::from_str(svg, &options)?;
+    let size = tree.size().to_int_size();
+    let mut pixmap =
+        tiny_skia::Pixmap::new(size.width(), size.height()).ok_or(anyhow!("svg has no size"))?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    Ok(pixmap.encode_png()?)
+}
+
+/// PNGs of schedules shared by link, which anyone can ask us to draw. Sites previewing a link
+/// all fetch the same image, so recent ones are kept, and only a few are drawn at once.
+pub struct SharedImages {
+    capacity: usize,
+    images: Mutex<VecDeque<(String, Vec<u8>)>>,
+    drawing: Semaphore,
+}
+
+impl Default for SharedImages {
+    fn default() -> Self {
+        Self::new(64, 2)
+    }
+}
+
+impl SharedImages {
+    fn new(capacity: usize, concurrent_drawings: usize) -> Self {
+        Self {
+            capacity,
+            images: Mutex::new(VecDeque::new()),
+            drawing: Semaphore::new(concurrent_drawings),
+        }
+    }
+
+    pub fn get(&self, blob: &str) -> Option<Vec<u8>> {
+        let images = self.images.lock().expect("image cache lock poisoned");
+        images
+            .iter()
+            .find(|(key, _)| key == blob)
+            .map(|(_, png)| png.clone())
+    }
+
+    /// Keeps `png`, forgetting the oldest image once there are too many
+    pub fn insert(&self, blob: String, png: Vec<u8>) {
+        let mut images = self.images.lock().expect("image cache lock poisoned");
+        if images.len() >= self.capacity {
+            images.pop_front();
+        }
+        images.push_back((blob, png));
+    }
+
+    /// Permission to draw one image, unless too many are being drawn already
+    pub fn try_draw(&self) -> Option<SemaphorePermit<'_>> {
+        self.drawing.try_acquire().ok()
+    }
+}
+
+/// Every timed meeting of the term on one week, between `top` and `bottom`
+fn draw_grid(
+    layer: &PdfLayerReference,
+    font: &IndirectFontRef,
+    sections: &[Section],
+    hue: &impl Fn(&Section) -> u64,
+    (top, bottom): (f32, f32),
+) {
+    let timed = sections
+        .iter()
+        .flat_map(|s| s.meeting_times.iter().map(move |mt| (s, mt)))
+        .filter_map(|(s, mt)| Some((s, mt, mt.start_time?, mt.end_time?)))
+        .collect_vec();
+    let earliest = timed
+        .iter()
+        .map(|(_, _, start, _)| start.hour())
+        .min()
+        .unwrap_or(8);
+    let latest = timed
+        .iter()
+        .map(|(_, _, _, end)| end.hour() + 1)
+        .max()
+        .unwrap_or(17);
+    let weekend = timed
+        .iter()
+        .any(|(_, mt, _, _)| Day::Saturday.is_in_days(mt.days) || Day::Sunday.is_in_days(mt.days));
+    let days = if weekend {
+        &Day::ALL[..]
+    } else {
+        &Day::WEEKDAYS[..]
+    };
+
+    let left = MARGIN + 12.0;
+    let day_width = (PAGE_WIDTH - MARGIN - left) / days.len() as f32;
+    let grid_top = top - 5.0;
+    let hour_height = (grid_top - bottom) / (latest - earliest) as f32;
+    let at = |t: f32| grid_top - (t - earliest as f32) * hour_height;
+
+    layer.set_outline_color(grey(0.8));
+    layer.set_outline_thickness(0.5);
+    for hour in earliest..=latest {
+        let y = at(hour as f32);
+        line(layer, (left, y), (PAGE_WIDTH - MARGIN, y));
+        let label = jiff::civil::time(hour.min(23), 0, 0, 0)
+            .strftime("%-I%P")
+            .to_string();
+        layer.use_text(label, 8.0, Mm(MARGIN), Mm(y - 1.0), font);
+    }
+
+    for (i, day) in days.iter().enumerate() {
+        let x = left + i as f32 * day_width;
+        line(layer, (x, grid_top), (x, bottom));
+        let name = day.to_string().to_lowercase();
+        layer.use_text(name, 9.0, Mm(x + 1.0), Mm(top - 3.5), font);
+
+        let on_day = timed
+            .iter()
+            .filter(|(_, mt, _, _)| day.is_in_days(mt.days))
+            .collect_vec();
+        // meetings at the same time share the column, like on the calendar
+        let overlaps = |a: &(&Section, &MeetingTime, Time, Time), b: &&(_, _, Time, Time)| {
+            a.2 < b.3 && b.2 < a.3
+        };
+        for (pos, &meeting) in on_day.iter().enumerate() {
+            let before = on_day[..pos]
+                .iter()
+                .filter(|o| overlaps(meeting, o))
+                .count();
+            let after = on_day[pos + 1..]
+                .iter()
+                .filter(|o| overlaps(meeting, o))
+                .count();
+            let width = day_width / (before + 1 + after) as f32;
+            let (section, mt, start, end) = meeting;
+            let x = x + before as f32 * width;
+            let (y_top, y_bottom) = (at(hours(*start)), at(hours(*end)));
+
+            layer.set_fill_color(print_colour(hue(section)));
+            layer.add_rect(Rect::new(
+                Mm(x + 0.5),
+                Mm(y_bottom),
+                Mm(x + width - 0.5),
+                Mm(y_top),
+            ));
+            layer.set_fill_color(grey(0.0));
+            let label = format!(
+                "{} {} {}",
+                section.subject_code, section.course_code, section.sequence_code
+            );
+            layer.use_text(label, 7.0, Mm(x + 1.5), Mm(y_top - 3.0), font);
+            if y_top - y_bottom > 7.0 {
+                layer.use_text(times(mt), 7.0, Mm(x + 1.5), Mm(y_top - 6.0), font);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::{date, time};
+
+    use super::*;
+
+    fn section(crn: u64, sequence_code: &str, start_time: Option<Time>) -> Section {
+        Section {
+            crn,
+            subject_code: "CSC".to_string(),
+            course_code: "111".to_string(),
+            sequence_code: sequence_code.to_string(),
+            enrollment: 0,
+            enrollment_capacity: 0,
+            waitlist: 0,
+            waitlist_capacity: 0,
+            meeting_times: vec![MeetingTime {
+                start_time,
+                end_time: start_time.map(|t| t + jiff::ToSpan::minutes(80)),
+                start_date: date(2026, 9, 8),
+                end_date: date(2026, 12, 4),
+                days: Day::Monday.into(),
+                building: Some("Engineering & Computer Science".to_string()),
+                room: Some("125".to_string()),
+            }],
+        }
+    }
+
+    #[test]
+    fn every_meeting_makes_the_pdf() {
+        let sections = [
+            section(2, "B01", Some(time(14, 30, 0, 0))),
+            section(1, "A01", Some(time(11, 30, 0, 0))),
+            section(3, "A02", None),
+        ];
+        assert_eq!(
+            rows(&sections)
+                .iter()
+                .map(|(s, _)| s.crn)
+                .collect::<Vec<_>>(),
+            [1, 3, 2]
+        );
+        assert_eq!(
+            term_dates(&sections),
+            Some((date(2026, 9, 8), date(2026, 12, 4)))
+        );
+        assert_eq!(times(&sections[2].meeting_times[0]), "async");
+
+        let term = "202609".parse().unwrap();
+        let pdf = pdf("fall", term, &sections, &BTreeMap::new()).unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn shared_images_are_bounded() {
+        let images = SharedImages::new(2, 1);
+        for blob in ["a", "b", "c"] {
+            images.insert(blob.to_string(), blob.as_bytes().to_vec());
+        }
+        assert_eq!(images.get("a"), None);
+        assert_eq!(images.get("c"), Some(b"c".to_vec()));
+
+        let drawing = images.try_draw();
+        assert!(drawing.is_some());
+        assert!(images.try_draw().is_none());
+        drop(drawing);
+        assert!(images.try_draw().is_some());
+    }
+}
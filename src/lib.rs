@@ -1,7 +1,10 @@
 pub mod app;
+pub mod campus;
 pub mod common;
 pub mod components;
+pub mod conflict;
 pub mod data;
+pub mod export;
 pub mod middlewares;
 pub mod routes;
 pub mod scraper;
@@ -1,16 +1,28 @@
 use axum::{
     async_trait,
-    extract::{FromRef, FromRequestParts, Path},
-    http::{request, StatusCode},
+    extract::{FromRef, FromRequestParts, Path, Request, State},
+    http::{
+        header::{COOKIE, SET_COOKIE},
+        request, HeaderValue, StatusCode,
+    },
+    middleware::Next,
+    response::Response,
 };
 use axum_extra::extract::{cookie::Cookie, CookieJar};
 use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+use itertools::Itertools;
+use jiff::{civil::Date, Zoned};
 use std::sync::Arc;
 use tracing::debug;
+use uuid::Uuid;
 
 use crate::{
-    common::{Schedule, SchedulePath, ScheduleWithId, Schedules},
-    data::{auth::GoogleCsrfCookie, store::Session, DatabaseAppState},
+    common::{CalendarView, History, Schedule, SchedulePath, ScheduleWithId, Schedules},
+    data::{
+        auth::{GoogleCsrfCookie, LINK_ACCOUNT_COOKIE, LINK_CONFIRM_COOKIE},
+        store::Session,
+        DatabaseAppState,
+    },
 };
 
 impl<'a> TryFrom<&Cookie<'a>> for Schedule {
@@ -72,6 +84,74 @@ where
     }
 }
 
+#[async_trait]
+impl<S> FromRequestParts<S> for History
+where
+    Arc<DatabaseAppState>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut request::Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let Path(SchedulePath { schedule_id }) = Path::from_request_parts(parts, state)
+            .await
+            .map_err(|_e| StatusCode::NOT_FOUND)?;
+
+        match Session::from_request_parts(parts, state).await {
+            Ok(session) => {
+                let state = Arc::from_ref(state);
+                state
+                    .get_schedule_history(&session.user_id, &schedule_id)
+                    .await
+                    .map_err(|e| {
+                        tracing::error!("failed to get history for {}: {:?}", schedule_id, e);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })
+            }
+            Err(StatusCode::UNAUTHORIZED) => Err(StatusCode::UNAUTHORIZED),
+            Err(_) => {
+                let jar = CookieJar::from_request_parts(parts, state).await.unwrap();
+                // a missing or unreadable history only costs the user their undo stack
+                Ok(jar
+                    .get(&History::cookie_name(&schedule_id))
+                    .and_then(|cookie| History::try_from(cookie.value()).ok())
+                    .unwrap_or_default())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for CalendarView
+where
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut request::Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let Path(SchedulePath { schedule_id }) = Path::from_request_parts(parts, state)
+            .await
+            .map_err(|_e| StatusCode::NOT_FOUND)?;
+
+        let jar = CookieJar::from_request_parts(parts, state).await.unwrap();
+        // anything unreadable just means the calendar opens the default way
+        Ok(CalendarView {
+            week: jar
+                .get(&CalendarView::week_cookie_name(&schedule_id))
+                .and_then(|cookie| cookie.value().parse().ok()),
+            full_week: jar
+                .get(CalendarView::FULL_WEEK_COOKIE)
+                .and_then(|cookie| cookie.value().parse().ok()),
+        })
+    }
+}
+
 #[async_trait]
 impl<S: Send + Sync> FromRequestParts<S> for Session
 where
@@ -113,6 +193,24 @@ where
     }
 }
 
+impl Schedules {
+    /// Every schedule saved in the browser by a user who wasn't logged in
+    pub fn from_cookies(jar: &CookieJar) -> Schedules {
+        Schedules {
+            schedules: jar
+                .iter()
+                .filter_map(|cookie| match Schedule::try_from(cookie) {
+                    Ok(schedule) => Some(ScheduleWithId {
+                        schedule,
+                        id: cookie.name().to_string(),
+                    }),
+                    Err(_e) => None,
+                })
+                .collect(),
+        }
+    }
+}
+
 #[async_trait]
 impl<S: Send + Sync> FromRequestParts<S> for Schedules
 where
@@ -142,18 +240,7 @@ where
                 StatusCode::UNAUTHORIZED => Err(StatusCode::UNAUTHORIZED),
                 _ => {
                     let jar = CookieJar::from_request_parts(parts, state).await.unwrap();
-                    Ok(Schedules {
-                        schedules: jar
-                            .iter()
-                            .filter_map(|cookie| match Schedule::try_from(cookie) {
-                                Ok(schedule) => Some(ScheduleWithId {
-                                    schedule,
-                                    id: cookie.name().to_string(),
-                                }),
-                                Err(_e) => None,
-                            })
-                            .collect(),
-                    })
+                    Ok(Schedules::from_cookies(&jar))
                 }
             },
         }
@@ -175,3 +262,155 @@ impl<S: Send + Sync> FromRequestParts<S> for GoogleCsrfCookie {
         })
     }
 }
+
+/// Cookies we set and sign: the session, schedules (named by id), their histories, the
+/// trash and accounts being linked.
+/// Anything else, like Google's CSRF cookie, is passed through untouched.
+fn is_signed_cookie(name: &str) -> bool {
+    name == "session"
+        || name == LINK_ACCOUNT_COOKIE
+        || name == LINK_CONFIRM_COOKIE
+        || name.starts_with("history_")
+        || name.starts_with("trash_")
+        || Uuid::parse_str(name).is_ok()
+}
+
+/// Whether an unsigned cookie from before cookies were signed is still worth accepting
+fn is_acceptable_legacy_cookie(state: &DatabaseAppState, cookie: &Cookie, today: Date) -> bool {
+    match cookie.name() {
+        // anyone can write an unsigned session, so it has to be logged in again
+        "session" | LINK_ACCOUNT_COOKIE | LINK_CONFIRM_COOKIE => false,
+        // histories and the trash were never set unsigned outside of development
+        name if name.starts_with("history_") || name.starts_with("trash_") => false,
+        _ => {
+            state.cookie_keys.accepts_unsigned_schedules(today)
+                && Schedule::try_from(cookie).is_ok_and(|s| state.is_plausible_schedule(&s))
+        }
+    }
+}
+
+fn with_cookie_attributes(cookie: Cookie<'static>) -> Cookie<'static> {
+    Cookie::build(cookie)
+        .http_only(true)
+        .secure(true)
+        .path("/")
+        .permanent()
+        .build()
+}
+
+/// A `Cookie` request header of `cookies`, encoded the way the jar decodes them again
+fn cookie_header(cookies: &[Cookie]) -> String {
+    cookies
+        .iter()
+        .map(|c| c.encoded().stripped().to_string())
+        .join("; ")
+}
+
+/// Verifies our cookies on the way in and signs them on the way out, so handlers only ever
+/// see cookie values we set. Cookies signed with an old key, or set before cookies were
+/// signed, are signed again with the current key.
+pub async fn signed_cookies(
+    State(state): State<Arc<DatabaseAppState>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let today = Zoned::now().date();
+    let mut verified = Vec::new();
+    let mut stale = Vec::new();
+    let mut dropped_session = false;
+    for cookie in CookieJar::from_headers(req.headers()).iter() {
+        let cookie = cookie.clone();
+        if !is_signed_cookie(cookie.name()) {
+            verified.push(cookie);
+            continue;
+        }
+        match state.cookie_keys.verify(&cookie) {
+            Some((cookie, is_stale)) => {
+                if is_stale {
+                    stale.push(cookie.clone());
+                }
+                verified.push(cookie);
+            }
+            None if is_acceptable_legacy_cookie(&state, &cookie, today) => {
+                debug!("migrating unsigned cookie {}", cookie.name());
+                stale.push(cookie.clone());
+                verified.push(cookie);
+            }
+            None => {
+                debug!("dropping cookie {} with a bad signature", cookie.name());
+                dropped_session |= cookie.name() == "session";
+            }
+        }
+    }
+
+    let headers = req.headers_mut();
+    headers.remove(COOKIE);
+    let cookie_header = cookie_header(&verified);
+    if let Ok(value) = HeaderValue::from_str(&cookie_header) {
+        if !cookie_header.is_empty() {
+            headers.insert(COOKIE, value);
+        }
+    }
+
+    let mut res = next.run(req).await;
+
+    let headers = res.headers_mut();
+    let set_cookies = headers
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        // the jar percent-encodes values, and they arrive back decoded
+        .filter_map(|v| Cookie::parse_encoded(v.to_string()).ok())
+        .collect::<Vec<_>>();
+    headers.remove(SET_COOKIE);
+
+    // the browser would otherwise keep sending a session that is never accepted
+    if dropped_session && !set_cookies.iter().any(|c| c.name() == "session") {
+        if let Ok(value) = HeaderValue::from_str(
+            &Cookie::build(("session", ""))
+                .path("/")
+                .removal()
+                .build()
+                .to_string(),
+        ) {
+            headers.append(SET_COOKIE, value);
+        }
+    }
+
+    let resigned = stale
+        .into_iter()
+        .filter(|c| !set_cookies.iter().any(|s| s.name() == c.name()))
+        .map(with_cookie_attributes)
+        .collect::<Vec<_>>();
+    for cookie in set_cookies.into_iter().chain(resigned) {
+        let is_removal = cookie.value().is_empty();
+        let cookie = match is_signed_cookie(cookie.name()) && !is_removal {
+            true => state.cookie_keys.sign(cookie),
+            false => cookie,
+        };
+        if let Ok(value) = HeaderValue::from_str(&cookie.encoded().to_string()) {
+            headers.append(SET_COOKIE, value);
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderMap;
+
+    use super::*;
+
+    #[test]
+    fn cookie_values_with_separators_survive_the_header() {
+        let cookies = [Cookie::new("a", "b; c=d"), Cookie::new("e", "f")];
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, cookie_header(&cookies).parse().unwrap());
+
+        let jar = CookieJar::from_headers(&headers);
+        assert_eq!(jar.get("a").map(Cookie::value), Some("b; c=d"));
+        assert_eq!(jar.get("e").map(Cookie::value), Some("f"));
+        assert!(jar.get("c").is_none());
+    }
+}
@@ -1,11 +1,19 @@
 pub mod calendar;
+pub mod changes;
+pub mod compare;
 pub mod donate;
+pub mod export;
+pub mod friends;
 pub mod generate;
 pub mod health;
+pub mod history;
 pub mod import;
 pub mod login;
 pub mod preview;
+pub mod register;
 pub mod root;
 pub mod schedule;
 pub mod search;
+pub mod settings;
 pub mod share;
+pub mod trash;
@@ -1,6 +1,8 @@
 use crate::{
-    common::{selected_sections, AppError, Schedule, SectionType},
-    components,
+    common::{
+        selected_sections, AppError, CalendarView, History, Schedule, SectionType, COURSE_HUES,
+    },
+    components, conflict,
     data::{store::Session, DatabaseAppState},
     scraper::{ThinCourse, ThinSection},
 };
@@ -9,23 +11,49 @@ use axum::{
     response::IntoResponse,
 };
 use axum_extra::extract::CookieJar;
+use jiff::civil::Date;
 use maud::html;
+use reqwest::StatusCode;
 use serde::Deserialize;
 use std::sync::Arc;
 use tracing::instrument;
 
+#[derive(Deserialize, Debug)]
+pub struct ViewQuery {
+    /// A day in the week to move the calendar to
+    week: Option<Date>,
+    /// Whether to show the weekend
+    full_week: Option<bool>,
+}
+
 #[instrument(level = "debug", skip(state))]
 pub async fn get_calendar(
     Path(schedule_id): Path<String>,
     State(state): State<Arc<DatabaseAppState>>,
+    Query(ViewQuery { week, full_week }): Query<ViewQuery>,
     schedule: Schedule,
+    mut calendar: CalendarView,
 ) -> Result<impl IntoResponse, AppError> {
     let courses = state.courses(schedule.term, &schedule.selected.thin_courses())?;
     let sections = selected_sections(&courses, &schedule.selected);
 
-    Ok(html! {
-        (components::calendar::view(&sections, &[]))
-    })
+    // the calendar stays how the user left it until they change it again
+    let mut jar = CookieJar::new();
+    if week.is_some() {
+        calendar.week = week;
+        jar = jar.add(calendar.week_cookie(&schedule_id));
+    }
+    if full_week.is_some() {
+        calendar.full_week = full_week;
+        jar = jar.add(calendar.full_week_cookie());
+    }
+
+    Ok((
+        jar,
+        html! {
+            (components::calendar::week_view(&schedule_id, &sections, &[], &schedule.selected, calendar))
+        },
+    ))
 }
 
 #[derive(Deserialize, Debug)]
@@ -38,48 +66,47 @@ pub async fn add_to_calendar(
     Path(schedule_id): Path<String>,
     State(state): State<Arc<DatabaseAppState>>,
     schedule: Schedule,
+    mut history: History,
+    calendar: CalendarView,
     session: Option<Session>,
     Form(Add { course }): Form<Add>,
 ) -> Result<impl IntoResponse, AppError> {
     let mut selected = schedule.selected.clone();
     let course_exists = selected.courses.keys().any(|c| *c == course);
 
-    let (jar, selected) = if course_exists {
+    let (jar, schedule) = if course_exists {
         // no-op if course is already in state
-        (CookieJar::new(), selected)
+        (CookieJar::new(), schedule)
     } else {
-        let default_sections = state.default_thin_sections(&schedule.term, course.clone())?;
+        let mut default_sections = state
+            .default_thin_sections(&schedule.term, course.clone())?
+            .ok_or(AppError::Code(StatusCode::BAD_REQUEST))?;
+        default_sections.hue = Some(selected.next_hue());
 
+        let description = format!("added {} {}", course.subject_code, course.course_code);
         selected.courses.insert(course, default_sections);
+        history.record(&schedule.selected, &selected, description);
 
         let new_schedule = Schedule {
-            name: schedule.name,
-            term: schedule.term,
-            selected: selected.clone(),
+            selected,
+            ..schedule
         };
 
-        match session {
-            Some(sess) => {
-                let _ = state
-                    .set_user_schedule(&sess.user_id, &schedule_id, &new_schedule)
-                    .await;
-                (CookieJar::new(), selected)
-            }
-            None => (
-                CookieJar::new().add(new_schedule.make_cookie(schedule_id.clone())),
-                selected,
-            ),
-        }
+        let jar = state
+            .save_schedule(session.as_ref(), &schedule_id, &new_schedule, &history)
+            .await?;
+        (jar, new_schedule)
     };
 
-    let courses = state.courses(schedule.term, &selected.thin_courses())?;
-    let sections = selected_sections(&courses, &selected);
+    let courses = state.courses(schedule.term, &schedule.selected.thin_courses())?;
+    let sections = selected_sections(&courses, &schedule.selected);
 
     Ok((
         jar,
         html! {
-            (components::calendar::view(&sections, &[]))
-            (components::courses::view(&schedule_id, &courses, &sections))
+            (components::calendar::week_view(&schedule_id, &sections, &[], &schedule.selected, calendar))
+            (components::courses::view(&schedule_id, &courses, &sections, &schedule))
+            (components::history::controls(&schedule_id, &history))
         },
     ))
 }
@@ -95,6 +122,8 @@ pub async fn rm_from_calendar(
     State(state): State<Arc<DatabaseAppState>>,
     Query(Remove { course }): Query<Remove>,
     schedule: Schedule,
+    mut history: History,
+    calendar: CalendarView,
     session: Option<Session>,
 ) -> Result<impl IntoResponse, AppError> {
     let selected = schedule.selected.clone();
@@ -106,8 +135,9 @@ pub async fn rm_from_calendar(
         return Ok((
             CookieJar::new(),
             html! {
-                (components::calendar::view(&sections, &[]))
-                (components::courses::view(&schedule_id, &courses, &sections))
+                (components::calendar::week_view(&schedule_id, &sections, &[], &schedule.selected, calendar))
+                (components::courses::view(&schedule_id, &courses, &sections, &schedule))
+                (components::history::controls(&schedule_id, &history))
             },
         ));
     }
@@ -120,27 +150,26 @@ pub async fn rm_from_calendar(
     let courses = state.courses(schedule.term, &new_selected.thin_courses())?;
     let sections = selected_sections(&courses, &selected);
 
+    history.record(
+        &schedule.selected,
+        &new_selected,
+        format!("removed {} {}", course.subject_code, course.course_code),
+    );
     let new_schedule = Schedule {
-        name: schedule.name,
-        term: schedule.term,
         selected: new_selected,
+        ..schedule
     };
 
-    let jar = match session {
-        Some(sess) => {
-            let _ = state
-                .set_user_schedule(&sess.user_id, &schedule_id, &new_schedule)
-                .await;
-            CookieJar::new()
-        }
-        None => CookieJar::new().add(new_schedule.make_cookie(schedule_id.clone())),
-    };
+    let jar = state
+        .save_schedule(session.as_ref(), &schedule_id, &new_schedule, &history)
+        .await?;
 
     Ok((
         jar,
         html! {
-            (components::calendar::view(&sections, &[]))
-            (components::courses::view(&schedule_id, &courses, &sections))
+            (components::calendar::week_view(&schedule_id, &sections, &[], &new_schedule.selected, calendar))
+            (components::courses::view(&schedule_id, &courses, &sections, &new_schedule))
+            (components::history::controls(&schedule_id, &history))
         },
     ))
 }
@@ -163,6 +192,8 @@ pub async fn update_calendar(
     Path(schedule_id): Path<String>,
     State(state): State<Arc<DatabaseAppState>>,
     schedule: Schedule,
+    mut history: History,
+    calendar: CalendarView,
     session: Option<Session>,
     Form(Update { crn }): Form<Update>,
 ) -> Result<impl IntoResponse, AppError> {
@@ -174,10 +205,18 @@ pub async fn update_calendar(
         course_code: section.course_code.clone(),
     };
 
+    if schedule.block_conflicts {
+        let courses = state.courses(schedule.term, &selected.thin_courses())?;
+        let current = selected_sections(&courses, &selected);
+        if !conflict::clashes_with(&section, &current).is_empty() {
+            return Err(AppError::Code(StatusCode::CONFLICT));
+        }
+    }
+
     if selected.courses.keys().any(|c| *c == course) {
         let selection = selected.courses.get_mut(&course).unwrap();
 
-        let section_type: SectionType = section.sequence_code.into();
+        let section_type: SectionType = section.sequence_code.clone().into();
         match section_type {
             SectionType::Lecture => selection.lecture = thin_section,
             SectionType::Lab => selection.lab = Some(thin_section),
@@ -188,26 +227,85 @@ pub async fn update_calendar(
     let courses = state.courses(schedule.term, &selected.thin_courses())?;
     let sections = selected_sections(&courses, &selected);
 
+    history.record(
+        &schedule.selected,
+        &selected,
+        format!(
+            "switched {} {} to {}",
+            course.subject_code, course.course_code, section.sequence_code
+        ),
+    );
     let new_schedule = Schedule {
-        name: schedule.name,
-        term: schedule.term,
         selected,
+        ..schedule
     };
 
-    let jar = match session {
-        Some(sess) => {
-            let _ = state
-                .set_user_schedule(&sess.user_id, &schedule_id, &new_schedule)
-                .await;
-            CookieJar::new()
-        }
-        None => CookieJar::new().add(new_schedule.make_cookie(schedule_id.clone())),
+    let jar = state
+        .save_schedule(session.as_ref(), &schedule_id, &new_schedule, &history)
+        .await?;
+
+    Ok((
+        jar,
+        html! {
+            (components::calendar::week_view(&schedule_id, &sections, &[], &new_schedule.selected, calendar))
+            (components::courses::view(&schedule_id, &courses, &sections, &new_schedule))
+            (components::history::controls(&schedule_id, &history))
+        },
+    ))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Colour {
+    course: ThinCourse,
+    hue: u64,
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn colour_course(
+    Path(schedule_id): Path<String>,
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+    mut history: History,
+    calendar: CalendarView,
+    session: Option<Session>,
+    Form(Colour { course, hue }): Form<Colour>,
+) -> Result<impl IntoResponse, AppError> {
+    if !COURSE_HUES.contains(&hue) {
+        return Err(AppError::Code(StatusCode::BAD_REQUEST));
+    }
+    let mut selected = schedule.selected.clone();
+    let selection = selected
+        .courses
+        .get_mut(&course)
+        .ok_or(AppError::Code(StatusCode::BAD_REQUEST))?;
+    selection.hue = Some(hue);
+
+    history.record(
+        &schedule.selected,
+        &selected,
+        format!(
+            "changed the colour of {} {}",
+            course.subject_code, course.course_code
+        ),
+    );
+    let new_schedule = Schedule {
+        selected,
+        ..schedule
     };
 
+    let jar = state
+        .save_schedule(session.as_ref(), &schedule_id, &new_schedule, &history)
+        .await?;
+
+    let courses = state.courses(new_schedule.term, &new_schedule.selected.thin_courses())?;
+    let sections = selected_sections(&courses, &new_schedule.selected);
+
     Ok((
         jar,
-        html!((components::calendar::view(&sections, &[]))(
-            components::courses::view(&schedule_id, &courses, &sections)
-        )),
+        html! {
+            (components::calendar::week_view(&schedule_id, &sections, &[], &new_schedule.selected, calendar))
+            (components::courses::view(&schedule_id, &courses, &sections, &new_schedule))
+            (components::history::controls(&schedule_id, &history))
+        },
     ))
 }
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use itertools::Itertools;
+use maud::{html, Markup};
+use reqwest::StatusCode;
+use tracing::instrument;
+
+use crate::{
+    common::AppError,
+    components,
+    data::{store::Session, DatabaseAppState},
+    scraper::Term,
+};
+
+/// How many changes to show before cutting the page off
+const CHANGELOG_LIMIT: u32 = 500;
+
+#[instrument(level = "debug", skip(state))]
+pub async fn index(State(state): State<Arc<DatabaseAppState>>, session: Option<Session>) -> Markup {
+    components::base(
+        html! {
+            div class="flex flex-col gap-2 py-2 px-2 lg:px-64" {
+                h1 class="text-xl" { "changes made in banner" }
+                @for term in state.get_terms() {
+                    a href={"/changes/" (term)} class="flex bg-blue-500 dark:bg-blue-600 hover:bg-blue-700 hover:dark:bg-blue-800 rounded-lg transition p-2" {
+                        (term.human_display())
+                    }
+                }
+            }
+        },
+        session,
+    )
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn get(
+    Path(term): Path<String>,
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+) -> Result<Markup, AppError> {
+    let term: Term = term.parse().map_err(|_e| StatusCode::NOT_FOUND)?;
+    if !state.get_terms().contains(&term) {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+    let changelog = state.changelog(&term, CHANGELOG_LIMIT)?;
+
+    Ok(components::base(
+        html! {
+            div class="flex flex-col gap-2 py-2 px-2 lg:px-64 h-full overflow-y-auto" {
+                h1 class="text-xl" { "changes made in banner for " (term.human_display()) }
+                @if changelog.is_empty() {
+                    p { "nothing has changed since this term was first scraped" }
+                }
+                @for (scraped_at, entries) in &changelog.iter().chunk_by(|e| e.scraped_at) {
+                    div class="bg-white dark:bg-neutral-800 rounded-lg p-2 shadow-lg" {
+                        h2 class="font-bold" {
+                            (scraped_at.strftime("%Y-%m-%d %H:%M UTC"))
+                        }
+                        ul class="list-disc list-inside text-sm" {
+                            @for entry in entries {
+                                li { (entry.change) }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        session,
+    ))
+}
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum_extra::extract::Query;
+use itertools::Itertools;
+use maud::Markup;
+use serde::Deserialize;
+use tracing::instrument;
+
+use crate::{
+    common::{selected_sections, AppError, Schedules, WeekSummary},
+    components::{self, compare::Compared},
+    data::{store::Session, DatabaseAppState},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct Compare {
+    #[serde(default)]
+    ids: Vec<String>,
+    #[serde(default)]
+    overlay: bool,
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn get(
+    State(state): State<Arc<DatabaseAppState>>,
+    schedules: Schedules,
+    session: Option<Session>,
+    Query(Compare { ids, overlay }): Query<Compare>,
+) -> Result<Markup, AppError> {
+    // in the order they were picked, skipping any that aren't the user's
+    let compared = ids
+        .iter()
+        .unique()
+        .filter_map(|id| schedules.schedules.iter().find(|s| s.id == *id))
+        .map(|s| {
+            let courses = state.courses(s.schedule.term, &s.schedule.selected.thin_courses())?;
+            let sections = selected_sections(&courses, &s.schedule.selected);
+            Ok(Compared {
+                schedule: s.clone(),
+                summary: WeekSummary::of(&sections),
+                sections,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(components::base(
+        components::compare::page(&compared, overlay),
+        session,
+    ))
+}
@@ -0,0 +1,127 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::IntoResponse,
+};
+use maud::Markup;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::instrument;
+
+use crate::{
+    common::{selected_sections, AppError, Schedule},
+    components,
+    data::DatabaseAppState,
+    export,
+};
+
+#[instrument(level = "debug", skip(state))]
+pub async fn print(
+    Path(schedule_id): Path<String>,
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+) -> Result<Markup, AppError> {
+    let courses = state.courses(schedule.term, &schedule.selected.thin_courses())?;
+    let sections = selected_sections(&courses, &schedule.selected);
+    Ok(components::print::page(&schedule_id, &schedule, &sections))
+}
+
+/// The name of a file downloaded from the schedule, keeping only characters every
+/// filesystem is happy with
+fn filename(schedule: &Schedule, extension: &str) -> String {
+    let name: String = schedule
+        .name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("attachment; filename=\"{name}.{extension}\"")
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn pdf(
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+) -> Result<impl IntoResponse, AppError> {
+    let courses = state.courses(schedule.term, &schedule.selected.thin_courses())?;
+    let sections = selected_sections(&courses, &schedule.selected);
+    let pdf = export::pdf(
+        &schedule.name,
+        schedule.term,
+        &sections,
+        &schedule.selected.hues(),
+    )?;
+    Ok((
+        [
+            (CONTENT_TYPE, "application/pdf".to_string()),
+            (CONTENT_DISPOSITION, filename(&schedule, "pdf")),
+        ],
+        pdf,
+    ))
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn svg(
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+) -> Result<impl IntoResponse, AppError> {
+    let svg = image(&state, &schedule)?;
+    Ok(([(CONTENT_TYPE, "image/svg+xml")], svg))
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn png(
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+) -> Result<impl IntoResponse, AppError> {
+    let png = export::png(&image(&state, &schedule)?)?;
+    Ok(([(CONTENT_TYPE, "image/png")], png))
+}
+
+/// Longer than the share link of any real schedule, so nobody can have us draw a huge one
+const MAX_SHARED_BLOB: usize = 8 * 1024;
+
+/// The image of a schedule shared by link, so that sites showing the link can show it too.
+/// Anyone can ask for it, so it's cached here and downstream to spare us drawing it for every
+/// preview.
+#[instrument(level = "debug", skip(state))]
+pub async fn shared_png(
+    State(state): State<Arc<DatabaseAppState>>,
+    Query(Shared { blob }): Query<Shared>,
+) -> Result<impl IntoResponse, AppError> {
+    if blob.len() > MAX_SHARED_BLOB {
+        return Err(AppError::Code(StatusCode::URI_TOO_LONG));
+    }
+    let png = match state.shared_images.get(&blob) {
+        Some(png) => png,
+        None => {
+            let schedule = Schedule::try_from(blob.clone())
+                .map_err(|_| AppError::Code(StatusCode::BAD_REQUEST))?;
+            let _drawing = state
+                .shared_images
+                .try_draw()
+                .ok_or(AppError::Code(StatusCode::TOO_MANY_REQUESTS))?;
+            let png = export::png(&image(&state, &schedule)?)?;
+            state.shared_images.insert(blob, png.clone());
+            png
+        }
+    };
+    Ok((
+        [
+            (CONTENT_TYPE, "image/png"),
+            (CACHE_CONTROL, "public, max-age=86400"),
+        ],
+        png,
+    ))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Shared {
+    blob: String,
+}
+
+fn image(state: &DatabaseAppState, schedule: &Schedule) -> Result<String, AppError> {
+    let courses = state.courses(schedule.term, &schedule.selected.thin_courses())?;
+    let sections = selected_sections(&courses, &schedule.selected);
+    Ok(components::calendar::image(&sections, &schedule.selected).into_string())
+}
@@ -0,0 +1,357 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use axum_extra::extract::{Form, Query};
+use jiff::civil::Date;
+use maud::{html, Markup};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tracing::instrument;
+
+use crate::{
+    common::{selected_sections, AppError, FreeTime, Schedule},
+    components,
+    data::{
+        store::{Friend, Session, SharedSchedule},
+        DatabaseAppState,
+    },
+    scraper::Term,
+};
+
+fn see_other(location: &str) -> impl IntoResponse {
+    ([("location", location.to_string())], StatusCode::SEE_OTHER)
+}
+
+fn parse_term(term: &str) -> Result<Term, AppError> {
+    term.parse()
+        .map_err(|_e| AppError::Code(StatusCode::BAD_REQUEST))
+}
+
+fn friends_list(friends: &[Friend]) -> Markup {
+    html! {
+        div id="friends" class="flex flex-col gap-2" {
+            h2 class="text-xl" { "friends" }
+            @if friends.is_empty() {
+                p class="text-sm" { "No friends yet. Send someone your invite link to add them." }
+            }
+            @for friend in friends {
+                div class="flex items-center gap-2 border-b border-slate-200 dark:border-neutral-700 py-1" {
+                    div class="grow flex flex-col" {
+                        span { (friend.name) }
+                        @if let Some(since) = friend.since {
+                            span class="text-xs text-neutral-500" { "friends since " (since.strftime("%Y-%m-%d")) }
+                        }
+                    }
+                    button class="bg-red-500 dark:bg-red-600 hover:bg-red-700 hover:dark:bg-red-800 transition rounded-lg px-2"
+                        hx-delete={"/friends/" (friend.user_id)}
+                        hx-target="#friends"
+                        hx-swap="outerHTML"
+                        hx-confirm={"Remove " (friend.name) " from your friends?"} {
+                        "remove"
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn sharing_form(terms: &[(Term, Vec<(String, String)>)], shared: &[SharedSchedule]) -> Markup {
+    html! {
+        div class="flex flex-col gap-2" {
+            h2 class="text-xl" { "sharing" }
+            p class="text-sm" {
+                "Friends only see the one schedule you pick for each term, and nothing for terms you don't share."
+            }
+            @for (term, schedules) in terms {
+                @let current = shared.iter().find(|s| s.term == *term).map(|s| s.schedule_id.as_str());
+                form method="post" action="/friends/share" class="flex gap-2 items-center" {
+                    input type="hidden" name="term" value=(term) {}
+                    span class="w-28" { (term.human_display()) }
+                    select name="schedule_id" class="grow text-black rounded-lg p-1 border-2 border-neutral-400 dark:border-0" {
+                        option value="" selected[current.is_none()] { "not shared" }
+                        @for (id, name) in schedules {
+                            option value=(id) selected[current == Some(id.as_str())] { (name) }
+                        }
+                    }
+                    button class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 transition rounded-lg px-2" {
+                        "save"
+                    }
+                    a href={"/friends/free/" (term)} class="hover:underline whitespace-nowrap" { "free time" }
+                }
+            }
+        }
+    }
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn get(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+) -> Result<Markup, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    let token = state
+        .invite_token(&session.user_id, &session.username)
+        .await?;
+    let friends = state.list_friends(&session.user_id).await?;
+    let shared = state.list_shared(&session.user_id).await?;
+
+    let mut terms: Vec<(Term, Vec<(String, String)>)> = Vec::new();
+    for s in state.get_user(&session.user_id).await?.schedules {
+        let entry = (s.id, s.schedule.name);
+        match terms.iter_mut().find(|(t, _)| *t == s.schedule.term) {
+            Some((_, schedules)) => schedules.push(entry),
+            None => terms.push((s.schedule.term, vec![entry])),
+        }
+    }
+    terms.sort_by_key(|(t, _)| std::cmp::Reverse(*t));
+
+    Ok(components::base(
+        html! {
+            div class="flex justify-center p-2" {
+                div class="w-full max-w-xl flex flex-col gap-4 rounded-lg p-2 bg-white dark:bg-neutral-800 shadow-xl" {
+                    div class="flex flex-col gap-2" {
+                        h2 class="text-xl" { "invite link" }
+                        p class="text-sm" { "Anyone who opens this link while logged in can add you as a friend." }
+                        form method="post" action="/friends/invite" class="flex gap-2" {
+                            input type="text" readonly value={(state.stage.public_url()) "/friends/invite/" (token)}
+                                class="grow rounded p-1 bg-slate-100 dark:bg-neutral-900" {}
+                            button class="bg-slate-300 dark:bg-neutral-700 hover:bg-slate-400 hover:dark:bg-neutral-600 transition rounded-lg px-2" {
+                                "new link"
+                            }
+                        }
+                    }
+                    (friends_list(&friends))
+                    (sharing_form(&terms, &shared))
+                }
+            }
+        },
+        Some(session),
+    ))
+}
+
+/// Replaces the invite link, so the old one stops working
+#[instrument(level = "debug", skip(state))]
+pub async fn new_invite(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    state
+        .new_invite_token(&session.user_id, &session.username)
+        .await?;
+    Ok(see_other("/friends"))
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn invite(
+    State(state): State<Arc<DatabaseAppState>>,
+    Path(token): Path<String>,
+    session: Option<Session>,
+) -> Result<Markup, AppError> {
+    let inviter = state.find_invite(&token).await?;
+    let content = match (&inviter, &session) {
+        (None, _) => html! { p { "This invite link doesn't work anymore." } },
+        (Some(inviter), Some(session)) if inviter.user_id == session.user_id => html! {
+            p { "This is your own invite link. Send it to a friend to add them." }
+        },
+        (Some(inviter), Some(_)) => html! {
+            form method="post" action={"/friends/invite/" (token)} class="flex flex-col gap-2 items-center" {
+                p { (inviter.name) " invited you to be friends on scheduler." }
+                p class="text-sm" { "You'll see the schedules each of you shares, and when you're both free." }
+                button class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 transition rounded-lg px-2" {
+                    "add " (inviter.name)
+                }
+            }
+        },
+        // logging in loses the link, so they'll have to come back to it
+        (Some(inviter), None) => html! {
+            p { (inviter.name) " invited you to be friends on scheduler. Log in, then open this link again." }
+            (components::button::link("/login", html!("log in")))
+        },
+    };
+
+    Ok(components::base(
+        html! {
+            div class="flex flex-col gap-2 h-full items-center justify-center" {
+                (content)
+            }
+        },
+        session,
+    ))
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn accept(
+    State(state): State<Arc<DatabaseAppState>>,
+    Path(token): Path<String>,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    state
+        .accept_invite(&session.user_id, &session.username, &token)
+        .await?
+        .ok_or(AppError::Code(StatusCode::BAD_REQUEST))?;
+    Ok(see_other("/friends"))
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn remove(
+    State(state): State<Arc<DatabaseAppState>>,
+    Path(friend_id): Path<String>,
+    session: Option<Session>,
+) -> Result<Markup, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    state.remove_friend(&session.user_id, &friend_id).await?;
+    let friends = state.list_friends(&session.user_id).await?;
+    Ok(friends_list(&friends))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Share {
+    term: String,
+    schedule_id: String,
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn share(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+    Form(Share { term, schedule_id }): Form<Share>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    let term = parse_term(&term)?;
+    let schedule_id = Some(schedule_id).filter(|id| !id.is_empty());
+    if let Some(schedule_id) = &schedule_id {
+        let owned = state
+            .get_user(&session.user_id)
+            .await?
+            .schedules
+            .iter()
+            .any(|s| s.id == *schedule_id && s.schedule.term == term);
+        if !owned {
+            return Err(AppError::Code(StatusCode::BAD_REQUEST));
+        }
+    }
+    state
+        .set_shared(&session.user_id, &term, schedule_id.as_deref())
+        .await?;
+    Ok(see_other("/friends"))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FreeTimeQuery {
+    /// The friends to find free time with, or all that share the term if empty
+    #[serde(default)]
+    with: Vec<String>,
+    /// A day in the week to look at, if not the one the calendar would show
+    week: Option<Date>,
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn free_time(
+    State(state): State<Arc<DatabaseAppState>>,
+    Path(term): Path<String>,
+    session: Option<Session>,
+    Query(FreeTimeQuery { with, week }): Query<FreeTimeQuery>,
+) -> Result<Markup, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    let term = parse_term(&term)?;
+
+    // the schedule the user shares for the term, or any of theirs in it if they share none
+    let shared = state.list_shared(&session.user_id).await?;
+    let schedules = state.get_user(&session.user_id).await?.schedules;
+    let own: Option<Schedule> = schedules
+        .iter()
+        .filter(|s| s.schedule.term == term)
+        .min_by_key(|s| !shared.iter().any(|sh| sh.schedule_id == s.id))
+        .map(|s| s.schedule.clone());
+
+    let friends = state.friends_schedules(&session.user_id, &term).await?;
+    let included: Vec<&(Friend, Schedule)> = friends
+        .iter()
+        .filter(|(f, _)| with.is_empty() || with.contains(&f.user_id))
+        .collect();
+
+    let sections_of = |schedule: &Schedule| -> anyhow::Result<_> {
+        let courses = state.courses(term, &schedule.selected.thin_courses())?;
+        Ok(selected_sections(&courses, &schedule.selected))
+    };
+    let own_sections = match &own {
+        Some(own) => sections_of(own)?,
+        None => Vec::new(),
+    };
+    let mut everyone = vec![own_sections.clone()];
+    for (_, schedule) in &included {
+        everyone.push(sections_of(schedule)?);
+    }
+    let monday = components::calendar::week_of(&everyone.concat(), week);
+    let free = FreeTime::common(&everyone, monday);
+
+    Ok(components::base(
+        html! {
+            div class="flex flex-col gap-2 p-2 h-full overflow-y-auto" {
+                div class="flex items-center gap-2" {
+                    h2 class="grow text-xl" { "free time · " (term.human_display()) }
+                    (components::button::link("/friends", html!("back")))
+                }
+                @if own.is_none() {
+                    p class="text-sm" { "You have no schedule in this term, so only your friends' classes count." }
+                }
+                @if friends.is_empty() {
+                    p class="text-sm" { "None of your friends share a schedule for this term yet." }
+                } @else {
+                    form method="get" class="flex gap-4 flex-wrap items-center" {
+                        @for (friend, _) in &friends {
+                            label class="flex gap-1 items-center" {
+                                input type="checkbox" name="with" value=(friend.user_id)
+                                    checked[with.is_empty() || with.contains(&friend.user_id)] {}
+                                (friend.name)
+                            }
+                        }
+                        @if let Some(monday) = monday {
+                            input type="date" name="week" value=(monday) class="rounded bg-slate-100 dark:bg-neutral-900" {}
+                        }
+                        button class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 transition rounded-lg px-2" {
+                            "update"
+                        }
+                    }
+                }
+                div class="w-full h-[70vh] shrink-0 lg:p-1 bg-white dark:bg-neutral-800 rounded-lg shadow-xl" {
+                    (components::calendar::free_time(&free, monday))
+                }
+                @if let Some(own) = &own {
+                    div class="flex flex-col gap-1 rounded-lg p-2 bg-white dark:bg-neutral-800 shadow-xl" {
+                        h3 class="text-lg" { "in the same courses" }
+                        @for (friend, schedule) in &included {
+                            @let common = own.selected.thin_courses().into_iter().filter(|c| schedule.selected.courses.contains_key(*c)).collect::<Vec<_>>();
+                            @if !common.is_empty() {
+                                div class="flex flex-col border-b border-slate-200 dark:border-neutral-700 py-1" {
+                                    span { (friend.name) }
+                                    @for course in common {
+                                        @let crns = schedule.selected.courses[course].crns();
+                                        @let same = own_sections.iter()
+                                            .filter(|s| s.subject_code == course.subject_code && s.course_code == course.course_code && crns.contains(&s.crn))
+                                            .map(|s| s.sequence_code.as_str())
+                                            .collect::<Vec<_>>();
+                                        span class="text-sm pl-2" {
+                                            (course.subject_code) " " (course.course_code)
+                                            @if same.is_empty() {
+                                                " · different sections"
+                                            } @else {
+                                                " · same " (same.join(", "))
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        Some(session),
+    ))
+}
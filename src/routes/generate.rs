@@ -6,7 +6,7 @@ use axum::{
     extract::{Path, Query, State},
     response::IntoResponse,
 };
-use axum_extra::extract::{CookieJar, Form};
+use axum_extra::extract::Form;
 use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
 use itertools::Itertools;
 use maud::{html, Markup};
@@ -15,7 +15,7 @@ use serde::Deserialize;
 use tracing::instrument;
 
 use crate::{
-    common::{AppError, Schedule, ScheduleWithId},
+    common::{AppError, History, Schedule, ScheduleWithId, SelectedCourses},
     components,
     data::{store::Session, DatabaseAppState},
     scraper::ThinSection,
@@ -67,11 +67,17 @@ pub async fn get(
 
     let overwrite_url = format!("/schedule/{}/generate", schedule_id);
 
+    // courses keep the colours they already had
+    let hues = schedule.selected.hues();
+    let mut selected: SelectedCourses = sections.clone().into();
+    for (course, selection) in selected.courses.iter_mut() {
+        selection.hue = hues.get(course).copied();
+    }
+
     let new_schedule = ScheduleWithId {
         schedule: Schedule {
-            name: schedule.name,
-            term: schedule.term,
-            selected: sections.clone().into(),
+            selected,
+            ..schedule
         },
         id: schedule_id.clone(),
     };
@@ -80,7 +86,7 @@ pub async fn get(
 
     Ok(components::base(
         html! {
-            (components::container::generator_container(&schedule_id, &sections, &prev_url, &next_url, &overwrite_url, &new_schedule.to_base64()))
+            (components::container::generator_container(&schedule_id, &sections, &new_schedule.schedule.selected, &prev_url, &next_url, &overwrite_url, &new_schedule.to_base64()))
         },
         session,
     ))
@@ -111,9 +117,11 @@ mod algo {
      * - map next_state back to sections (hydrate)
      */
     use itertools::Itertools;
-    use jiff::civil::{Date, Time};
 
-    use crate::scraper;
+    use crate::{
+        conflict::{partition, MeetingTime},
+        scraper,
+    };
 
     pub fn next_schedule(
         courses: &[scraper::Course],
@@ -155,27 +163,6 @@ mod algo {
         //})
     }
 
-    // group sections into (course, sequence type)
-    fn partition(courses: &[scraper::Course]) -> Vec<Vec<scraper::Section>> {
-        courses
-            .iter()
-            .flat_map(|c| {
-                c.sections
-                    .iter()
-                    .chunk_by(|&s| {
-                        (
-                            s.subject_code.clone(),
-                            s.course_code.clone(),
-                            s.sequence_code.chars().next(),
-                        )
-                    })
-                    .into_iter()
-                    .map(|(_, chunk)| chunk.cloned().collect::<Vec<_>>())
-                    .collect::<Vec<_>>()
-            })
-            .collect::<Vec<_>>()
-    }
-
     struct Groups {
         groups: Vec<Group>,
     }
@@ -214,68 +201,6 @@ mod algo {
         }
     }
 
-    struct MeetingTime {
-        start_time: Option<Time>,
-        end_time: Option<Time>,
-        start_date: Date,
-        end_date: Date,
-
-        days: u8,
-    }
-
-    impl MeetingTime {
-        fn new(t: &scraper::MeetingTime) -> Self {
-            let mut day_mask = 0u8;
-            if t.days.monday {
-                day_mask |= 1 << 0;
-            }
-            if t.days.tuesday {
-                day_mask |= 1 << 1;
-            }
-            if t.days.wednesday {
-                day_mask |= 1 << 2;
-            }
-            if t.days.thursday {
-                day_mask |= 1 << 3;
-            }
-            if t.days.friday {
-                day_mask |= 1 << 4;
-            }
-            if t.days.saturday {
-                day_mask |= 1 << 5;
-            }
-            if t.days.sunday {
-                day_mask |= 1 << 6;
-            }
-            MeetingTime {
-                start_time: t.start_time,
-                end_time: t.end_time,
-                start_date: t.start_date,
-                end_date: t.end_date,
-                days: day_mask,
-            }
-        }
-    }
-
-    impl PartialEq for MeetingTime {
-        fn eq(&self, other: &Self) -> bool {
-            // no conflict in term
-            if self.end_date < other.start_date || other.end_date < self.start_date {
-                return false;
-            }
-            // no conflict in week
-            if (self.days & other.days) == 0 {
-                return false;
-            }
-            // no conflict in day
-            if self.end_time < other.start_time || other.end_time < self.start_time {
-                return false;
-            }
-
-            true
-        }
-    }
-
     fn find_next(groups: &Groups, state: Option<&[scraper::ThinSection]>) -> Option<Vec<usize>> {
         // ThinSection -> array offset
         let state: Option<Vec<usize>> = state.and_then(|state| {
@@ -372,6 +297,8 @@ pub struct Overwrite {
 pub async fn post(
     Path(schedule_id): Path<String>,
     State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+    mut history: History,
     session: Option<Session>,
     Form(overwrite): Form<Overwrite>,
 ) -> Result<impl IntoResponse, AppError> {
@@ -381,15 +308,19 @@ pub async fn post(
     let new_schedule: ScheduleWithId = serde_json::from_slice(&new_schedule_json)
         .map_err(|e| anyhow!("could not deserialize schedule, {}", e))?;
 
-    let jar = match session {
-        Some(session) => {
-            let _ = state
-                .set_user_schedule(&session.user_id, &new_schedule.id, &new_schedule.schedule)
-                .await;
-            CookieJar::new()
-        }
-        None => CookieJar::new().add(new_schedule.schedule.make_cookie(new_schedule.id)),
-    };
+    history.record(
+        &schedule.selected,
+        &new_schedule.schedule.selected,
+        "applied a generated schedule".to_string(),
+    );
+    let jar = state
+        .save_schedule(
+            session.as_ref(),
+            &schedule_id,
+            &new_schedule.schedule,
+            &history,
+        )
+        .await?;
 
     Ok((
         jar,
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use axum_extra::extract::CookieJar;
+use maud::{html, Markup};
+use reqwest::StatusCode;
+use tracing::instrument;
+
+use crate::{
+    common::{selected_sections, AppError, CalendarView, History, Schedule, SelectedCourses},
+    components,
+    data::{store::Session, DatabaseAppState},
+};
+
+#[instrument(level = "debug")]
+pub async fn get(
+    Path(schedule_id): Path<String>,
+    schedule: Schedule,
+    history: History,
+    session: Option<Session>,
+) -> Result<Markup, AppError> {
+    Ok(components::base(
+        components::history::page(&schedule_id, &schedule.name, &history),
+        session,
+    ))
+}
+
+/// Switches the schedule to `selected`, if there is anywhere to go, and re-renders
+/// everything that depends on it
+async fn apply(
+    state: &DatabaseAppState,
+    schedule_id: &str,
+    schedule: Schedule,
+    history: &History,
+    calendar: CalendarView,
+    session: Option<&Session>,
+    selected: Option<SelectedCourses>,
+) -> Result<impl IntoResponse, AppError> {
+    let (jar, schedule) = match selected {
+        Some(selected) => {
+            let schedule = Schedule {
+                selected,
+                ..schedule
+            };
+            let jar = state
+                .save_schedule(session, schedule_id, &schedule, history)
+                .await?;
+            (jar, schedule)
+        }
+        None => (CookieJar::new(), schedule),
+    };
+
+    let courses = state.courses(schedule.term, &schedule.selected.thin_courses())?;
+    let sections = selected_sections(&courses, &schedule.selected);
+    let schedule_id = schedule_id.to_string();
+
+    Ok((
+        jar,
+        html! {
+            (components::calendar::week_view(&schedule_id, &sections, &[], &schedule.selected, calendar))
+            (components::courses::view(&schedule_id, &courses, &sections, &schedule))
+            (components::history::controls(&schedule_id, history))
+        },
+    ))
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn undo(
+    Path(schedule_id): Path<String>,
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+    mut history: History,
+    calendar: CalendarView,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let selected = history.undo();
+    apply(
+        &state,
+        &schedule_id,
+        schedule,
+        &history,
+        calendar,
+        session.as_ref(),
+        selected,
+    )
+    .await
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn redo(
+    Path(schedule_id): Path<String>,
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+    mut history: History,
+    calendar: CalendarView,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let selected = history.redo();
+    apply(
+        &state,
+        &schedule_id,
+        schedule,
+        &history,
+        calendar,
+        session.as_ref(),
+        selected,
+    )
+    .await
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn restore(
+    Path((schedule_id, index)): Path<(String, usize)>,
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+    mut history: History,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let selected = history
+        .restore(index)
+        .ok_or(AppError::Code(StatusCode::NOT_FOUND))?;
+    let schedule = Schedule {
+        selected,
+        ..schedule
+    };
+    let jar = state
+        .save_schedule(session.as_ref(), &schedule_id, &schedule, &history)
+        .await?;
+
+    Ok((
+        jar,
+        [("hx-location", format!("/schedule/{}", schedule_id))],
+        StatusCode::SEE_OTHER,
+    ))
+}
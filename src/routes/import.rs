@@ -1,19 +1,29 @@
-use axum::{extract::Query, response::IntoResponse};
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+};
 use axum_extra::extract::CookieJar;
-use reqwest::StatusCode;
+use maud::{html, Markup, DOCTYPE};
+use reqwest::{StatusCode, Url};
 use serde::Deserialize;
 use tracing::instrument;
 use uuid::Uuid;
 
 use common::Schedule;
 
-use crate::common::{self, AppError};
+use crate::{
+    common::{self, AppError},
+    data::DatabaseAppState,
+};
 
 #[derive(Deserialize)]
 pub struct Params {
     blob: String,
 }
 
+/// Imports a shared schedule and goes to it
 #[instrument(level = "debug")]
 pub async fn get(Query(Params { blob }): Query<Params>) -> Result<impl IntoResponse, AppError> {
     let uuid = Uuid::new_v4().to_string();
@@ -28,3 +38,39 @@ pub async fn get(Query(Params { blob }): Query<Params>) -> Result<impl IntoRespo
         })
         .map_err(AppError::Anyhow)
 }
+
+/// Where a share link points: the schedule's name and image for sites that preview links, which
+/// don't follow the redirect on to the import, so only people opening the link get a copy
+#[instrument(level = "debug", skip(state))]
+pub async fn preview(
+    State(state): State<Arc<DatabaseAppState>>,
+    Query(Params { blob }): Query<Params>,
+) -> Result<Markup, AppError> {
+    let schedule = Schedule::try_from(blob.clone()).map_err(AppError::Anyhow)?;
+    // behind the proxy the request doesn't know the address people reach us at
+    let url = |path: &str| {
+        Url::parse_with_params(
+            &format!("{}{}", state.stage.public_url(), path),
+            &[("blob", &blob)],
+        )
+        .map_err(|e| AppError::Anyhow(e.into()))
+    };
+    let (import, image) = (url("/import")?, url("/import/image.png")?);
+
+    Ok(html! {
+        (DOCTYPE)
+        html {
+            head {
+                title { (schedule.name) " · scheduler" }
+                meta http-equiv="refresh" content={"0; url=" (import.as_str())} {}
+                meta property="og:title" content=(schedule.name) {}
+                meta property="og:description" content={(schedule.term.human_display()) " schedule"} {}
+                meta property="og:image" content=(image.as_str()) {}
+                meta name="twitter:card" content="summary_large_image" {}
+            }
+            body {
+                a href=(import.as_str()) { "go to " (schedule.name) }
+            }
+        }
+    })
+}
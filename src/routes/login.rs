@@ -2,19 +2,29 @@ use std::sync::Arc;
 
 use axum::{
     extract::{Query, State},
-    response::IntoResponse,
+    http::{header::USER_AGENT, HeaderMap},
+    response::{IntoResponse, Response},
 };
-use axum_extra::extract::{cookie::Cookie, CookieJar, Form};
-use maud::html;
+use axum_extra::extract::{
+    cookie::{Cookie, SameSite},
+    CookieJar, Form,
+};
+use maud::{html, Markup};
 use reqwest::StatusCode;
 use serde::Deserialize;
-use tracing::debug;
+use time::Duration;
+use tracing::{debug, warn};
 
 use crate::{
-    common::{AppError, Stage},
+    common::{AppError, History, Schedules},
     components,
     data::{
-        auth::{Authority, GoogleCsrfCookie},
+        auth::{
+            check_state, new_oauth_state, removed_oauth_state_cookie, AuthError, Authority,
+            GoogleCsrfCookie, LINK_ACCOUNT_COOKIE, LINK_CONFIRM_COOKIE, OAUTH_STATE_COOKIE,
+            OIDC_FLOW_COOKIE,
+        },
+        oidc::OidcFlow,
         store::Session,
         DatabaseAppState,
     },
@@ -26,131 +36,590 @@ pub async fn get(
 ) -> Result<impl IntoResponse, AppError> {
     // if visiting the login page when already logged in, log them out
     let jar = match session {
-        Some(_s) => {
-            let cookie = Cookie::build(("session", "")).path("/").removal().build();
+        Some(s) => {
+            state.revoke_session(&s.user_id, &s.session_id).await?;
             debug!("destroying session cookie");
-            CookieJar::new().add(cookie)
+            CookieJar::new().add(removed_session_cookie())
         }
         None => CookieJar::new(),
     };
-    let discord_link = match state.stage {
-        Stage::LOCAL => "https://discord.com/oauth2/authorize?client_id=1324110828810797108&response_type=code&redirect_uri=http%3A%2F%2Flocalhost%3A8443%2Flogin%2Fdiscord&scope=identify",
-        Stage::PROD => "https://discord.com/oauth2/authorize?client_id=1324110828810797108&response_type=code&redirect_uri=https%3A%2F%2Fscheduler.brennanmcmicking.net%2Flogin%2Fdiscord&scope=identify",
-    };
-    Ok((
-        jar,
+    Ok(login_page(&state, jar, None))
+}
+
+/// The login page, with the reason the last attempt failed if there was one
+fn login_page(
+    state: &DatabaseAppState,
+    jar: CookieJar,
+    error: Option<&AuthError>,
+) -> (CookieJar, Markup) {
+    let (oauth_state, cookie) = new_oauth_state();
+    (
+        jar.add(cookie),
         components::base(
             html! {
-                script src="https://accounts.google.com/gsi/client" async {}
                 div id="login-container" class="flex flex-col gap-2 py-2 w-full items-center" {
-                    a href=(discord_link) {
-                        div class="rounded bg-[#5865F2] flex h-10 p-2 gap-2" {
-                            img class="w-10" src="/assets/discord-mark-white.svg" {}
-                            p class="whitespace-nowrap" { "Continue with Discord" }
+                    @if let Some(error) = error {
+                        div class="rounded-lg p-2 bg-red-100 dark:bg-red-900 max-w-md text-center" {
+                            (error.message())
                         }
                     }
-                    div id="g_id_onload"
-                        data-client_id="839626045148-u695skik1hvq9o41dactp72usr0i9bsh.apps.googleusercontent.com"
-                        data-context="signin"
-                        data-ux_mode="popup"
-                        data-login_uri="/login/google"
-                        data-auto_prompt="false" {}
-                    div class="g_id_signin w-auto"
-                        data-type="standard"
-                        data-shape="rectangular"
-                        data-theme="outline"
-                        data-text="continue_with"
-                        data-size="large"
-                        data-logo_alignment="left" {}
+                    (login_buttons(state, &oauth_state))
                 }
             },
             None,
         ),
+    )
+}
+
+/// A button for each provider, sending Discord the page's OAuth state
+fn login_buttons(state: &DatabaseAppState, oauth_state: &str) -> Markup {
+    let discord_link = state.discord_client.authorize_url(oauth_state);
+    html! {
+        script src="https://accounts.google.com/gsi/client" async {}
+        a href=(discord_link) {
+            div class="rounded bg-[#5865F2] flex h-10 p-2 gap-2" {
+                img class="w-10" src="/assets/discord-mark-white.svg" {}
+                p class="whitespace-nowrap" { "Continue with Discord" }
+            }
+        }
+        @if let Some(oidc) = &state.oidc_client {
+            a href="/login/oidc/start" {
+                div class="rounded bg-slate-600 text-white flex h-10 p-2 gap-2" {
+                    img class="w-6" src="/assets/key.svg" {}
+                    p class="whitespace-nowrap" { "Continue with " (oidc.config.name) }
+                }
+            }
+        }
+        div id="g_id_onload"
+            data-client_id=(state.google_client_id)
+            data-context="signin"
+            data-ux_mode="popup"
+            data-login_uri="/login/google"
+            data-auto_prompt="false" {}
+        div class="g_id_signin w-auto"
+            data-type="standard"
+            data-shape="rectangular"
+            data-theme="outline"
+            data-text="continue_with"
+            data-size="large"
+            data-logo_alignment="left" {}
+    }
+}
+
+/// How long someone has to finish signing in with a login they are linking
+const LINK_MAX_AGE: Duration = Duration::minutes(10);
+
+/// Lets someone who is logged in sign in with another provider to add it to their account
+pub async fn get_link(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    let (oauth_state, cookie) = new_oauth_state();
+    // only the session that asked, signing in from this page, links the login
+    let intent = Cookie::build((
+        LINK_ACCOUNT_COOKIE,
+        format!("{}:{}", session.session_id, oauth_state),
+    ))
+    .http_only(true)
+    .secure(true)
+    .same_site(SameSite::Lax)
+    .path("/login")
+    .max_age(LINK_MAX_AGE)
+    .build();
+
+    Ok((
+        CookieJar::new().add(cookie).add(intent),
+        components::base(
+            html! {
+                div id="login-container" class="flex flex-col gap-2 py-2 w-full items-center" {
+                    p class="max-w-md text-center" {
+                        "Sign in with another login to add it to your account. "
+                        "Any schedules saved under it will be moved here."
+                    }
+                    (login_buttons(&state, &oauth_state))
+                }
+            },
+            Some(session),
+        ),
     ))
 }
 
+/// Whether the link cookie was set for `session` on the page this sign-in started from
+fn is_link_intended(intent: &str, session: &Session, jar: &CookieJar) -> bool {
+    let oauth_state = jar.get(OAUTH_STATE_COOKIE).map(|c| c.value());
+    match intent.split_once(':') {
+        Some((session_id, state)) => {
+            session_id == session.session_id && check_state(oauth_state, Some(state)).is_ok()
+        }
+        None => false,
+    }
+}
+
+/// Shows the login page again explaining what went wrong
+fn failed_login(state: &DatabaseAppState, error: AuthError) -> Response {
+    warn!("sign in failed: {:?}", error);
+    let (jar, page) = login_page(state, CookieJar::new(), Some(&error));
+    (error.status(), jar, page).into_response()
+}
+
+/// Starts a session for someone the provider vouched for and sends them on their way,
+/// or adds the login to their account if they came from the link page
+async fn finish_login(
+    state: &DatabaseAppState,
+    identity: String,
+    username: String,
+    authority: Authority,
+    headers: &HeaderMap,
+    jar: &CookieJar,
+    session: Option<Session>,
+) -> Result<Response, AppError> {
+    let cleanup = CookieJar::new()
+        .add(removed_oauth_state_cookie())
+        .add(Cookie::build((LINK_ACCOUNT_COOKIE, "")).path("/login").removal().build());
+
+    if let (Some(session), Some(intent)) = (session, jar.get(LINK_ACCOUNT_COOKIE)) {
+        if !is_link_intended(intent.value(), &session, jar) {
+            return Ok((cleanup, failed_login(state, AuthError::StateMismatch)).into_response());
+        }
+        let account_id = state.account_for(&session.user_id).await?;
+        if state
+            .belongs_to_other_account(&account_id, &identity)
+            .await?
+        {
+            let pending = Cookie::build((
+                LINK_CONFIRM_COOKIE,
+                format!("{}:{}", session.session_id, identity),
+            ))
+            .http_only(true)
+            .secure(true)
+            .same_site(SameSite::Strict)
+            .path("/login")
+            .max_age(LINK_MAX_AGE)
+            .build();
+            return Ok((cleanup.add(pending), confirm_link_page(session)).into_response());
+        }
+        return link(state, account_id, &identity, session, headers, cleanup).await;
+    }
+
+    let user_id = state.account_for(&identity).await?;
+    start_session(
+        state,
+        user_id,
+        username,
+        authority,
+        headers,
+        after_login(jar),
+        cleanup,
+    )
+    .await
+}
+
+/// Adds `identity` to `account_id`, the account `session` is logged in to
+async fn link(
+    state: &DatabaseAppState,
+    account_id: String,
+    identity: &str,
+    session: Session,
+    headers: &HeaderMap,
+    jar: CookieJar,
+) -> Result<Response, AppError> {
+    state.link_identity(&account_id, identity).await?;
+    if account_id == session.user_id {
+        return Ok((jar, [("location", "/settings")], StatusCode::SEE_OTHER).into_response());
+    }
+    // logged in from before accounts, and that session was just moved to the account
+    start_session(
+        state,
+        account_id,
+        session.username,
+        session.authority,
+        headers,
+        "/settings",
+        jar,
+    )
+    .await
+}
+
+async fn start_session(
+    state: &DatabaseAppState,
+    user_id: String,
+    username: String,
+    authority: Authority,
+    headers: &HeaderMap,
+    location: &str,
+    jar: CookieJar,
+) -> Result<Response, AppError> {
+    let session = Session {
+        session_id: state.make_session(&user_id, user_agent(headers)).await?,
+        user_id,
+        username,
+        authority,
+    };
+    let cookie = Cookie::build(("session", session.to_base64()))
+        .http_only(true)
+        .secure(true)
+        .path("/")
+        .permanent()
+        .build();
+
+    Ok((
+        jar.add(cookie),
+        [("location", location)],
+        StatusCode::SEE_OTHER,
+    )
+        .into_response())
+}
+
+/// Asks before linking a login that has an account of its own, since that account is
+/// merged into this one
+fn confirm_link_page(session: Session) -> Markup {
+    components::base(
+        html! {
+            div class="flex justify-center p-2" {
+                form action="/login/link/confirm" method="post" class="w-full max-w-md flex flex-col gap-2 rounded-lg p-2 bg-white dark:bg-neutral-800 shadow-xl" {
+                    h2 class="text-xl" { "Merge accounts?" }
+                    p {
+                        "The login you signed in with already has its own account. Merging moves its "
+                        "schedules, friends and logins into this account, and signs it out everywhere."
+                    }
+                    div class="flex gap-2 justify-end" {
+                        button name="action" value="cancel" class="bg-red-500 dark:bg-red-600 hover:bg-red-700 hover:dark:bg-red-800 rounded-lg transition px-2" {
+                            "cancel"
+                        }
+                        button name="action" value="merge" class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 rounded-lg transition px-2" {
+                            "merge"
+                        }
+                    }
+                }
+            }
+        },
+        Some(session),
+    )
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkAction {
+    Merge,
+    Cancel,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LinkConfirmation {
+    action: LinkAction,
+}
+
+/// Merges the account of the login waiting in the confirm cookie, if the user agreed to
+pub async fn post_link_confirm(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Form(LinkConfirmation { action }): Form<LinkConfirmation>,
+) -> Result<Response, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    let cleanup = CookieJar::new().add(
+        Cookie::build((LINK_CONFIRM_COOKIE, ""))
+            .path("/login")
+            .removal()
+            .build(),
+    );
+    let identity = jar.get(LINK_CONFIRM_COOKIE).and_then(|c| {
+        let (session_id, identity) = c.value().split_once(':')?;
+        (session_id == session.session_id).then(|| identity.to_string())
+    });
+    let Some(identity) = identity else {
+        return Ok((cleanup, failed_login(&state, AuthError::StateMismatch)).into_response());
+    };
+    if action == LinkAction::Cancel {
+        return Ok((cleanup, [("location", "/settings")], StatusCode::SEE_OTHER).into_response());
+    }
+
+    let account_id = state.account_for(&session.user_id).await?;
+    link(&state, account_id, &identity, session, &headers, cleanup).await
+}
+
 #[derive(Deserialize, Debug)]
 pub struct GoogleLoginRequest {
     g_csrf_token: String,
     credential: String,
 }
 
+fn removed_session_cookie() -> Cookie<'static> {
+    Cookie::build(("session", "")).path("/").removal().build()
+}
+
+fn user_agent(headers: &HeaderMap) -> Option<&str> {
+    headers.get(USER_AGENT).and_then(|v| v.to_str().ok())
+}
+
+pub async fn logout(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    if let Some(session) = session {
+        state
+            .revoke_session(&session.user_id, &session.session_id)
+            .await?;
+    }
+    Ok((
+        CookieJar::new().add(removed_session_cookie()),
+        [("location", "/")],
+        StatusCode::SEE_OTHER,
+    ))
+}
+
+/// Where to send a user who just logged in: to the import page if they made
+/// schedules before logging in, since those are hidden once they have a session
+fn after_login(jar: &CookieJar) -> &'static str {
+    match Schedules::from_cookies(jar).schedules.is_empty() {
+        true => "/",
+        false => "/login/import",
+    }
+}
+
 pub async fn post_google(
     State(state): State<Arc<DatabaseAppState>>,
-    csrf_cookie: GoogleCsrfCookie,
+    csrf_cookie: Option<GoogleCsrfCookie>,
+    session: Option<Session>,
+    headers: HeaderMap,
+    jar: CookieJar,
     Form(GoogleLoginRequest {
         g_csrf_token,
         credential,
     }): Form<GoogleLoginRequest>,
-) -> Result<impl IntoResponse, AppError> {
-    debug!("{}", g_csrf_token);
-    debug!("{}", csrf_cookie.value);
-    debug!("{}", credential);
-
-    match csrf_cookie.value == g_csrf_token {
-        true => {
-            let payload = state
-                .google_client
-                .validate_id_token(credential)
-                .await
-                .unwrap();
-            debug!("{}", payload.sub);
-
-            // prepend the identity provider to prevent collisions
-            let user_id = format!("google_{}", &payload.sub);
-            let session = Session {
-                session_id: state.make_session(&user_id).await?,
-                user_id,
-                username: payload
-                    .email
-                    .expect("no email provided in google sign in response"),
-                authority: Authority::GOOGLE,
-            };
-
-            let cookie = Cookie::build(("session", session.to_base64()))
-                .http_only(true)
-                .secure(true)
-                .path("/")
-                .permanent()
-                .build();
-
-            Ok((
-                CookieJar::new().add(cookie),
-                [("location", "/")],
-                StatusCode::SEE_OTHER,
-            ))
-        }
-        false => Err(StatusCode::BAD_REQUEST.into()),
+) -> Result<Response, AppError> {
+    let csrf_cookie = csrf_cookie.map(|c| c.value);
+    if let Err(e) = check_state(csrf_cookie.as_deref(), Some(&g_csrf_token)) {
+        return Ok(failed_login(&state, e));
     }
+
+    let payload = match state.google_client.validate_id_token(credential).await {
+        Ok(payload) => payload,
+        Err(e) => return Ok(failed_login(&state, AuthError::Provider(e.into()))),
+    };
+    let Some(email) = payload.email else {
+        return Ok(failed_login(&state, AuthError::MissingEmail));
+    };
+    debug!("{}", payload.sub);
+
+    // prepend the identity provider to prevent collisions
+    let identity = format!("google_{}", &payload.sub);
+    finish_login(
+        &state,
+        identity,
+        email,
+        Authority::GOOGLE,
+        &headers,
+        &jar,
+        session,
+    )
+    .await
 }
 
-#[derive(Deserialize)]
-pub struct DiscordCallback {
-    code: String,
+/// What Discord or the OIDC provider sends back: a code to exchange, or why there isn't one
+#[derive(Deserialize, Debug)]
+pub struct OAuthCallback {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
 }
 
 pub async fn get_discord(
     State(app_state): State<Arc<DatabaseAppState>>,
-    Query(DiscordCallback { code }): Query<DiscordCallback>,
-) -> Result<impl IntoResponse, AppError> {
-    let user = app_state.discord_client.get_user(&code).await?;
-    let user_id = format!("discord_{}", user.id);
-    let session = Session {
-        session_id: app_state.make_session(&user_id).await?,
-        user_id,
-        username: user.username,
-        authority: Authority::DISCORD,
+    Query(callback): Query<OAuthCallback>,
+    session: Option<Session>,
+    headers: HeaderMap,
+    jar: CookieJar,
+) -> Result<Response, AppError> {
+    let expected = jar.get(OAUTH_STATE_COOKIE).map(|c| c.value());
+    if let Err(e) = check_state(expected, callback.state.as_deref()) {
+        return Ok(failed_login(&app_state, e));
+    }
+    let code = match (callback.code, callback.error) {
+        (Some(code), None) => code,
+        (_, error) => {
+            let reason = error.unwrap_or("no code".to_string());
+            return Ok(failed_login(&app_state, AuthError::Denied(reason)));
+        }
     };
-    let cookie = Cookie::build(("session", session.to_base64()))
+
+    let user = match app_state.discord_client.get_user(&code).await {
+        Ok(user) => user,
+        Err(e) => return Ok(failed_login(&app_state, AuthError::Provider(e))),
+    };
+    let identity = format!("discord_{}", user.id);
+    finish_login(
+        &app_state,
+        identity,
+        user.username,
+        Authority::DISCORD,
+        &headers,
+        &jar,
+        session,
+    )
+    .await
+}
+
+/// Sends the browser off to the OIDC provider, remembering what it needs to check on
+/// the way back
+pub async fn get_oidc_start(
+    State(state): State<Arc<DatabaseAppState>>,
+) -> Result<Response, AppError> {
+    let client = state
+        .oidc_client
+        .as_ref()
+        .ok_or(AppError::Code(StatusCode::NOT_FOUND))?;
+    let flow = OidcFlow::new();
+    let url = match client.authorize_url(&flow).await {
+        Ok(url) => url,
+        Err(e) => return Ok(failed_login(&state, AuthError::Provider(e))),
+    };
+    let cookie = Cookie::build((OIDC_FLOW_COOKIE, flow.to_base64()))
         .http_only(true)
         .secure(true)
-        .path("/")
-        .permanent()
+        .same_site(SameSite::Lax)
+        .path("/login")
         .build();
 
     Ok((
         CookieJar::new().add(cookie),
-        [("location", "/")],
+        [("location", url)],
         StatusCode::SEE_OTHER,
-    ))
+    )
+        .into_response())
+}
+
+/// Where the OIDC provider sends the browser back to
+pub async fn get_oidc(
+    State(state): State<Arc<DatabaseAppState>>,
+    Query(callback): Query<OAuthCallback>,
+    session: Option<Session>,
+    headers: HeaderMap,
+    jar: CookieJar,
+) -> Result<Response, AppError> {
+    let client = state
+        .oidc_client
+        .as_ref()
+        .ok_or(AppError::Code(StatusCode::NOT_FOUND))?;
+    let flow = jar
+        .get(OIDC_FLOW_COOKIE)
+        .and_then(|c| OidcFlow::try_from(c.value()).ok());
+    let expected = flow.as_ref().map(|f| f.state.as_str());
+    if let Err(e) = check_state(expected, callback.state.as_deref()) {
+        return Ok(failed_login(&state, e));
+    }
+    let (Some(flow), Some(code), None) = (flow, callback.code, &callback.error) else {
+        let reason = callback.error.unwrap_or("no code".to_string());
+        return Ok(failed_login(&state, AuthError::Denied(reason)));
+    };
+
+    let user = match client.get_user(&code, &flow).await {
+        Ok(user) => user,
+        Err(e) => return Ok(failed_login(&state, AuthError::Provider(e))),
+    };
+    let identity = format!("oidc_{}", user.sub);
+    let response = finish_login(
+        &state,
+        identity,
+        user.username,
+        Authority::OIDC,
+        &headers,
+        &jar,
+        session,
+    )
+    .await?;
+    Ok((
+        CookieJar::new().add(Cookie::build((OIDC_FLOW_COOKIE, "")).path("/login").removal().build()),
+        response,
+    )
+        .into_response())
+}
+
+pub async fn get_import(
+    session: Option<Session>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    let mut schedules = Schedules::from_cookies(&jar).schedules;
+    if schedules.is_empty() {
+        return Ok(([("location", "/")], StatusCode::SEE_OTHER).into_response());
+    }
+    schedules.sort_by_key(|s| (s.schedule.term, s.schedule.name.clone()));
+
+    Ok(components::base(
+        html! {
+            div class="flex justify-center p-2" {
+                form action="/login/import" method="post" class="w-full max-w-xl flex flex-col gap-2 rounded-lg p-2 bg-white dark:bg-neutral-800 shadow-xl" {
+                    h2 class="text-xl" { "Schedules in this browser" }
+                    p {
+                        "These were made before you logged in. Import them into your account to keep them, "
+                        "otherwise they will be removed from this browser."
+                    }
+                    @for schedule in &schedules {
+                        label class="flex gap-2 items-center" {
+                            input type="checkbox" name="schedule" value=(schedule.id) checked {}
+                            span class="grow" { (schedule.schedule.name) }
+                            span { (schedule.schedule.term.human_display()) }
+                        }
+                    }
+                    div class="flex gap-2 justify-end" {
+                        button name="action" value="discard" class="bg-red-500 dark:bg-red-600 hover:bg-red-700 hover:dark:bg-red-800 rounded-lg transition px-2" {
+                            "discard"
+                        }
+                        button name="action" value="import" class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 rounded-lg transition px-2" {
+                            "import"
+                        }
+                    }
+                }
+            }
+        },
+        Some(session),
+    )
+    .into_response())
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportAction {
+    Import,
+    Discard,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Import {
+    action: ImportAction,
+    #[serde(default)] // if no box checked, field does not exist
+    schedule: Vec<String>,
+}
+
+pub async fn post_import(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+    jar: CookieJar,
+    Form(Import { action, schedule }): Form<Import>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+
+    let mut removals = CookieJar::new();
+    for cookie_schedule in Schedules::from_cookies(&jar).schedules {
+        let id = cookie_schedule.id;
+        let history_name = History::cookie_name(&id);
+        if action == ImportAction::Import && schedule.contains(&id) {
+            let history = jar
+                .get(&history_name)
+                .and_then(|c| History::try_from(c.value()).ok())
+                .unwrap_or_default();
+            state
+                .save_schedule(Some(&session), &id, &cookie_schedule.schedule, &history)
+                .await?;
+            debug!("imported schedule {} into {}", id, session.user_id);
+        }
+        removals = removals
+            .add(Cookie::build((history_name, "")).path("/").removal().build())
+            .add(Cookie::build((id, "")).path("/").removal().build());
+    }
+
+    Ok((removals, [("location", "/")], StatusCode::SEE_OTHER))
 }
@@ -6,40 +6,50 @@ use axum::{
 };
 use maud::html;
 use serde::Deserialize;
+use tracing::instrument;
 
 use crate::{
-    common::{selected_sections, AppError, Schedule},
+    common::{selected_sections, AppError, CalendarView, Schedule},
     components,
     data::DatabaseAppState,
-    scraper::ThinSection,
+    scraper::{ThinCourse, ThinSection},
 };
 
-fn default_crn() -> u64 {
-    0
-}
-
 #[derive(Deserialize, Debug)]
 pub struct Preview {
-    #[serde(default = "default_crn")]
-    crn: u64,
+    /// A section to try in place of the course's current one
+    crn: Option<u64>,
+    /// A course to try adding, with the sections it would be added with
+    course: Option<ThinCourse>,
 }
 
+/// The calendar with a section or course ghosted onto it, as it would be if it were picked
+#[instrument(level = "debug", skip(state))]
 pub async fn preview(
-    Path(_schedule_id): Path<String>,
+    Path(schedule_id): Path<String>,
     State(state): State<Arc<DatabaseAppState>>,
-    Query(Preview { crn }): Query<Preview>,
+    Query(Preview { crn, course }): Query<Preview>,
     schedule: Schedule,
+    calendar: CalendarView,
 ) -> Result<impl IntoResponse, AppError> {
     let selected = schedule.selected;
-    let previewed = if crn != 0 && !selected.crns().contains(&crn) {
-        let thin_section = ThinSection { crn };
-        vec![state.get_section(&schedule.term, &thin_section)?]
-    } else {
-        vec![]
+    let previewed_crns = match (crn, course) {
+        (Some(crn), _) if !selected.crns().contains(&crn) => vec![crn],
+        (_, Some(course)) if !selected.courses.contains_key(&course) => state
+            .default_thin_sections(&schedule.term, course)?
+            .map(|s| s.crns())
+            .unwrap_or_default(),
+        _ => vec![],
     };
+    let previewed = previewed_crns
+        .into_iter()
+        .map(|crn| state.get_section(&schedule.term, &ThinSection { crn }))
+        .collect::<anyhow::Result<Vec<_>>>()?;
 
     let courses = state.courses(schedule.term, &selected.thin_courses())?;
     let sections = selected_sections(&courses, &selected);
 
-    Ok(html!((components::calendar::view(&sections, &previewed))))
+    Ok(html! {
+        (components::calendar::week_view(&schedule_id, &sections, &previewed, &selected, calendar))
+    })
 }
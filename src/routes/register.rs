@@ -0,0 +1,25 @@
+use axum::extract::{Path, State};
+use maud::Markup;
+use std::sync::Arc;
+use tracing::instrument;
+
+use crate::{
+    common::{selected_sections, AppError, Schedule},
+    components,
+    data::{store::Session, DatabaseAppState},
+};
+
+#[instrument(level = "debug", skip(state))]
+pub async fn get(
+    Path(schedule_id): Path<String>,
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+    session: Option<Session>,
+) -> Result<Markup, AppError> {
+    let courses = state.courses(schedule.term, &schedule.selected.thin_courses())?;
+    let sections = selected_sections(&courses, &schedule.selected);
+    Ok(components::base(
+        components::register::page(&schedule_id, &schedule, &courses, &sections),
+        session,
+    ))
+}
@@ -1,4 +1,5 @@
 use axum::extract::State;
+use axum_extra::extract::CookieJar;
 use maud::{html, Markup};
 use std::sync::Arc;
 use tracing::instrument;
@@ -14,10 +15,21 @@ pub async fn root(
     State(state): State<Arc<DatabaseAppState>>,
     schedules: Schedules,
     session: Option<Session>,
+    jar: CookieJar,
 ) -> Markup {
+    // schedules made before logging in that weren't imported or discarded yet
+    let importable = match session {
+        Some(_) => Schedules::from_cookies(&jar).schedules.len(),
+        None => 0,
+    };
     components::base(
         html! {
             div class="flex flex-col gap-2 py-2 px-2 lg:px-64 h-full justify-items-center" {
+                @if importable > 0 {
+                    a href="/login/import" class="rounded-lg p-2 bg-amber-200 dark:bg-amber-800 hover:underline" {
+                        (importable) " schedule(s) saved in this browser aren't in your account yet"
+                    }
+                }
                 form action="/schedule" method="post" class="flex gap-2" {
                     input type="text" id="name" name="name" size="10" placeholder="schedule name" class="p-2 rounded-lg text-black grow border-neutral-400 border-2 dark:border-0" {}
                     select name="term" id="term" class="text-black rounded-lg p-2 border-2 border-neutral-400 dark:border-0" {
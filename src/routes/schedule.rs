@@ -5,6 +5,7 @@ use axum::{
     response::IntoResponse,
 };
 use axum_extra::extract::{cookie::Cookie, CookieJar, Form};
+use jiff::Timestamp;
 use maud::{html, Markup};
 use reqwest::StatusCode;
 use serde::Deserialize;
@@ -13,7 +14,10 @@ use tracing::instrument;
 use uuid::Uuid;
 
 use crate::{
-    common::{selected_sections, AppError, Schedule, Schedules, SelectedCourses},
+    common::{
+        selected_sections, AppError, CalendarView, History, Schedule, Schedules, SelectedCourses,
+        TrashedSchedule,
+    },
     components::{self, schedules},
     data::{store::Session, DatabaseAppState},
     scraper::Term,
@@ -24,15 +28,19 @@ pub async fn get(
     Path(schedule_id): Path<String>,
     State(state): State<Arc<DatabaseAppState>>,
     schedule: Schedule,
+    history: History,
+    calendar: CalendarView,
     session: Option<Session>,
 ) -> Result<Markup, AppError> {
     let search_courses = state.thin_courses(schedule.term)?;
     let courses = state.courses(schedule.term, &schedule.selected.thin_courses())?;
     let sections = selected_sections(&courses, &schedule.selected);
+    let changes = state.schedule_changes(&schedule, &courses)?;
 
     Ok(components::base(
         html! {
-            (components::container::main_container(&schedule_id, &search_courses, &courses, &sections))
+            (components::container::main_container(&schedule_id, &search_courses, &courses, &sections, &history, &schedule, calendar))
+            (components::changes::banner(&schedule_id, &changes))
         },
         session,
     ))
@@ -52,14 +60,12 @@ pub async fn post(
 ) -> Result<impl IntoResponse, AppError> {
     let uuid = Uuid::new_v4();
     let term: Term = term.parse().unwrap();
-    let name = match name.len() > 100 {
-        true => name[..100].to_string(),
-        false => name,
-    };
     let new_schedule = Schedule {
-        name,
+        name: schedule_name(&name),
         term,
         selected: SelectedCourses::default(),
+        changes_seen_at: Some(Timestamp::now()),
+        block_conflicts: false,
     };
     if state.get_terms().contains(&term) {
         let jar = match session {
@@ -82,6 +88,124 @@ pub async fn post(
     }
 }
 
+/// Names are kept short enough to fit in the schedule list
+fn schedule_name(name: &str) -> String {
+    name.chars().take(100).collect()
+}
+
+fn see_other(schedule_id: &str, page: &str) -> impl IntoResponse {
+    (
+        [("location", format!("/schedule/{}{}", schedule_id, page))],
+        StatusCode::SEE_OTHER,
+    )
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn edit(
+    Path(schedule_id): Path<String>,
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+    session: Option<Session>,
+) -> Result<Markup, AppError> {
+    Ok(components::base(
+        schedules::edit_page(&schedule_id, &schedule, &state.get_terms()),
+        session,
+    ))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Rename {
+    name: String,
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn rename(
+    Path(schedule_id): Path<String>,
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+    session: Option<Session>,
+    Form(Rename { name }): Form<Rename>,
+) -> Result<impl IntoResponse, AppError> {
+    let name = schedule_name(name.trim());
+    if name.is_empty() {
+        return Err(AppError::Code(StatusCode::BAD_REQUEST));
+    }
+    let schedule = Schedule { name, ..schedule };
+    let jar = match session {
+        Some(sess) => {
+            state
+                .set_user_schedule(&sess.user_id, &schedule_id, &schedule)
+                .await?;
+            CookieJar::new()
+        }
+        None => CookieJar::new().add(schedule.make_cookie(schedule_id.clone())),
+    };
+
+    Ok((jar, see_other(&schedule_id, "/edit")))
+}
+
+/// Makes a copy of the schedule, undo history and all, to try out a variant of it
+#[instrument(level = "debug", skip(state))]
+pub async fn duplicate(
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+    history: History,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let copy_id = Uuid::new_v4().to_string();
+    let copy = Schedule {
+        name: schedule_name(&format!("{} (copy)", schedule.name)),
+        ..schedule
+    };
+    let jar = state
+        .save_schedule(session.as_ref(), &copy_id, &copy, &history)
+        .await?;
+
+    Ok((jar, see_other(&copy_id, "")))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Copy {
+    term: String,
+}
+
+/// Starts a schedule in another term with the same courses, as far as they are offered there
+#[instrument(level = "debug", skip(state))]
+pub async fn copy(
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+    session: Option<Session>,
+    Form(Copy { term }): Form<Copy>,
+) -> Result<impl IntoResponse, AppError> {
+    let term: Term = term
+        .parse()
+        .map_err(|_e| AppError::Code(StatusCode::BAD_REQUEST))?;
+    if !state.get_terms().contains(&term) {
+        return Err(AppError::Code(StatusCode::BAD_REQUEST));
+    }
+    let (selected, missing) = state.carry_over(&schedule.selected, &term)?;
+    let copy_id = Uuid::new_v4().to_string();
+    let copy = Schedule {
+        name: schedule.name,
+        term,
+        selected,
+        changes_seen_at: Some(Timestamp::now()),
+        block_conflicts: schedule.block_conflicts,
+    };
+    let jar = state
+        .save_schedule(session.as_ref(), &copy_id, &copy, &History::default())
+        .await?;
+
+    if missing.is_empty() {
+        return Ok((jar, see_other(&copy_id, "")).into_response());
+    }
+    Ok((
+        jar,
+        components::base(schedules::copied_page(&copy_id, &copy, &missing), session),
+    )
+        .into_response())
+}
+
 #[instrument(level = "debug", skip(state))]
 pub async fn delete(
     State(state): State<Arc<DatabaseAppState>>,
@@ -89,27 +213,102 @@ pub async fn delete(
     session: Option<Session>,
     schedules: Schedules,
 ) -> Result<impl IntoResponse, AppError> {
-    let new_schedules = schedules
+    // only schedules the user can see are theirs to delete
+    let (deleted, new_schedules): (Vec<_>, Vec<_>) = schedules
         .schedules
         .into_iter()
-        .filter(|s| !s.id.eq(&schedule_id))
-        .collect();
-    let mut jar = CookieJar::new();
+        .partition(|s| s.id.eq(&schedule_id));
+    let deleted = deleted
+        .into_iter()
+        .next()
+        .ok_or(AppError::Code(StatusCode::NOT_FOUND))?;
+    let mut jar = CookieJar::new().add(
+        Cookie::build((CalendarView::week_cookie_name(&schedule_id), ""))
+            .path("/")
+            .removal()
+            .build(),
+    );
 
     match session {
         Some(sess) => {
             state
-                .delete_user_schedule(&sess.user_id, &schedule_id)
-                .await;
+                .trash_user_schedule(&sess.user_id, &schedule_id)
+                .await?;
         }
         None => {
-            jar = jar.add(Cookie::build((schedule_id, "")).path("/").removal().build());
+            // the history doesn't fit in the trash cookie, so a restored schedule starts over
+            let history_cookie = History::cookie_name(&schedule_id);
+            jar = jar
+                .add(TrashedSchedule::new(deleted).make_cookie())
+                .add(Cookie::build((history_cookie, "")).path("/").removal().build())
+                .add(Cookie::build((schedule_id, "")).path("/").removal().build());
         }
     };
 
     Ok((jar, schedules::view(new_schedules)))
 }
 
+/// Hides the changes banner until something else changes
+#[instrument(level = "debug", skip(state))]
+pub async fn dismiss_changes(
+    Path(schedule_id): Path<String>,
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let schedule = Schedule {
+        changes_seen_at: Some(Timestamp::now()),
+        ..schedule
+    };
+    let jar = match session {
+        Some(sess) => {
+            state
+                .set_user_schedule(&sess.user_id, &schedule_id, &schedule)
+                .await?;
+            CookieJar::new()
+        }
+        None => CookieJar::new().add(schedule.make_cookie(schedule_id)),
+    };
+
+    Ok((jar, html!()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlockConflicts {
+    block: Option<bool>,
+}
+
+/// Turns blocking sections that would clash with the rest of the schedule on or off
+#[instrument(level = "debug", skip(state))]
+pub async fn block_conflicts(
+    Path(schedule_id): Path<String>,
+    State(state): State<Arc<DatabaseAppState>>,
+    schedule: Schedule,
+    session: Option<Session>,
+    Form(BlockConflicts { block }): Form<BlockConflicts>,
+) -> Result<impl IntoResponse, AppError> {
+    let schedule = Schedule {
+        block_conflicts: block.unwrap_or(false),
+        ..schedule
+    };
+    let jar = match session {
+        Some(sess) => {
+            state
+                .set_user_schedule(&sess.user_id, &schedule_id, &schedule)
+                .await?;
+            CookieJar::new()
+        }
+        None => CookieJar::new().add(schedule.make_cookie(schedule_id.clone())),
+    };
+
+    let courses = state.courses(schedule.term, &schedule.selected.thin_courses())?;
+    let sections = selected_sections(&courses, &schedule.selected);
+    Ok((
+        jar,
+        components::courses::view(&schedule_id, &courses, &sections, &schedule),
+    ))
+}
+
 #[instrument(level = "debug", skip(_state))]
 #[debug_middleware]
 pub async fn not_found(
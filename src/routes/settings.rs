@@ -0,0 +1,377 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::header::CONTENT_DISPOSITION,
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::{cookie::Cookie, CookieJar, Form, Query};
+use jiff::Timestamp;
+use maud::{html, Markup};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tracing::{instrument, warn};
+
+use crate::{
+    common::AppError,
+    components,
+    data::{
+        notify::Contact,
+        store::{PendingEmail, Preferences, Session, SessionInfo},
+        DatabaseAppState,
+    },
+};
+
+fn notifications_form(
+    identities: &[String],
+    preferences: &Preferences,
+    message: Option<&str>,
+) -> Markup {
+    let email = match (&preferences.pending_email, &preferences.contact) {
+        (Some(pending), _) => pending.address.as_str(),
+        (None, Some(Contact::Email { address })) => address.as_str(),
+        _ => "",
+    };
+    let is_discord = Contact::from_identities(identities).is_some();
+    html! {
+        form id="notifications" class="flex flex-col gap-2" hx-post="/settings/notifications" hx-swap="outerHTML" {
+            h2 class="text-xl" { "notifications" }
+            label class="flex gap-2 items-center" {
+                input type="checkbox" name="notify_changes" value="true" checked[preferences.notify_changes] {}
+                "tell me when a section in one of my schedules is cancelled or moved"
+            }
+            label class="flex flex-col" {
+                @if is_discord {
+                    "email (leave blank to be messaged on discord)"
+                } @else {
+                    "email"
+                }
+                input type="email" name="email" value=(email)
+                    class="rounded p-1 bg-slate-100 dark:bg-neutral-900" {}
+            }
+            @if let Some(pending) = &preferences.pending_email {
+                p class="text-sm" {
+                    "Follow the link we emailed to " (pending.address) " to start getting notifications there."
+                }
+            }
+            div class="flex gap-2 items-center" {
+                button class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 transition rounded-lg px-2" {
+                    "save"
+                }
+                @if let Some(message) = message {
+                    (message)
+                }
+            }
+        }
+    }
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn get(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+) -> Result<Markup, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    let preferences = state.get_preferences(&session.user_id).await?;
+    let sessions = state.list_sessions(&session.user_id).await?;
+    let identities = state.list_identities(&session.user_id).await?;
+
+    Ok(components::base(
+        html! {
+            div class="flex justify-center p-2" {
+                div class="w-full max-w-xl flex flex-col gap-4 rounded-lg p-2 bg-white dark:bg-neutral-800 shadow-xl" {
+                    (notifications_form(&identities, &preferences, None))
+                    (identities_list(&state, &identities))
+                    (sessions_list(&session, &sessions))
+                    (data_controls())
+                }
+            }
+        },
+        Some(session),
+    ))
+}
+
+/// Which provider a login like `google_<sub>` is with
+fn provider_name<'a>(state: &'a DatabaseAppState, identity: &str) -> &'a str {
+    match identity.split_once('_').map(|(provider, _)| provider) {
+        Some("google") => "Google",
+        Some("discord") => "Discord",
+        Some("oidc") => state
+            .oidc_client
+            .as_ref()
+            .map(|c| c.config.name.as_str())
+            .unwrap_or("single sign-on"),
+        _ => "unknown",
+    }
+}
+
+fn identities_list(state: &DatabaseAppState, identities: &[String]) -> Markup {
+    html! {
+        div id="identities" class="flex flex-col gap-2" {
+            h2 class="text-xl" { "linked logins" }
+            @for identity in identities {
+                div class="border-b border-slate-200 dark:border-neutral-700 py-1" {
+                    (provider_name(state, identity))
+                }
+            }
+            div class="flex justify-end" {
+                a href="/login/link" class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 transition rounded-lg px-2" {
+                    "link another login"
+                }
+            }
+        }
+    }
+}
+
+fn format_time(time: Option<Timestamp>) -> String {
+    match time {
+        Some(t) => t.strftime("%Y-%m-%d %H:%M UTC").to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+fn sessions_list(current: &Session, sessions: &[SessionInfo]) -> Markup {
+    html! {
+        div id="sessions" class="flex flex-col gap-2" {
+            h2 class="text-xl" { "logged in devices" }
+            @for info in sessions {
+                div class="flex items-center gap-2 border-b border-slate-200 dark:border-neutral-700 py-1" {
+                    div class="grow flex flex-col min-w-0" {
+                        span class="truncate" {
+                            (info.user_agent.as_deref().unwrap_or("unknown device"))
+                        }
+                        span class="text-xs text-neutral-500" {
+                            "logged in " (format_time(info.created_at))
+                            " · last used " (format_time(info.last_used))
+                        }
+                    }
+                    @if info.session_id == current.session_id {
+                        span class="px-2 whitespace-nowrap" { "this device" }
+                    } @else {
+                        button class="bg-red-500 dark:bg-red-600 hover:bg-red-700 hover:dark:bg-red-800 transition rounded-lg px-2"
+                            hx-delete={"/settings/sessions/" (info.session_id)}
+                            hx-target="#sessions"
+                            hx-swap="outerHTML" {
+                            "revoke"
+                        }
+                    }
+                }
+            }
+            div class="flex justify-end" {
+                button class="bg-red-500 dark:bg-red-600 hover:bg-red-700 hover:dark:bg-red-800 transition rounded-lg px-2"
+                    hx-delete="/settings/sessions"
+                    hx-confirm="Log out on every device, including this one?" {
+                    "log out everywhere"
+                }
+            }
+        }
+    }
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn revoke_session(
+    State(state): State<Arc<DatabaseAppState>>,
+    Path(session_id): Path<String>,
+    session: Option<Session>,
+) -> Result<Markup, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    // sessions are keyed by user, so this can only ever revoke the user's own
+    state
+        .revoke_session(&session.user_id, &session_id)
+        .await?;
+    let sessions = state.list_sessions(&session.user_id).await?;
+    Ok(sessions_list(&session, &sessions))
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn revoke_all_sessions(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    state.revoke_all_sessions(&session.user_id).await?;
+    Ok((
+        CookieJar::new().add(Cookie::build(("session", "")).path("/").removal().build()),
+        [("hx-location", "/login")],
+    ))
+}
+
+fn data_controls() -> Markup {
+    html! {
+        div id="data" class="flex flex-col gap-2" {
+            h2 class="text-xl" { "your data" }
+            p class="text-sm" {
+                "Download your schedules, their history, your settings and logged in devices as JSON, "
+                "or delete all of it. Share links hold their own copy of a schedule and keep working."
+            }
+            div class="flex justify-end gap-2" {
+                a href="/settings/export" download="scheduler-export.json"
+                    class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 transition rounded-lg px-2" {
+                    "download my data"
+                }
+                button class="bg-red-500 dark:bg-red-600 hover:bg-red-700 hover:dark:bg-red-800 transition rounded-lg px-2"
+                    hx-delete="/settings/account"
+                    hx-confirm="Delete your account and every schedule in it? This can't be undone." {
+                    "delete my account"
+                }
+            }
+        }
+    }
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn export(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    let export = state.export_account(&session.user_id).await?;
+    Ok((
+        [(
+            CONTENT_DISPOSITION,
+            "attachment; filename=\"scheduler-export.json\"",
+        )],
+        Json(export),
+    ))
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn delete_account(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    state.delete_account(&session.user_id).await?;
+    Ok((
+        CookieJar::new().add(Cookie::build(("session", "")).path("/").removal().build()),
+        [("hx-location", "/")],
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Notifications {
+    notify_changes: Option<bool>,
+    email: Option<String>,
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn post_notifications(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+    Form(form): Form<Notifications>,
+) -> Result<Markup, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    let identities = state.list_identities(&session.user_id).await?;
+    let old = state.get_preferences(&session.user_id).await?;
+    let confirmed = match &old.contact {
+        Some(Contact::Email { address }) => Some(address.as_str()),
+        _ => None,
+    };
+
+    let mut message = "saved";
+    let (contact, pending_email) = match form.email.as_deref().map(str::trim) {
+        None | Some("") => (Contact::from_identities(&identities), None),
+        Some(address) if confirmed == Some(address) => (old.contact.clone(), None),
+        Some(address) => {
+            let pending = match &old.pending_email {
+                Some(pending) if pending.address == address => pending.clone(),
+                Some(pending) if !pending.may_resend(Timestamp::now()) => {
+                    return Ok(notifications_form(
+                        &identities,
+                        &old,
+                        Some("an email was just sent, try again in a few minutes"),
+                    ));
+                }
+                _ => {
+                    let pending = PendingEmail::new(address.to_string());
+                    if let Err(e) = send_confirmation(&state, &pending).await {
+                        warn!("could not send confirmation email: {e:#}");
+                        return Ok(notifications_form(
+                            &identities,
+                            &old,
+                            Some("couldn't send an email to that address"),
+                        ));
+                    }
+                    pending
+                }
+            };
+            message = "saved, follow the link we emailed you to confirm your address";
+            // notifications keep going where they did until the new address is confirmed
+            let contact = old
+                .contact
+                .clone()
+                .or(Contact::from_identities(&identities));
+            (contact, Some(pending))
+        }
+    };
+    let preferences = Preferences {
+        notify_changes: form.notify_changes.unwrap_or(false),
+        contact,
+        pending_email,
+    };
+
+    if preferences.notify_changes
+        && preferences.contact.is_none()
+        && preferences.pending_email.is_none()
+    {
+        return Ok(notifications_form(
+            &identities,
+            &preferences,
+            Some("an email address is needed to send notifications"),
+        ));
+    }
+
+    state
+        .set_preferences(&session.user_id, &preferences)
+        .await?;
+    Ok(notifications_form(&identities, &preferences, Some(message)))
+}
+
+async fn send_confirmation(state: &DatabaseAppState, pending: &PendingEmail) -> anyhow::Result<()> {
+    let body = format!(
+        "Follow this link to get notifications from scheduler at this address:\n\n\
+        {}/settings/email/confirm?token={}\n\n\
+        It works for a day. If you didn't ask for this, you can ignore this email.",
+        state.stage.public_url(),
+        pending.token
+    );
+    state
+        .notifier
+        .send(
+            &Contact::Email {
+                address: pending.address.clone(),
+            },
+            "Confirm your email",
+            &body,
+        )
+        .await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmailConfirmation {
+    token: String,
+}
+
+/// Where the link in a confirmation email goes, making the address the user's contact
+#[instrument(level = "debug", skip(state))]
+pub async fn confirm_email(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+    Query(EmailConfirmation { token }): Query<EmailConfirmation>,
+) -> Result<impl IntoResponse, AppError> {
+    let session = session.ok_or(AppError::Code(StatusCode::UNAUTHORIZED))?;
+    let mut preferences = state.get_preferences(&session.user_id).await?;
+    let pending = preferences
+        .pending_email
+        .take()
+        .filter(|p| p.confirms(&token, Timestamp::now()))
+        .ok_or(AppError::Code(StatusCode::BAD_REQUEST))?;
+    preferences.contact = Some(Contact::Email {
+        address: pending.address,
+    });
+    state
+        .set_preferences(&session.user_id, &preferences)
+        .await?;
+    Ok(([("location", "/settings")], StatusCode::SEE_OTHER))
+}
@@ -24,7 +24,11 @@ pub async fn get(
                         "back"
                     }
                     textarea class="p-2 w-full text-black rounded-lg grow border-neutral-400 border-2 dark:border-0" readonly {
-                        (uri.scheme_str().unwrap_or("http")) "://" (host) "/import?blob=" (schedule.to_base64())
+                        (uri.scheme_str().unwrap_or("http")) "://" (host) "/import/preview?blob=" (schedule.to_base64())
+                    }
+                    div class="flex gap-2" {
+                        (components::button::link(&format!("/schedule/{schedule_id}/image.png"), html!("png image")))
+                        (components::button::link(&format!("/schedule/{schedule_id}/image.svg"), html!("svg image")))
                     }
                 }
             }
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+};
+use axum_extra::extract::{cookie::Cookie, CookieJar};
+use maud::{html, Markup};
+use reqwest::StatusCode;
+use tracing::instrument;
+
+use crate::{
+    common::{AppError, TrashedSchedule},
+    components,
+    data::{store::Session, DatabaseAppState},
+};
+
+/// Trashed schedules of a user who isn't logged in
+fn from_cookies(jar: &CookieJar) -> Vec<TrashedSchedule> {
+    let mut trash: Vec<TrashedSchedule> = jar
+        .iter()
+        .filter(|c| c.name().starts_with("trash_"))
+        .filter_map(|c| TrashedSchedule::try_from(c.value()).ok())
+        .filter(|t| !t.is_expired())
+        .collect();
+    trash.sort_by_key(|t| std::cmp::Reverse(t.deleted_at));
+    trash
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn get(
+    State(state): State<Arc<DatabaseAppState>>,
+    session: Option<Session>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AppError> {
+    let (trash, removals) = match &session {
+        Some(session) => (state.list_trash(&session.user_id).await?, CookieJar::new()),
+        None => {
+            // the browser would keep expired ones around, so clear them out while we're here
+            let removals = jar
+                .iter()
+                .filter(|c| c.name().starts_with("trash_"))
+                .filter(|c| TrashedSchedule::try_from(c.value()).map_or(true, |t| t.is_expired()))
+                .fold(CookieJar::new(), |removals, c| {
+                    removals.add(
+                        Cookie::build((c.name().to_string(), ""))
+                            .path("/")
+                            .removal()
+                            .build(),
+                    )
+                });
+            (from_cookies(&jar), removals)
+        }
+    };
+
+    Ok((removals, components::base(view(&trash), session)))
+}
+
+fn view(trash: &[TrashedSchedule]) -> Markup {
+    html! {
+        div class="flex flex-col gap-2 py-2 px-2 lg:px-64" {
+            h2 class="text-xl" { "recently deleted" }
+            p class="text-sm" {
+                "Deleted schedules can be restored for " (TrashedSchedule::RETENTION_DAYS) " days."
+            }
+            @if trash.is_empty() {
+                p { "nothing here" }
+            }
+            @for trashed in trash {
+                form method="post" action={"/trash/" (trashed.id) "/restore"} class="flex gap-2 items-center rounded-lg p-2 bg-white dark:bg-neutral-800" {
+                    div class="grow flex flex-col" {
+                        span { (trashed.schedule.name) " · " (trashed.schedule.term.human_display()) }
+                        span class="text-xs text-neutral-500" {
+                            "deleted " (trashed.deleted_at.strftime("%Y-%m-%d"))
+                            ", gone for good " (trashed.purge_at().strftime("%Y-%m-%d"))
+                        }
+                    }
+                    button class="bg-green-500 dark:bg-green-600 hover:bg-green-700 hover:dark:bg-green-800 rounded-lg transition px-2" {
+                        "restore"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[instrument(level = "debug", skip(state))]
+pub async fn restore(
+    State(state): State<Arc<DatabaseAppState>>,
+    Path(schedule_id): Path<String>,
+    session: Option<Session>,
+    jar: CookieJar,
+) -> Result<impl IntoResponse, AppError> {
+    let jar = match session {
+        Some(session) => {
+            let owned = state
+                .list_trash(&session.user_id)
+                .await?
+                .iter()
+                .any(|t| t.id == schedule_id);
+            if !owned {
+                return Err(AppError::Code(StatusCode::NOT_FOUND));
+            }
+            state
+                .restore_user_schedule(&session.user_id, &schedule_id)
+                .await?;
+            CookieJar::new()
+        }
+        None => {
+            let trashed = from_cookies(&jar)
+                .into_iter()
+                .find(|t| t.id == schedule_id)
+                .ok_or(AppError::Code(StatusCode::NOT_FOUND))?;
+            CookieJar::new()
+                .add(
+                    Cookie::build((TrashedSchedule::cookie_name(&schedule_id), ""))
+                        .path("/")
+                        .removal()
+                        .build(),
+                )
+                .add(trashed.schedule.make_cookie(schedule_id.clone()))
+        }
+    };
+
+    Ok((
+        jar,
+        [("location", format!("/schedule/{}", schedule_id))],
+        StatusCode::SEE_OTHER,
+    ))
+}
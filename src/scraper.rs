@@ -1,18 +1,27 @@
 use core::fmt;
-use rusqlite::Connection;
+use rusqlite::{Connection, OpenFlags, Row};
 use serde::{Deserialize, Deserializer, Serialize};
-use std::{cmp::Ordering, fmt::Display, path::Path, str::FromStr};
-use tracing::info;
+use std::{
+    cmp::Ordering, collections::HashMap, fmt::Display, path::Path, str::FromStr, sync::Arc,
+    time::Duration,
+};
+use tracing::{info, warn};
 
 use anyhow::{anyhow, bail, Context, Result};
 use clap::ValueEnum;
 use jiff::{
     civil::{date, Date, Time},
-    ToSpan, Zoned,
+    Timestamp, ToSpan, Zoned,
 };
 
 use crate::common::SectionType;
 
+pub mod diff;
+pub mod report;
+
+use diff::Change;
+use report::{ScrapeReport, TermOutcome, TermReport};
+
 #[derive(
     Clone, Copy, Debug, ValueEnum, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
 )]
@@ -232,6 +241,20 @@ impl fmt::Display for Day {
     }
 }
 
+impl From<Day> for Days {
+    fn from(day: Day) -> Self {
+        Days {
+            monday: day == Day::Monday,
+            tuesday: day == Day::Tuesday,
+            wednesday: day == Day::Wednesday,
+            thursday: day == Day::Thursday,
+            friday: day == Day::Friday,
+            saturday: day == Day::Saturday,
+            sunday: day == Day::Sunday,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MeetingTime {
     pub start_time: Option<Time>,
@@ -245,6 +268,13 @@ pub struct MeetingTime {
     pub room: Option<String>,
 }
 
+impl MeetingTime {
+    /// Whether any of the meeting's weeks is the one starting `monday`
+    pub fn is_held_in_week(&self, monday: Date) -> bool {
+        self.start_date <= monday + 6.days() && monday <= self.end_date
+    }
+}
+
 impl Display for MeetingTime {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -280,6 +310,13 @@ impl Section {
             _ => panic!(),
         }
     }
+
+    pub fn thin_course(&self) -> ThinCourse {
+        ThinCourse {
+            subject_code: self.subject_code.clone(),
+            course_code: self.course_code.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -339,40 +376,136 @@ impl Serialize for ThinCourse {
     }
 }
 
-pub async fn scrape(force: bool, oldest: Option<Term>) -> Result<()> {
+/// Knobs for how hard we lean on Banner while scraping
+#[derive(Debug, Clone)]
+pub struct ScrapeOptions {
+    /// Maximum number of page requests in flight at once
+    pub concurrency: usize,
+    /// How many times a transient failure is retried before the term is given up on
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every subsequent retry
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between retries
+    pub max_backoff: Duration,
+    /// Minimum spacing between two requests to the same host
+    pub min_request_interval: Duration,
+    /// Saved progress older than this is thrown away instead of resumed
+    pub resume_max_age: Duration,
+}
+
+impl Default for ScrapeOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            min_request_interval: Duration::from_millis(250),
+            resume_max_age: Duration::from_secs(6 * 60 * 60),
+        }
+    }
+}
+
+/// Scrapes every term (optionally no older than `oldest`) into `sections_<term>.sqlite3`.
+///
+/// Only failing to fetch the list of terms is an error. A term that fails to scrape keeps its
+/// previous database and is recorded in the returned report instead.
+pub async fn scrape(
+    force: bool,
+    oldest: Option<Term>,
+    options: &ScrapeOptions,
+) -> Result<ScrapeReport> {
+    let limiter = Arc::new(scrape::RateLimiter::new(options.min_request_interval));
+
     info!("fetching list of all terms");
-    let mut terms = scrape::fetch_terms().await?;
+    let mut terms = scrape::fetch_terms(options, limiter.clone()).await?;
     if let Some(oldest) = oldest {
         terms.retain(|t| t >= &oldest)
     }
 
     let now = Zoned::now();
+    let mut report = ScrapeReport::default();
 
-    // no point in parallelizing, UVic's server is the bottleneck
+    // terms are scraped one at a time, the pages within a term are fetched concurrently
     for &term in terms.iter() {
         let filename = format!("sections_{}.sqlite3", term);
 
         if !force && term < now && Path::new(&filename).exists() {
             info!("db already downloaded for {}", term);
+            report.terms.push(TermReport {
+                term,
+                outcome: TermOutcome::Skipped,
+            });
             continue;
         }
         info!("fetching sections for term {}", term);
 
-        let courses = scrape::fetch_sections(term).await?;
-        persist(filename, &courses)?;
+        let outcome = match scrape::fetch_sections(term, options, limiter.clone()).await {
+            Ok((courses, stats)) => match persist(&filename, &courses) {
+                Ok(changes) => TermOutcome::Scraped {
+                    courses: courses.len(),
+                    stats,
+                    changes,
+                },
+                Err(e) => TermOutcome::Failed {
+                    error: format!("{e:#}"),
+                },
+            },
+            Err(e) => TermOutcome::Failed {
+                error: format!("{e:#}"),
+            },
+        };
+        if let TermOutcome::Failed { error } = &outcome {
+            warn!(%term, error, "failed to scrape term, keeping previous database");
+        }
+        report.terms.push(TermReport { term, outcome });
     }
 
-    Ok(())
+    Ok(report)
 }
 
-pub fn persist<P: AsRef<Path>>(filename: P, courses: &Vec<Course>) -> Result<()> {
+/// Writes `courses` to a fresh database at `filename`. The new database is only moved into place
+/// once it has been fully written, so a failure leaves any existing database untouched.
+///
+/// If a database already exists, the differences from it are returned and appended to the
+/// changelog, which is carried over from the old database. Nothing is written if the changelog
+/// can't be carried over, since it would be lost.
+pub fn persist<P: AsRef<Path>>(filename: P, courses: &Vec<Course>) -> Result<Vec<Change>> {
+    let filename = filename.as_ref();
+    let mut tmp_filename = filename.as_os_str().to_owned();
+    tmp_filename.push(".tmp");
+
     let conn = Connection::open_in_memory()?;
 
     store_sections(&conn, courses)?;
+    diff::create_changelog_table(&conn)?;
+
+    let changes = match filename.exists() {
+        true => {
+            let old = Connection::open_with_flags(filename, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+            diff::carry_changelog(&conn, &old).with_context(|| {
+                format!(
+                    "failed to carry over the changelog of {}",
+                    filename.display()
+                )
+            })?;
+            match load_sections(&old) {
+                Ok(old_courses) => diff::diff(&old_courses, courses),
+                Err(e) => {
+                    warn!("could not compare against {}: {e:#}", filename.display());
+                    Vec::new()
+                }
+            }
+        }
+        false => Vec::new(),
+    };
+    diff::record_changes(&conn, Timestamp::now(), &changes)?;
 
-    conn.backup(rusqlite::DatabaseName::Main, filename, None)?;
+    conn.backup(rusqlite::DatabaseName::Main, &tmp_filename, None)?;
+    std::fs::rename(&tmp_filename, filename)
+        .with_context(|| format!("failed to move new database into {}", filename.display()))?;
 
-    Ok(())
+    Ok(changes)
 }
 
 /// Store sessions to database `conn`, creating tables and writing rows. Writing to a non-empty
@@ -492,16 +625,130 @@ pub fn store_sections(conn: &Connection, courses: &Vec<Course>) -> Result<()> {
     Ok(())
 }
 
+pub(crate) fn meeting_time_from_row(row: &Row) -> Result<MeetingTime> {
+    let start_time: Option<String> = row.get("start_time")?;
+    let end_time: Option<String> = row.get("end_time")?;
+
+    let start_date: String = row.get("start_date")?;
+    let end_date: String = row.get("end_date")?;
+
+    Ok(MeetingTime {
+        start_time: start_time.map(|s| s.parse()).transpose()?,
+        end_time: end_time.map(|s| s.parse()).transpose()?,
+        start_date: start_date.parse()?,
+        end_date: end_date.parse()?,
+
+        days: Days {
+            monday: row.get("monday")?,
+            tuesday: row.get("tuesday")?,
+            wednesday: row.get("wednesday")?,
+            thursday: row.get("thursday")?,
+            friday: row.get("friday")?,
+            saturday: row.get("saturday")?,
+            sunday: row.get("sunday")?,
+        },
+
+        building: row.get("building")?,
+        room: row.get("room")?,
+    })
+}
+
+/// Reads every course back out of a database written by [`store_sections`]
+pub fn load_sections(conn: &Connection) -> Result<Vec<Course>> {
+    let mut meeting_times: HashMap<u64, Vec<MeetingTime>> = HashMap::new();
+    conn.prepare(
+        "SELECT
+            crn, start_time, end_time, start_date, end_date,
+            monday, tuesday, wednesday, thursday, friday, saturday, sunday,
+            building, room
+        FROM meeting_time",
+    )?
+    .query_and_then((), |row| Ok((row.get("crn")?, meeting_time_from_row(row)?)))?
+    .try_for_each(|res: Result<(u64, MeetingTime)>| -> Result<()> {
+        let (crn, mt) = res?;
+        meeting_times.entry(crn).or_default().push(mt);
+        Ok(())
+    })?;
+
+    let mut sections: HashMap<(String, String), Vec<Section>> = HashMap::new();
+    conn.prepare(
+        "SELECT
+            crn, subject_code, course_code, sequence_code,
+            enrollment, enrollment_capacity, waitlist, waitlist_capacity
+        FROM section
+        ORDER BY sequence_code",
+    )?
+    .query_and_then((), |row| {
+        let crn = row.get("crn")?;
+        Ok(Section {
+            crn,
+            subject_code: row.get("subject_code")?,
+            course_code: row.get("course_code")?,
+            sequence_code: row.get("sequence_code")?,
+            enrollment: row.get("enrollment")?,
+            enrollment_capacity: row.get("enrollment_capacity")?,
+            waitlist: row.get("waitlist")?,
+            waitlist_capacity: row.get("waitlist_capacity")?,
+            meeting_times: meeting_times.remove(&crn).unwrap_or_default(),
+        })
+    })?
+    .try_for_each(|res: Result<Section>| -> Result<()> {
+        let section = res?;
+        sections
+            .entry((section.subject_code.clone(), section.course_code.clone()))
+            .or_default()
+            .push(section);
+        Ok(())
+    })?;
+
+    let courses = conn
+        .prepare(
+            "SELECT subject_code, course_code, title, campus
+            FROM course
+            ORDER BY subject_code, course_code",
+        )?
+        .query_and_then((), |row| {
+            let subject_code: String = row.get("subject_code")?;
+            let course_code: String = row.get("course_code")?;
+            Ok(Course {
+                sections: sections
+                    .remove(&(subject_code.clone(), course_code.clone()))
+                    .unwrap_or_default(),
+                subject_code,
+                course_code,
+                title: row.get("title")?,
+                campus: row.get("campus")?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(courses)
+}
+
 pub mod scrape {
-    use std::collections::HashMap;
+    use std::{
+        collections::{BTreeMap, HashMap, HashSet},
+        future::Future,
+        path::PathBuf,
+        sync::{
+            atomic::{self, AtomicU32},
+            Arc,
+        },
+        time::Duration,
+    };
 
     use anyhow::{anyhow, bail, Context, Ok, Result};
-    use jiff::civil::{Date, Time};
-    use reqwest::Client;
+    use futures::StreamExt;
+    use jiff::{
+        civil::{Date, Time},
+        Timestamp,
+    };
+    use reqwest::{Client, Url};
     use serde::{Deserialize, Serialize};
-    use tracing::{debug, instrument};
+    use tokio::{sync::Mutex, time::Instant};
+    use tracing::{debug, instrument, warn};
 
-    use super::Term;
+    use super::{report::FetchStats, ScrapeOptions, Term};
 
     #[derive(Deserialize, Serialize, Debug)]
     #[serde(rename_all = "camelCase")]
@@ -617,47 +864,309 @@ pub mod scrape {
 
     const URL_PREFIX: &str = "https://banner.uvic.ca/StudentRegistrationSsb/ssb";
 
-    #[instrument()]
-    pub async fn fetch_sections(term: Term) -> Result<Vec<super::Course>> {
-        let client = Client::builder().cookie_store(true).build()?;
+    /// Banner occasionally reports a slightly different total than it actually pages through.
+    /// Anything beyond this fraction is treated as a failed scrape.
+    const MISMATCH_TOLERANCE: f64 = 0.01;
 
-        debug!("fetching auth cookie");
-        // setup the good cookies
-        client
-            .get(format!(
-                "{}/classSearch/classSearch?term={}&txt_subject=CSUP&txt_courseNumber=000",
-                URL_PREFIX, term
-            ))
-            .send()
-            .await?;
+    /// Spaces out requests so that no single host sees more than one request per `interval`
+    #[derive(Debug)]
+    pub struct RateLimiter {
+        interval: Duration,
+        next_slot: Mutex<HashMap<String, Instant>>,
+    }
+
+    impl RateLimiter {
+        pub fn new(interval: Duration) -> Self {
+            Self {
+                interval,
+                next_slot: Mutex::new(HashMap::new()),
+            }
+        }
+
+        async fn wait(&self, url: &Url) {
+            let host = url.host_str().unwrap_or_default().to_string();
+            let slot = {
+                let mut next_slot = self.next_slot.lock().await;
+                let now = Instant::now();
+                let slot = next_slot.get(&host).map_or(now, |&t| t.max(now));
+                next_slot.insert(host, slot + self.interval);
+                slot
+            };
+            tokio::time::sleep_until(slot).await;
+        }
+    }
+
+    /// Error from a single request attempt, split by whether retrying could help
+    #[derive(Debug)]
+    enum FetchError {
+        Transient(anyhow::Error),
+        Fatal(anyhow::Error),
+    }
+
+    impl From<reqwest::Error> for FetchError {
+        fn from(e: reqwest::Error) -> Self {
+            let transient = match e.status() {
+                Some(status) => status.is_server_error() || status.as_u16() == 429,
+                None => e.is_timeout() || e.is_connect() || e.is_request() || e.is_body(),
+            };
+            match transient {
+                true => FetchError::Transient(e.into()),
+                false => FetchError::Fatal(e.into()),
+            }
+        }
+    }
+
+    /// Delay before retry number `attempt` (starting at 0)
+    fn backoff(options: &ScrapeOptions, attempt: u32) -> Duration {
+        options
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(options.max_backoff)
+    }
+
+    /// HTTP client for one Banner session, with retries and a shared per-host rate limit
+    struct Fetcher {
+        client: Client,
+        options: ScrapeOptions,
+        limiter: Arc<RateLimiter>,
+        retries: AtomicU32,
+    }
+
+    impl Fetcher {
+        fn new(options: &ScrapeOptions, limiter: Arc<RateLimiter>) -> Result<Self> {
+            let client = Client::builder()
+                .cookie_store(true)
+                .timeout(Duration::from_secs(120))
+                .build()?;
+            Ok(Self {
+                client,
+                options: options.clone(),
+                limiter,
+                retries: AtomicU32::new(0),
+            })
+        }
+
+        async fn get_text(&self, url: &Url) -> Result<String, FetchError> {
+            self.limiter.wait(url).await;
+            let text = self
+                .client
+                .get(url.clone())
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            Result::Ok(text)
+        }
+
+        /// Runs `attempt` until it succeeds, fails fatally, or runs out of retries
+        async fn with_retries<T, F, Fut>(&self, what: &str, mut attempt: F) -> Result<T>
+        where
+            F: FnMut() -> Fut,
+            Fut: Future<Output = Result<T, FetchError>>,
+        {
+            let mut retry = 0;
+            loop {
+                match attempt().await {
+                    Result::Ok(value) => return Ok(value),
+                    Err(FetchError::Fatal(e)) => return Err(e.context(format!("{what} failed"))),
+                    Err(FetchError::Transient(e)) if retry >= self.options.max_retries => {
+                        return Err(e.context(format!("{what} failed after {retry} retries")));
+                    }
+                    Err(FetchError::Transient(e)) => {
+                        let delay = backoff(&self.options, retry);
+                        warn!(what, retry, ?delay, "transient error, retrying: {e:#}");
+                        self.retries.fetch_add(1, atomic::Ordering::Relaxed);
+                        tokio::time::sleep(delay).await;
+                        retry += 1;
+                    }
+                }
+            }
+        }
 
-        debug!("fetching first sections");
-        let res = fetch_sections_partial(client.clone(), term, 0).await?;
+        async fn fetch_page(&self, term: Term, offset: u32) -> Result<SectionResults> {
+            let url = Url::parse(&format!(
+                "{}/searchResults/searchResults?txt_term={}&pageOffset={}&pageMaxSize=10000",
+                URL_PREFIX, term, offset
+            ))?;
+            self.with_retries(&format!("fetching offset {offset}"), || async {
+                debug!("fetching offset {}", offset);
+                let text = self.get_text(&url).await?;
+                // Banner answers with an HTML error page when it's overloaded, so a response
+                // that doesn't parse is as worth retrying as a 503
+                parse_section_results(&text).map_err(FetchError::Transient)
+            })
+            .await
+        }
+    }
 
-        let mut sections = res.data;
+    fn parse_section_results(text: &str) -> Result<SectionResults> {
+        match serde_json::from_str::<SectionResults>(text) {
+            Result::Ok(results) => Ok(results),
+            Result::Err(e) => {
+                let line = text
+                    .lines()
+                    .nth(e.line().saturating_sub(1))
+                    .with_context(|| anyhow!("can't find line for error: {}", e))?;
+                bail!("line: {}\nerr: {}", line, e);
+            }
+        }
+    }
 
-        let sections_num = u32::try_from(sections.len())?;
-        let sections_left = res.total_count - sections_num;
-        // ceil division
-        let requests_left = sections_left.div_ceil(res.page_max_size);
+    /// Pages fetched so far for a term, saved next to the database so an interrupted scrape can
+    /// pick up where it left off
+    #[derive(Deserialize, Serialize, Debug, Default)]
+    struct Progress {
+        started_at: i64,
+        total_count: u32,
+        page_max_size: u32,
+        pages: BTreeMap<u32, Vec<Section>>,
+    }
 
-        let handles = (0..requests_left).map(|i| {
-            fetch_sections_partial(client.clone(), term, sections_num + i * res.page_max_size)
-        });
+    impl Progress {
+        fn path(term: Term) -> PathBuf {
+            PathBuf::from(format!("sections_{}.progress.json", term))
+        }
+
+        /// Loads saved progress for `term`, unless there is none or it's too old to trust
+        fn load(term: Term, max_age: Duration) -> Option<Self> {
+            let raw = std::fs::read(Self::path(term)).ok()?;
+            let progress: Progress = serde_json::from_slice(&raw)
+                .inspect_err(|e| warn!(%term, "ignoring unreadable scrape progress: {e}"))
+                .ok()?;
+            let age = Timestamp::now().as_second() - progress.started_at;
+            if age < 0 || age as u64 > max_age.as_secs() {
+                debug!(%term, age, "ignoring stale scrape progress");
+                return None;
+            }
+            Some(progress)
+        }
+
+        fn save(&self, term: Term) -> Result<()> {
+            std::fs::write(Self::path(term), serde_json::to_vec(self)?)
+                .context("failed to save scrape progress")
+        }
+
+        fn clear(term: Term) {
+            let _ = std::fs::remove_file(Self::path(term));
+        }
+
+        /// Merges every page into one list, dropping the duplicates that show up when Banner's
+        /// ordering shifts between pages.
+        ///
+        /// Only called once every page is in, so the saved progress is removed first: pages
+        /// that don't add up would only be resumed into the same failure, and a term that
+        /// fails to persist is better fetched afresh than from hours-old pages.
+        fn finish(self, term: Term) -> Result<Vec<Section>> {
+            Self::clear(term);
+
+            let mut seen = HashSet::new();
+            let sections = self
+                .pages
+                .into_values()
+                .flatten()
+                .filter(|s| seen.insert(s.course_reference_number.clone()))
+                .collect::<Vec<_>>();
+
+            let fetched = u32::try_from(sections.len())?;
+            let difference = fetched.abs_diff(self.total_count);
+            if f64::from(difference) > f64::from(self.total_count) * MISMATCH_TOLERANCE {
+                bail!(
+                    "expected to fetch {} sections, but actually got {}",
+                    self.total_count,
+                    fetched
+                );
+            } else if difference > 0 {
+                warn!(
+                    total_count = self.total_count,
+                    fetched, "section count mismatch within tolerance"
+                );
+            }
+            Ok(sections)
+        }
+    }
+
+    #[instrument(skip(options, limiter))]
+    pub async fn fetch_sections(
+        term: Term,
+        options: &ScrapeOptions,
+        limiter: Arc<RateLimiter>,
+    ) -> Result<(Vec<super::Course>, FetchStats)> {
+        let fetcher = Fetcher::new(options, limiter)?;
+
+        debug!("fetching auth cookie");
+        // setup the good cookies
+        let url = Url::parse(&format!(
+            "{}/classSearch/classSearch?term={}&txt_subject=CSUP&txt_courseNumber=000",
+            URL_PREFIX, term
+        ))?;
+        fetcher
+            .with_retries("fetching auth cookie", || fetcher.get_text(&url))
+            .await?;
 
-        for res in futures::future::join_all(handles).await {
-            let res = res?;
-            sections.extend(res.data);
+        let mut progress = match Progress::load(term, options.resume_max_age) {
+            Some(progress) => {
+                debug!(pages = progress.pages.len(), "resuming from saved progress");
+                progress
+            }
+            None => {
+                debug!("fetching first sections");
+                let res = fetcher.fetch_page(term, 0).await?;
+                let progress = Progress {
+                    started_at: Timestamp::now().as_second(),
+                    total_count: res.total_count,
+                    page_max_size: res.page_max_size.max(1),
+                    pages: BTreeMap::from([(0, res.data)]),
+                };
+                progress.save(term)?;
+                progress
+            }
+        };
+        let resumed_pages = progress.pages.len() - 1;
+
+        // the first page tells us how big every other page is
+        let first_page_len = u32::try_from(progress.pages.get(&0).map_or(0, Vec::len))?;
+        let sections_left = progress.total_count.saturating_sub(first_page_len);
+        let requests_left = sections_left.div_ceil(progress.page_max_size);
+
+        let missing = (0..requests_left)
+            .map(|i| first_page_len + i * progress.page_max_size)
+            .filter(|offset| !progress.pages.contains_key(offset))
+            .collect::<Vec<_>>();
+
+        let mut pages = futures::stream::iter(missing)
+            .map(|offset| {
+                let fetcher = &fetcher;
+                async move { (offset, fetcher.fetch_page(term, offset).await) }
+            })
+            .buffer_unordered(options.concurrency.max(1));
+
+        let mut failures = Vec::new();
+        while let Some((offset, res)) = pages.next().await {
+            match res {
+                Result::Ok(res) => {
+                    progress.pages.insert(offset, res.data);
+                    progress.save(term)?;
+                }
+                Err(e) => failures.push(format!("offset {offset}: {e:#}")),
+            }
         }
+        drop(pages);
 
-        if res.total_count != u32::try_from(sections.len())? {
+        if !failures.is_empty() {
             bail!(
-                "expected to fetch {} sections, but actually got {}",
-                res.total_count,
-                sections.len()
+                "{} of {} pages failed, progress saved for the next run:\n{}",
+                failures.len(),
+                requests_left + 1,
+                failures.join("\n")
             );
         }
 
+        let total_count = progress.total_count;
+        let pages_fetched = progress.pages.len();
+        let sections = progress.finish(term)?;
+        let fetched = u32::try_from(sections.len())?;
+
         let mut courses: HashMap<(String, String), super::Course> = HashMap::new();
 
         for section in sections {
@@ -680,55 +1189,79 @@ pub mod scrape {
         let mut courses = courses.into_values().collect::<Vec<_>>();
         courses.sort_by_cached_key(|k| (k.subject_code.to_string(), k.course_code.to_string()));
 
-        Ok(courses)
-    }
+        let stats = FetchStats {
+            expected_sections: total_count,
+            fetched_sections: fetched,
+            pages: pages_fetched,
+            resumed_pages,
+            retries: fetcher.retries.load(atomic::Ordering::Relaxed),
+        };
 
-    #[instrument(skip(client, term))]
-    async fn fetch_sections_partial(
-        client: Client,
-        term: Term,
-        offset: u32,
-    ) -> Result<SectionResults> {
-        debug!("fetching offset {}", offset);
-        let text = client
-            .get(format!(
-                "{}/searchResults/searchResults?txt_term={}&pageOffset={}&pageMaxSize=10000",
-                URL_PREFIX, term, offset
-            ))
-            .send()
-            .await?
-            .text()
-            .await?;
-        match serde_json::from_str::<SectionResults>(&text) {
-            Result::Ok(results) => Ok(results),
-            Result::Err(e) => {
-                let line = text
-                    .lines()
-                    .nth(e.line() - 1)
-                    .with_context(|| anyhow!("can't find line for error: {}", e))?;
-                bail!("line: {}\nerr: {}", line, e);
-            }
-        }
+        Ok((courses, stats))
     }
 
-    #[instrument()]
-    pub async fn fetch_terms() -> Result<Vec<Term>> {
+    #[instrument(skip(options, limiter))]
+    pub async fn fetch_terms(
+        options: &ScrapeOptions,
+        limiter: Arc<RateLimiter>,
+    ) -> Result<Vec<Term>> {
         #[derive(Deserialize)]
         struct TermResult {
             code: String,
         }
 
-        Ok(Client::new()
-            .get(format!(
-                "{}/classSearch/getTerms?searchTerm=&offset=1&max=10000",
-                URL_PREFIX
-            ))
-            .send()
-            .await?
-            .json::<Vec<TermResult>>()
+        let fetcher = Fetcher::new(options, limiter)?;
+        let url = Url::parse(&format!(
+            "{}/classSearch/getTerms?searchTerm=&offset=1&max=10000",
+            URL_PREFIX
+        ))?;
+
+        fetcher
+            .with_retries("fetching terms", || async {
+                let text = fetcher.get_text(&url).await?;
+                serde_json::from_str::<Vec<TermResult>>(&text)
+                    .map_err(|e| FetchError::Transient(e.into()))
+            })
             .await?
             .into_iter()
             .map(|t| t.code.parse::<Term>())
-            .collect::<Result<Vec<_>>>()?)
+            .collect::<Result<Vec<_>>>()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn backoff_doubles_up_to_max() {
+            let options = ScrapeOptions {
+                initial_backoff: Duration::from_secs(1),
+                max_backoff: Duration::from_secs(5),
+                ..Default::default()
+            };
+            let delays = (0..5)
+                .map(|i| backoff(&options, i).as_secs())
+                .collect::<Vec<_>>();
+            assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+        }
+
+        #[test]
+        fn pages_that_dont_add_up_are_fetched_again() {
+            let term: Term = "199001".parse().unwrap();
+            let max_age = Duration::from_secs(60);
+            Progress {
+                started_at: Timestamp::now().as_second(),
+                total_count: 100,
+                page_max_size: 10,
+                pages: BTreeMap::from([(0, Vec::new())]),
+            }
+            .save(term)
+            .unwrap();
+
+            let resumed = Progress::load(term, max_age).expect("fresh progress is resumed");
+            assert!(resumed.finish(term).is_err());
+            // so the next run starts over instead of failing the same way
+            assert!(Progress::load(term, max_age).is_none());
+        }
     }
 }
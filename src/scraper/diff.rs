@@ -0,0 +1,523 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use anyhow::Result;
+use jiff::Timestamp;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use super::{Course, MeetingTime, Section, ThinCourse};
+
+/// One difference between two scrapes of the same term
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    CourseAdded {
+        course: ThinCourse,
+        title: String,
+    },
+    CourseRemoved {
+        course: ThinCourse,
+        title: String,
+    },
+    SectionAdded {
+        course: ThinCourse,
+        crn: u64,
+        sequence_code: String,
+    },
+    SectionCancelled {
+        course: ThinCourse,
+        crn: u64,
+        sequence_code: String,
+    },
+    TimeChanged {
+        course: ThinCourse,
+        crn: u64,
+        sequence_code: String,
+        before: String,
+        after: String,
+    },
+    RoomChanged {
+        course: ThinCourse,
+        crn: u64,
+        sequence_code: String,
+        before: String,
+        after: String,
+    },
+    CapacityChanged {
+        course: ThinCourse,
+        crn: u64,
+        sequence_code: String,
+        before: u32,
+        after: u32,
+    },
+}
+
+impl Change {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Change::CourseAdded { .. } => "course_added",
+            Change::CourseRemoved { .. } => "course_removed",
+            Change::SectionAdded { .. } => "section_added",
+            Change::SectionCancelled { .. } => "section_cancelled",
+            Change::TimeChanged { .. } => "time_changed",
+            Change::RoomChanged { .. } => "room_changed",
+            Change::CapacityChanged { .. } => "capacity_changed",
+        }
+    }
+
+    pub fn course(&self) -> &ThinCourse {
+        match self {
+            Change::CourseAdded { course, .. }
+            | Change::CourseRemoved { course, .. }
+            | Change::SectionAdded { course, .. }
+            | Change::SectionCancelled { course, .. }
+            | Change::TimeChanged { course, .. }
+            | Change::RoomChanged { course, .. }
+            | Change::CapacityChanged { course, .. } => course,
+        }
+    }
+
+    /// Whether this is something a student with the section in their schedule needs to know about
+    pub fn affects_schedule(&self) -> bool {
+        matches!(
+            self,
+            Change::SectionCancelled { .. }
+                | Change::TimeChanged { .. }
+                | Change::RoomChanged { .. }
+        )
+    }
+
+    pub fn crn(&self) -> Option<u64> {
+        match self {
+            Change::CourseAdded { .. } | Change::CourseRemoved { .. } => None,
+            Change::SectionAdded { crn, .. }
+            | Change::SectionCancelled { crn, .. }
+            | Change::TimeChanged { crn, .. }
+            | Change::RoomChanged { crn, .. }
+            | Change::CapacityChanged { crn, .. } => Some(*crn),
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let c = self.course();
+        let course = format!("{} {}", c.subject_code, c.course_code);
+        match self {
+            Change::CourseAdded { title, .. } => write!(f, "{course} ({title}) was added"),
+            Change::CourseRemoved { title, .. } => write!(f, "{course} ({title}) was removed"),
+            Change::SectionAdded {
+                crn, sequence_code, ..
+            } => write!(f, "{course} {sequence_code} (CRN {crn}) was added"),
+            Change::SectionCancelled {
+                crn, sequence_code, ..
+            } => write!(f, "{course} {sequence_code} (CRN {crn}) was cancelled"),
+            Change::TimeChanged {
+                crn,
+                sequence_code,
+                before,
+                after,
+                ..
+            } => write!(
+                f,
+                "{course} {sequence_code} (CRN {crn}) moved from {before} to {after}"
+            ),
+            Change::RoomChanged {
+                crn,
+                sequence_code,
+                before,
+                after,
+                ..
+            } => write!(
+                f,
+                "{course} {sequence_code} (CRN {crn}) changed rooms from {before} to {after}"
+            ),
+            Change::CapacityChanged {
+                crn,
+                sequence_code,
+                before,
+                after,
+                ..
+            } => write!(
+                f,
+                "{course} {sequence_code} (CRN {crn}) capacity changed from {before} to {after}"
+            ),
+        }
+    }
+}
+
+/// A change as recorded in a term database's changelog
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangelogEntry {
+    pub scraped_at: Timestamp,
+    pub change: Change,
+}
+
+fn describe_times(meeting_times: &[MeetingTime]) -> String {
+    let times = meeting_times
+        .iter()
+        .map(|mt| match mt.start_time.zip(mt.end_time) {
+            Some((st, et)) => format!(
+                "{} {}-{} ({} to {})",
+                mt.days,
+                st.strftime("%-I:%M%P"),
+                et.strftime("%-I:%M%P"),
+                mt.start_date,
+                mt.end_date
+            ),
+            None => format!("async ({} to {})", mt.start_date, mt.end_date),
+        })
+        .collect::<BTreeSet<_>>();
+    match times.is_empty() {
+        true => "no meeting times".to_string(),
+        false => times.into_iter().collect::<Vec<_>>().join("; "),
+    }
+}
+
+fn describe_rooms(meeting_times: &[MeetingTime]) -> String {
+    let rooms = meeting_times
+        .iter()
+        .filter_map(|mt| match (&mt.building, &mt.room) {
+            (Some(b), Some(r)) => Some(format!("{b} {r}")),
+            (Some(b), None) => Some(b.clone()),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+    match rooms.is_empty() {
+        true => "no room".to_string(),
+        false => rooms.into_iter().collect::<Vec<_>>().join(", "),
+    }
+}
+
+fn thin(course: &Course) -> ThinCourse {
+    ThinCourse {
+        subject_code: course.subject_code.clone(),
+        course_code: course.course_code.clone(),
+    }
+}
+
+fn diff_section(course: &ThinCourse, old: &Section, new: &Section, changes: &mut Vec<Change>) {
+    let crn = new.crn;
+    let sequence_code = new.sequence_code.clone();
+
+    let (before, after) = (
+        describe_times(&old.meeting_times),
+        describe_times(&new.meeting_times),
+    );
+    if before != after {
+        changes.push(Change::TimeChanged {
+            course: course.clone(),
+            crn,
+            sequence_code: sequence_code.clone(),
+            before,
+            after,
+        });
+    }
+
+    let (before, after) = (
+        describe_rooms(&old.meeting_times),
+        describe_rooms(&new.meeting_times),
+    );
+    if before != after {
+        changes.push(Change::RoomChanged {
+            course: course.clone(),
+            crn,
+            sequence_code: sequence_code.clone(),
+            before,
+            after,
+        });
+    }
+
+    if old.enrollment_capacity != new.enrollment_capacity {
+        changes.push(Change::CapacityChanged {
+            course: course.clone(),
+            crn,
+            sequence_code,
+            before: old.enrollment_capacity,
+            after: new.enrollment_capacity,
+        });
+    }
+}
+
+/// Everything that differs between two scrapes of the same term
+pub fn diff(old: &[Course], new: &[Course]) -> Vec<Change> {
+    let old: BTreeMap<ThinCourse, &Course> = old.iter().map(|c| (thin(c), c)).collect();
+    let new: BTreeMap<ThinCourse, &Course> = new.iter().map(|c| (thin(c), c)).collect();
+
+    let mut changes = Vec::new();
+
+    for (course, old_course) in &old {
+        if new.contains_key(course) {
+            continue;
+        }
+        changes.push(Change::CourseRemoved {
+            course: course.clone(),
+            title: old_course.title.clone(),
+        });
+        // saved schedules only know about CRNs, so spell out every section that went with it
+        for section in &old_course.sections {
+            changes.push(Change::SectionCancelled {
+                course: course.clone(),
+                crn: section.crn,
+                sequence_code: section.sequence_code.clone(),
+            });
+        }
+    }
+
+    for (course, new_course) in &new {
+        let Some(old_course) = old.get(course) else {
+            changes.push(Change::CourseAdded {
+                course: course.clone(),
+                title: new_course.title.clone(),
+            });
+            continue;
+        };
+
+        let old_sections: BTreeMap<u64, &Section> =
+            old_course.sections.iter().map(|s| (s.crn, s)).collect();
+        let new_sections: BTreeMap<u64, &Section> =
+            new_course.sections.iter().map(|s| (s.crn, s)).collect();
+
+        for (crn, section) in &old_sections {
+            if !new_sections.contains_key(crn) {
+                changes.push(Change::SectionCancelled {
+                    course: course.clone(),
+                    crn: *crn,
+                    sequence_code: section.sequence_code.clone(),
+                });
+            }
+        }
+        for (crn, section) in &new_sections {
+            match old_sections.get(crn) {
+                Some(old_section) => diff_section(course, old_section, section, &mut changes),
+                None => changes.push(Change::SectionAdded {
+                    course: course.clone(),
+                    crn: *crn,
+                    sequence_code: section.sequence_code.clone(),
+                }),
+            }
+        }
+    }
+
+    changes
+}
+
+/// `scraped_at` is in milliseconds since the epoch so the changelog sorts by it
+pub(super) fn create_changelog_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS changelog (
+            id INTEGER PRIMARY KEY,
+            scraped_at INTEGER NOT NULL,
+
+            kind TEXT NOT NULL,
+            subject_code TEXT NOT NULL,
+            course_code TEXT NOT NULL,
+            crn INTEGER,
+
+            change TEXT NOT NULL
+        ) STRICT;
+        CREATE INDEX IF NOT EXISTS changelog_crn ON changelog(crn);
+        ",
+    )?;
+    Ok(())
+}
+
+pub(super) fn record_changes(
+    conn: &Connection,
+    scraped_at: Timestamp,
+    changes: &[Change],
+) -> Result<()> {
+    for change in changes {
+        conn.execute(
+            "INSERT INTO changelog (
+                scraped_at, kind, subject_code, course_code, crn, change
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (
+                scraped_at.as_millisecond(),
+                change.kind(),
+                &change.course().subject_code,
+                &change.course().course_code,
+                change.crn(),
+                serde_json::to_string(change)?,
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+/// Copies the changelog of the database `old` into `conn`, which has an empty one
+pub(super) fn carry_changelog(conn: &Connection, old: &Connection) -> Result<()> {
+    if !has_changelog(old)? {
+        return Ok(());
+    }
+
+    let mut rows = old.prepare(
+        "SELECT scraped_at, kind, subject_code, course_code, crn, change FROM changelog
+        ORDER BY id",
+    )?;
+    let mut rows = rows.query(())?;
+    while let Some(row) = rows.next()? {
+        conn.execute(
+            "INSERT INTO changelog (
+                scraped_at, kind, subject_code, course_code, crn, change
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            (
+                row.get::<_, i64>("scraped_at")?,
+                row.get::<_, String>("kind")?,
+                row.get::<_, String>("subject_code")?,
+                row.get::<_, String>("course_code")?,
+                row.get::<_, Option<u64>>("crn")?,
+                row.get::<_, String>("change")?,
+            ),
+        )?;
+    }
+    Ok(())
+}
+
+fn has_changelog(conn: &Connection) -> Result<bool> {
+    Ok(conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'changelog'",
+            (),
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+fn entry_from_row(row: &rusqlite::Row) -> Result<ChangelogEntry> {
+    let change: String = row.get("change")?;
+    Ok(ChangelogEntry {
+        scraped_at: Timestamp::from_millisecond(row.get("scraped_at")?)?,
+        change: serde_json::from_str(&change)?,
+    })
+}
+
+/// Reads the newest `limit` changelog entries, or nothing if the database predates the changelog
+pub fn read_changelog(conn: &Connection, limit: u32) -> Result<Vec<ChangelogEntry>> {
+    if !has_changelog(conn)? {
+        return Ok(Vec::new());
+    }
+
+    let entries = conn
+        .prepare(
+            "SELECT scraped_at, change FROM changelog
+            ORDER BY scraped_at DESC, id ASC
+            LIMIT ?1",
+        )?
+        .query_and_then((limit,), entry_from_row)?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(entries)
+}
+
+/// Reads the changelog entries for any of `crns`, oldest first, recorded strictly after `since`
+pub fn read_changes_for(
+    conn: &Connection,
+    crns: &[u64],
+    since: Option<Timestamp>,
+) -> Result<Vec<ChangelogEntry>> {
+    if crns.is_empty() || !has_changelog(conn)? {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = vec!["?"; crns.len()].join(", ");
+    let entries = conn
+        .prepare(&format!(
+            "SELECT scraped_at, change FROM changelog
+            WHERE crn IN ({placeholders})
+            ORDER BY id"
+        ))?
+        .query_and_then(rusqlite::params_from_iter(crns), entry_from_row)?
+        .filter(|entry| match (entry, since) {
+            (Ok(entry), Some(since)) => entry.scraped_at > since,
+            _ => true,
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::civil::{date, time};
+
+    use super::*;
+    use crate::scraper::Days;
+
+    fn section(crn: u64, hour: i8, room: &str, capacity: u32) -> Section {
+        Section {
+            crn,
+            subject_code: "CSC".to_string(),
+            course_code: "111".to_string(),
+            sequence_code: "A01".to_string(),
+            enrollment: 0,
+            enrollment_capacity: capacity,
+            waitlist: 0,
+            waitlist_capacity: 0,
+            meeting_times: vec![MeetingTime {
+                start_time: Some(time(hour, 30, 0, 0)),
+                end_time: Some(time(hour + 1, 20, 0, 0)),
+                start_date: date(2025, 1, 6),
+                end_date: date(2025, 4, 4),
+                days: Days {
+                    monday: true,
+                    tuesday: false,
+                    wednesday: false,
+                    thursday: true,
+                    friday: false,
+                    saturday: false,
+                    sunday: false,
+                },
+                building: Some("ECS".to_string()),
+                room: Some(room.to_string()),
+            }],
+        }
+    }
+
+    fn course(sections: Vec<Section>) -> Course {
+        Course {
+            subject_code: "CSC".to_string(),
+            course_code: "111".to_string(),
+            title: "Fundamentals of Programming".to_string(),
+            campus: "Main".to_string(),
+            sections,
+        }
+    }
+
+    #[test]
+    fn detects_section_changes() {
+        let old = vec![course(vec![
+            section(1, 10, "125", 100),
+            section(2, 13, "130", 100),
+        ])];
+        let new = vec![course(vec![
+            section(2, 14, "116", 120),
+            section(3, 8, "125", 50),
+        ])];
+
+        let kinds = diff(&old, &new)
+            .iter()
+            .map(|c| (c.kind(), c.crn()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ("section_cancelled", Some(1)),
+                ("time_changed", Some(2)),
+                ("room_changed", Some(2)),
+                ("capacity_changed", Some(2)),
+                ("section_added", Some(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn identical_scrapes_have_no_changes() {
+        let courses = vec![course(vec![section(1, 10, "125", 100)])];
+        assert!(diff(&courses, &courses).is_empty());
+    }
+}
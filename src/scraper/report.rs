@@ -0,0 +1,125 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+use super::{diff::Change, Term};
+
+/// What happened to every term during one run of the scraper
+#[derive(Debug, Default, Serialize)]
+pub struct ScrapeReport {
+    pub terms: Vec<TermReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TermReport {
+    #[serde(serialize_with = "serialize_term")]
+    pub term: Term,
+    #[serde(flatten)]
+    pub outcome: TermOutcome,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TermOutcome {
+    /// Already had an up-to-date database
+    Skipped,
+    Scraped {
+        courses: usize,
+        #[serde(flatten)]
+        stats: FetchStats,
+        /// Differences from the previous database, empty on the first scrape of a term
+        changes: Vec<Change>,
+    },
+    /// The previous database (if any) was left in place
+    Failed { error: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct FetchStats {
+    pub expected_sections: u32,
+    pub fetched_sections: u32,
+    pub pages: usize,
+    /// Pages reused from an earlier, interrupted run
+    pub resumed_pages: usize,
+    pub retries: u32,
+}
+
+fn serialize_term<S: serde::Serializer>(term: &Term, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(term)
+}
+
+impl ScrapeReport {
+    pub fn failures(&self) -> impl Iterator<Item = &TermReport> {
+        self.terms
+            .iter()
+            .filter(|t| matches!(t.outcome, TermOutcome::Failed { .. }))
+    }
+
+    /// Every change found during this run, by term
+    pub fn changes(&self) -> impl Iterator<Item = (Term, &[Change])> {
+        self.terms.iter().filter_map(|t| match &t.outcome {
+            TermOutcome::Scraped { changes, .. } => Some((t.term, changes.as_slice())),
+            _ => None,
+        })
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Emits one structured tracing event per term
+    pub fn log(&self) {
+        for TermReport { term, outcome } in &self.terms {
+            match outcome {
+                TermOutcome::Skipped => info!(%term, status = "skipped"),
+                TermOutcome::Scraped {
+                    courses,
+                    stats,
+                    changes,
+                } => info!(
+                    %term,
+                    status = "scraped",
+                    courses,
+                    sections = stats.fetched_sections,
+                    expected_sections = stats.expected_sections,
+                    pages = stats.pages,
+                    resumed_pages = stats.resumed_pages,
+                    retries = stats.retries,
+                    changes = changes.len(),
+                ),
+                TermOutcome::Failed { error } => error!(%term, status = "failed", error),
+            }
+        }
+        if !self.is_success() {
+            warn!(
+                failed = self.failures().count(),
+                "some terms failed to scrape"
+            );
+        }
+    }
+}
+
+impl Display for ScrapeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for TermReport { term, outcome } in &self.terms {
+            match outcome {
+                TermOutcome::Skipped => writeln!(f, "{term}: skipped")?,
+                TermOutcome::Scraped {
+                    courses,
+                    stats,
+                    changes,
+                } => writeln!(
+                    f,
+                    "{term}: {courses} courses, {}/{} sections, {} retries, {} changes",
+                    stats.fetched_sections,
+                    stats.expected_sections,
+                    stats.retries,
+                    changes.len()
+                )?,
+                TermOutcome::Failed { error } => writeln!(f, "{term}: FAILED: {error}")?,
+            }
+        }
+        Ok(())
+    }
+}